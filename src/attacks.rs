@@ -0,0 +1,280 @@
+use std::sync::OnceLock;
+
+use crate::board_elements::BitBoard;
+use crate::board_elements::PieceColor;
+
+// Attack bitboards of every piece kind on every square, with a1 as bit 0. Sliding pieces use
+// fancy magic bitboards: the blockers on the rays of a piece are multiplied by a magic number,
+// and the top bits of the product index a table of precomputed attack sets. The magic numbers
+// are searched for once, the first time any attack is looked up.
+static ATTACK_TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const KNIGHT_STEPS: [(i32, i32); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+const KING_STEPS: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+pub fn knight_attacks(square: usize) -> BitBoard {
+    BitBoard(tables().knight[square])
+}
+pub fn king_attacks(square: usize) -> BitBoard {
+    BitBoard(tables().king[square])
+}
+// The squares a pawn of the given color on the square captures on
+pub fn pawn_attacks(color: PieceColor, square: usize) -> BitBoard {
+    BitBoard(tables().pawn[color.index()][square])
+}
+pub fn bishop_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+    let tables = tables();
+    BitBoard(tables.sliding[tables.bishop_magics[square].index(occupied.0)])
+}
+pub fn rook_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+    let tables = tables();
+    BitBoard(tables.sliding[tables.rook_magics[square].index(occupied.0)])
+}
+pub fn queen_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+    BitBoard(bishop_attacks(square, occupied).0 | rook_attacks(square, occupied).0)
+}
+// The squares strictly between two squares on the same rank, file or diagonal, empty otherwise
+pub fn between(a: usize, b: usize) -> BitBoard {
+    let (a_bit, b_bit) = (BitBoard(1 << a), BitBoard(1 << b));
+    if rook_attacks(a, BitBoard(0)).0 & b_bit.0 != 0 {
+        BitBoard(rook_attacks(a, b_bit).0 & rook_attacks(b, a_bit).0)
+    } else if bishop_attacks(a, BitBoard(0)).0 & b_bit.0 != 0 {
+        BitBoard(bishop_attacks(a, b_bit).0 & bishop_attacks(b, a_bit).0)
+    } else {
+        BitBoard(0)
+    }
+}
+
+fn tables() -> &'static AttackTables {
+    ATTACK_TABLES.get_or_init(AttackTables::generate)
+}
+
+struct AttackTables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    bishop_magics: [Magic; 64],
+    rook_magics: [Magic; 64],
+    // The attack sets of both slider kinds on every square, each magic owning a slice of it
+    sliding: Vec<u64>,
+}
+impl AttackTables {
+    fn generate() -> AttackTables {
+        let mut knight = [0; 64];
+        let mut king = [0; 64];
+        let mut pawn = [[0; 64]; 2];
+        for square in 0..64 {
+            knight[square] = step_attacks(square, &KNIGHT_STEPS);
+            king[square] = step_attacks(square, &KING_STEPS);
+            pawn[PieceColor::White.index()][square] = step_attacks(square, &[(1, -1), (1, 1)]);
+            pawn[PieceColor::Black.index()][square] = step_attacks(square, &[(-1, -1), (-1, 1)]);
+        }
+        let mut sliding = Vec::new();
+        let bishop_magics =
+            std::array::from_fn(|square| Magic::find(square, &BISHOP_DIRECTIONS, &mut sliding));
+        let rook_magics =
+            std::array::from_fn(|square| Magic::find(square, &ROOK_DIRECTIONS, &mut sliding));
+        AttackTables {
+            knight,
+            king,
+            pawn,
+            bishop_magics,
+            rook_magics,
+            sliding,
+        }
+    }
+}
+
+struct Magic {
+    // The squares whose occupancy changes the attack set. The last square of every ray never
+    // blocks anything behind it, so it is left out.
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+impl Magic {
+    fn index(&self, occupied: u64) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+    // Tries random sparse numbers until one maps every blocker set on the mask to a slot of the
+    // table without two different attack sets colliding, then appends the table to sliding
+    fn find(square: usize, directions: &[(i32, i32)], sliding: &mut Vec<u64>) -> Magic {
+        let (rank, file) = (square / 8, square % 8);
+        let edges =
+            ((RANK_1 | RANK_8) & !(RANK_1 << (8 * rank))) | ((FILE_A | FILE_H) & !(FILE_A << file));
+        let mask = ray_attacks(square, 0, directions) & !edges;
+        let bits = mask.count_ones();
+
+        // Every subset of the mask, enumerated with the Carry-Rippler trick
+        let mut occupancies = Vec::with_capacity(1 << bits);
+        let mut subset: u64 = 0;
+        loop {
+            occupancies.push((subset, ray_attacks(square, subset, directions)));
+            subset = subset.wrapping_sub(mask) & mask;
+            if subset == 0 {
+                break;
+            }
+        }
+
+        let mut table = vec![0; 1 << bits];
+        // The attempt that last wrote each slot, so the table need not be cleared between tries
+        let mut written_by = vec![0; 1 << bits];
+        let mut attempt = 0;
+        let mut random = Xorshift(MAGIC_SEEDS[rank]);
+        loop {
+            let magic = random.sparse();
+            if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+                continue;
+            }
+            attempt += 1;
+            let candidate = Magic {
+                mask,
+                magic,
+                shift: 64 - bits,
+                offset: 0,
+            };
+            let fits = occupancies.iter().all(|&(occupied, attacks)| {
+                let index = candidate.index(occupied);
+                if written_by[index] != attempt {
+                    written_by[index] = attempt;
+                    table[index] = attacks;
+                    true
+                } else {
+                    table[index] == attacks
+                }
+            });
+            if fits {
+                let offset = sliding.len();
+                sliding.extend(table);
+                return Magic {
+                    offset,
+                    ..candidate
+                };
+            }
+        }
+    }
+}
+
+// Seeds for each rank of the board that are known to lead to magic numbers within a few tries, so
+// finding all of them takes next to no time
+const MAGIC_SEEDS: [u64; 8] = [728, 10316, 55013, 32803, 12281, 15100, 16645, 255];
+const RANK_1: u64 = 0xFF;
+const RANK_8: u64 = RANK_1 << 56;
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+fn step_attacks(square: usize, steps: &[(i32, i32)]) -> u64 {
+    let (rank, file) = ((square / 8) as i32, (square % 8) as i32);
+    steps
+        .iter()
+        .map(|(rank_step, file_step)| (rank + rank_step, file + file_step))
+        .filter(|&(rank, file)| (0..8).contains(&rank) && (0..8).contains(&file))
+        .fold(0, |attacks, (rank, file)| attacks | 1 << (rank * 8 + file))
+}
+// The attacks of a slider found by walking its rays up to and including the first blocker
+fn ray_attacks(square: usize, occupied: u64, directions: &[(i32, i32)]) -> u64 {
+    let mut attacks = 0;
+    for (rank_step, file_step) in directions {
+        let (mut rank, mut file) = ((square / 8) as i32, (square % 8) as i32);
+        loop {
+            rank += rank_step;
+            file += file_step;
+            if !(0..8).contains(&rank) || !(0..8).contains(&file) {
+                break;
+            }
+            let bit = 1 << (rank * 8 + file);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+        }
+    }
+    attacks
+}
+
+struct Xorshift(u64);
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    // Magic numbers with few bits set are found much faster
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_elements::PieceColor::*;
+
+    #[test]
+    fn magic_lookups_match_walking_the_rays() {
+        let mut random = Xorshift(42);
+        for square in 0..64 {
+            for _ in 0..200 {
+                let occupied = random.next() & random.next();
+                assert_eq!(
+                    bishop_attacks(square, BitBoard(occupied)).0,
+                    ray_attacks(square, occupied, &BISHOP_DIRECTIONS)
+                );
+                assert_eq!(
+                    rook_attacks(square, BitBoard(occupied)).0,
+                    ray_attacks(square, occupied, &ROOK_DIRECTIONS)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn leaper_attacks_stay_on_the_board() {
+        // a1 and h8
+        assert_eq!(knight_attacks(0).0, 1 << 10 | 1 << 17);
+        assert_eq!(king_attacks(63).0, 1 << 62 | 1 << 55 | 1 << 54);
+        // e4
+        assert_eq!(knight_attacks(28).0.count_ones(), 8);
+        assert_eq!(pawn_attacks(White, 28).0, 1 << 35 | 1 << 37);
+        assert_eq!(pawn_attacks(Black, 24).0, 1 << 17);
+    }
+
+    #[test]
+    fn between_only_covers_aligned_squares() {
+        // a1 to d4, a1 to a4, and a1 to b3 which share no line
+        assert_eq!(between(0, 27).0, 1 << 9 | 1 << 18);
+        assert_eq!(between(0, 24).0, 1 << 8 | 1 << 16);
+        assert_eq!(between(0, 17).0, 0);
+        assert_eq!(between(0, 1).0, 0);
+    }
+
+    #[test]
+    fn sliders_stop_at_the_first_blocker() {
+        // A rook on a1 with a blocker on a4 and the rest of the first rank empty
+        let attacks = rook_attacks(0, BitBoard(1 << 24 | 1 << 40));
+        assert_eq!(attacks.0, 1 << 8 | 1 << 16 | 1 << 24 | 0xFE);
+        assert_eq!(queen_attacks(27, BitBoard(0)).0.count_ones(), 27);
+    }
+}
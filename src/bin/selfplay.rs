@@ -0,0 +1,112 @@
+use mango_chess::{
+    pv_to_uci, search, search_comment, BoardState, OpeningBook, PgnGame, DEFAULT_DEPTH, ENGINE_NAME,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "Usage: selfplay [--pgn <file>] [--book <polyglot book>]";
+
+// Plays a game against itself, printing every move. With --pgn the game is also saved, and with
+// --book the opening moves are picked from a Polyglot book.
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mut pgn_path = None;
+    let mut book = None;
+    let mut options = args[1..].iter();
+    while let Some(option) = options.next() {
+        let Some(value) = options.next() else {
+            exit_with_error(USAGE);
+        };
+        match option.as_str() {
+            "--pgn" => pgn_path = Some(value.clone()),
+            "--book" => match OpeningBook::open(value) {
+                Ok(opening_book) => book = Some(opening_book),
+                Err(error) => exit_with_error(&format!("Failed to open book {value}: {error}")),
+            },
+            _ => exit_with_error(USAGE),
+        }
+    }
+
+    let mut board_state = BoardState::new_game();
+    let mut game = PgnGame::new(board_state.clone());
+    game.set_tag("Event", "mango_chess self-play");
+    game.set_tag("Date", &today());
+    game.set_tag("White", ENGINE_NAME);
+    game.set_tag("Black", ENGINE_NAME);
+    let mut moves = 0;
+    let game_result = loop {
+        println!("{}", board_state.board);
+        if let Some(game_result) = board_state.get_game_winner() {
+            println!("{game_result}");
+            break game_result;
+        }
+        moves += 1;
+        if let Some(book_move) = book
+            .as_ref()
+            .and_then(|book| book.choose_move(&board_state))
+        {
+            println!(
+                "Book move {}. Total moves: {}",
+                board_state.move_to_san(book_move),
+                moves
+            );
+            game.push_move(book_move, Some("book".to_string()));
+            board_state.make_move(book_move);
+            continue;
+        }
+        let result = search(&board_state, DEFAULT_DEPTH);
+        let best_move = result.best_move.unwrap();
+        let absolute_eval = result.eval * board_state.to_move.signum();
+        println!(
+            "Evaluation is {} with the move {}. Total moves: {}",
+            absolute_eval,
+            board_state.move_to_san(best_move),
+            moves
+        );
+        println!(
+            "Expected line: {} (depth {}/{}, {} nodes, {} nps)",
+            pv_to_uci(&result.pv),
+            result.depth,
+            result.seldepth,
+            result.nodes,
+            result.nps()
+        );
+        game.push_move(best_move, Some(search_comment(&result)));
+        board_state.make_move(best_move);
+    };
+    game.set_result(game_result.into());
+
+    if let Some(path) = pgn_path {
+        match std::fs::write(&path, game.to_string()) {
+            Ok(()) => println!("Saved the game to {path}"),
+            Err(error) => exit_with_error(&format!("Failed to write {path}: {error}")),
+        }
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1);
+}
+
+// Today's date in the YYYY.MM.DD format of the PGN Date tag
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() / 86400)
+        .unwrap_or(0) as i64;
+    // Converts days since 1970-01-01 to a civil date, counting in 400 year eras starting in March
+    let shifted_days = days + 719468;
+    let era = shifted_days.div_euclid(146097);
+    let day_of_era = shifted_days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
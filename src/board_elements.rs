@@ -65,6 +65,40 @@ impl PieceColor {
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Winner(PieceColor),
+    Draw(DrawReason),
+}
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::Winner(game_winner) => {
+                write!(f, "Checkmate! {game_winner} takes the crown.")
+            }
+            GameResult::Draw(reason) => write!(f, "The game ends in a draw by {reason}."),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    Stalemate,
+    Repetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+    Agreement,
+}
+impl std::fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::Stalemate => write!(f, "stalemate"),
+            DrawReason::Repetition => write!(f, "threefold repetition"),
+            DrawReason::FiftyMoveRule => write!(f, "the fifty move rule"),
+            DrawReason::InsufficientMaterial => write!(f, "insufficient material"),
+            DrawReason::Agreement => write!(f, "agreement"),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PieceKind {
     Pawn,
     Knight,
@@ -176,6 +210,23 @@ impl std::fmt::Display for Piece {
         write!(f, "{symbol}")
     }
 }
+// The FEN letter of the piece, uppercase for white and lowercase for black
+impl From<Piece> for char {
+    fn from(piece: Piece) -> char {
+        let letter = match piece.kind {
+            Pawn => 'p',
+            Knight => 'n',
+            Bishop => 'b',
+            Rook => 'r',
+            Queen => 'q',
+            King => 'k',
+        };
+        match piece.color {
+            White => letter.to_ascii_uppercase(),
+            Black => letter,
+        }
+    }
+}
 impl Piece {
     pub fn pawn(color: PieceColor) -> Piece {
         Piece { color, kind: Pawn }
@@ -203,7 +254,7 @@ impl Piece {
     }
     #[inline]
     pub fn index(self) -> usize {
-        self.kind.index() * self.color.index()
+        self.color.index() * 6 + self.kind.index()
     }
     pub fn value(self) -> i32 {
         match self.kind {
@@ -324,6 +375,9 @@ impl BitBoard {
         let mask = 1 << index;
         self.0 |= mask;
     }
+    pub fn contains(self, square: ChessCell) -> bool {
+        self.0 & (1 << square.as_index()) != 0
+    }
 }
 impl std::fmt::Display for BitBoard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -336,7 +390,7 @@ impl Iterator for BitBoard {
         if self.0 == 0 {
             return None;
         }
-        let position = self.0.leading_zeros() as usize;
+        let position = self.0.trailing_zeros() as usize;
         let cell = ChessCell::from_index(position);
         self.0 ^= 1 << position;
         Some(cell)
@@ -389,11 +443,10 @@ impl FromStr for ChessCell {
             'h' => 7,
             _ => return Err("Failed to parse ChessCell from string: Invalid file"),
         };
-        let row = row.to_digit(10);
-        if row.is_none() {
-            return Err("Failed to parse ChessCell from string: rank was not a valid number");
-        }
-        let rank = row.unwrap() as usize;
+        let rank = match row.to_digit(10) {
+            Some(rank @ 1..=8) => rank as usize,
+            _ => return Err("Failed to parse ChessCell from string: Rank was not between 1 and 8"),
+        };
         Ok(ChessCell(rank + 1, file + BOARD_START))
     }
 }
@@ -421,6 +474,18 @@ pub struct ChessMove {
     pub dest: ChessCell,
     pub promotion: Option<Piece>,
 }
+impl ChessMove {
+    // Formats the move in the long algebraic notation used by UCI, e.g. e2e4, e7e8q or e1g1
+    pub fn to_uci(self) -> String {
+        match self.promotion {
+            Some(piece) => {
+                let promotion = char::from(piece).to_ascii_lowercase();
+                format!("{}{}{}", self.start, self.dest, promotion)
+            }
+            None => format!("{}{}", self.start, self.dest),
+        }
+    }
+}
 impl std::fmt::Display for ChessMove {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}->{}", self.start, self.dest)
@@ -495,4 +560,14 @@ mod tests {
     fn converting_g1_to_string_leads_to_correct_string() {
         assert_eq!(G1.to_string(), "g1")
     }
+
+    #[test]
+    fn moves_are_formatted_in_uci_notation() {
+        assert_eq!(ChessMove::from((E2, E4)).to_uci(), "e2e4");
+        assert_eq!(ChessMove::from((E1, G1)).to_uci(), "e1g1");
+        assert_eq!(
+            ChessMove::from((E2, E1, Piece::knight(Black))).to_uci(),
+            "e2e1n"
+        );
+    }
 }
@@ -1,26 +1,38 @@
+use std::str::FromStr;
+
+use crate::attacks::bishop_attacks;
+use crate::attacks::king_attacks;
+use crate::attacks::knight_attacks;
+use crate::attacks::pawn_attacks;
+use crate::attacks::rook_attacks;
 use crate::board_elements::BitBoard;
 use crate::board_elements::CastlingRights;
 use crate::board_elements::CastlingType;
 use crate::board_elements::ChessCell;
 use crate::board_elements::ChessMove;
+use crate::board_elements::DrawReason;
+use crate::board_elements::GameResult;
 use crate::board_elements::Piece;
 use crate::board_elements::PieceColor;
 use crate::board_elements::PieceColor::*;
+use crate::board_elements::PieceKind;
 use crate::board_elements::PieceKind::*;
 use crate::board_elements::Square;
 use crate::chess_board::ChessBoard;
 use crate::constants::*;
-use crate::evaluation::evaluate;
+use crate::endgame::evaluate_endgame;
 use crate::evaluation::evaluate_piece;
+use crate::evaluation::piece_square_eval;
 use crate::fen;
 use crate::fen::castling_rights_from_fen;
 use crate::fen::en_passant_square_from_fen;
+use crate::fen::FenError;
 use crate::move_generation::generate_moves;
-use crate::move_generation::generate_pseudo_moves_for_piece;
 use crate::move_scoring::positional_value_delta;
-use crate::ray_attacks::*;
+use crate::san;
+use crate::san::SanError;
+use crate::zobrist_hashing::PolyglotOracle;
 use crate::zobrist_hashing::ZobristOracle;
-use crate::GameResult;
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct BoardState {
@@ -30,6 +42,8 @@ pub struct BoardState {
     pub white_bitboard: BitBoard,
     // Keeps track of all the black pieces
     pub black_bitboard: BitBoard,
+    // Keeps track of the pieces of each kind of both colors, indexed by PieceKind::index
+    pub piece_bitboards: [BitBoard; 6],
     pub white_king_location: ChessCell,
     pub black_king_location: ChessCell,
     pub last_move: Option<ChessMove>,
@@ -37,6 +51,24 @@ pub struct BoardState {
     pub eval: i32,
     pub castling_rights: CastlingRights,
     pub zobrist_key: u64,
+    // Number of moves since the last capture or pawn move
+    pub halfmove_clock: u32,
+    // Starts at 1 and is incremented after every black move
+    pub fullmove_number: u32,
+    // The zobrist keys of every earlier position in the game, oldest first
+    zobrist_history: Vec<u64>,
+    undo_stack: Vec<UndoRecord>,
+}
+// Everything make_move overwrites that unmake_move needs to restore the previous position
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct UndoRecord {
+    mov: ChessMove,
+    captured_piece: Option<Piece>,
+    castling_rights: CastlingRights,
+    en_passant: Option<ChessCell>,
+    eval: i32,
+    halfmove_clock: u32,
+    last_move: Option<ChessMove>,
 }
 impl BoardState {
     #[inline]
@@ -72,7 +104,7 @@ impl BoardState {
         self.eval += eval_increment * self.to_move.signum()
     }
     pub fn pov_eval(&self) -> i32 {
-        self.eval * self.to_move.signum()
+        evaluate_endgame(self).unwrap_or(self.eval * self.to_move.signum())
     }
     #[inline]
     fn update_king_position(&mut self, dest: ChessCell) {
@@ -84,8 +116,7 @@ impl BoardState {
     pub fn kill_en_passant_piece(&mut self, square: ChessCell, eval_increment: &mut i32) {
         let trespasser = self.board.square(square).piece().unwrap();
         *eval_increment += evaluate_piece(trespasser, square.as_index());
-        *self.board.square_mut(square) = Square::Empty;
-        self.remove_from_bitboard(square);
+        self.clear_square(square);
     }
     // returns captured piece, if any
     pub fn move_piece(&mut self, mov: ChessMove, eval_increment: &mut i32) -> Option<Piece> {
@@ -95,22 +126,30 @@ impl BoardState {
             *eval_increment += evaluate_piece(captured_piece, mov.dest.as_index());
         }
 
-        *self.board.square_mut(mov.start) = Square::Empty;
+        self.clear_square(mov.start);
+        self.clear_square(mov.dest);
 
         if let Some(promoted_piece) = mov.promotion {
-            *self.board.square_mut(mov.dest) = Square::Full(promoted_piece);
+            self.put_piece(mov.dest, promoted_piece);
             *eval_increment += evaluate_piece(promoted_piece, mov.dest.as_index())
                 - evaluate_piece(moving_piece, mov.start.as_index());
         } else {
-            *self.board.square_mut(mov.dest) = Square::Full(moving_piece);
+            self.put_piece(mov.dest, moving_piece);
             *eval_increment += positional_value_delta(moving_piece, mov);
         }
 
-        self.update_bitboards(mov);
-
         captured_piece
     }
     pub fn make_move(&mut self, mov: ChessMove) {
+        let mut undo_record = UndoRecord {
+            mov,
+            captured_piece: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            eval: self.eval,
+            halfmove_clock: self.halfmove_clock,
+            last_move: self.last_move,
+        };
         // Used for zobrist key incremental updates
         let en_passant_file_before = self.en_passant.map(|cell| cell.1);
         let castling_rights_before = self.castling_rights;
@@ -161,6 +200,18 @@ impl BoardState {
 
         let captured_piece = self.move_piece(mov, &mut eval_increment);
 
+        if moving_piece.kind == Pawn || captured_piece.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if self.to_move == Black {
+            self.fullmove_number += 1;
+        }
+        undo_record.captured_piece = captured_piece;
+        self.undo_stack.push(undo_record);
+        self.zobrist_history.push(self.zobrist_key);
+
         self.increment_eval(eval_increment);
 
         self.swap_to_move();
@@ -177,65 +228,171 @@ impl BoardState {
             removed_castling_rights,
         );
     }
-    fn resolve_castling(&mut self, mov: ChessMove, eval_increment: &mut i32) {
-        let (rook_start, rook_dest) = match mov.dest {
-            G1 => (H1, F1),
-            C1 => (A1, D1),
-            G8 => (H8, F8),
-            C8 => (A8, D8),
-            _ => unreachable!(),
-        };
-        let rook_move = ChessMove {
-            start: rook_start,
-            dest: rook_dest,
-            promotion: None,
-        };
-        self.move_piece(rook_move, eval_increment);
-    }
-    fn remove_from_bitboard(&mut self, square: ChessCell) {
-        let opposing_player_bitboard = match self.to_move {
-            White => &mut self.black_bitboard,
-            Black => &mut self.white_bitboard,
-        };
-        opposing_player_bitboard.remove_piece(square.as_index());
-    }
-    fn update_bitboards(&mut self, mov: ChessMove) {
-        let (current_player_bitboard, opposing_player_bitboard) = match self.to_move {
-            White => (&mut self.white_bitboard, &mut self.black_bitboard),
-            Black => (&mut self.black_bitboard, &mut self.white_bitboard),
+    // Takes back the last move made with make_move, restoring the exact previous position.
+    pub fn unmake_move(&mut self) {
+        let undo_record = self
+            .undo_stack
+            .pop()
+            .expect("Tried to unmake a move when no move has been made");
+        let ChessMove { start, dest, .. } = undo_record.mov;
+
+        self.swap_to_move();
+        if self.to_move == Black {
+            self.fullmove_number -= 1;
+        }
+
+        let moved_piece = self.board.square(dest).piece().unwrap();
+        let original_piece = match undo_record.mov.promotion {
+            Some(_) => Piece::pawn(self.to_move),
+            None => moved_piece,
         };
 
-        current_player_bitboard.remove_piece(mov.start.as_index());
-        current_player_bitboard.add_piece(mov.dest.as_index());
+        self.clear_square(dest);
+        self.put_piece(start, original_piece);
+        if let Some(captured_piece) = undo_record.captured_piece {
+            self.put_piece(dest, captured_piece);
+        }
+
+        if original_piece.kind == King {
+            self.update_king_position(start);
+            if start.1.abs_diff(dest.1) == 2 {
+                let rook_move = castling_rook_move(dest);
+                let rook = self.board.square(rook_move.dest).piece().unwrap();
+                self.clear_square(rook_move.dest);
+                self.put_piece(rook_move.start, rook);
+            }
+        }
 
-        opposing_player_bitboard.remove_piece(mov.dest.as_index());
+        if original_piece.kind == Pawn
+            && Some(dest) == undo_record.en_passant
+            && start.1.abs_diff(dest.1) == 1
+        {
+            let en_passant_capture = ChessCell(start.0, dest.1);
+            self.put_piece(en_passant_capture, Piece::pawn(self.to_move.opposite()));
+        }
+
+        self.castling_rights = undo_record.castling_rights;
+        self.en_passant = undo_record.en_passant;
+        self.eval = undo_record.eval;
+        self.zobrist_key = self.zobrist_history.pop().unwrap();
+        self.halfmove_clock = undo_record.halfmove_clock;
+        self.last_move = undo_record.last_move;
+    }
+    fn resolve_castling(&mut self, mov: ChessMove, eval_increment: &mut i32) {
+        self.move_piece(castling_rook_move(mov.dest), eval_increment);
+    }
+    // Places the piece on an empty square, keeping the bitboards in sync with the board
+    fn put_piece(&mut self, square: ChessCell, piece: Piece) {
+        let index = square.as_index();
+        match piece.color {
+            White => self.white_bitboard.add_piece(index),
+            Black => self.black_bitboard.add_piece(index),
+        }
+        self.piece_bitboards[piece.kind.index()].add_piece(index);
+        *self.board.square_mut(square) = Square::Full(piece);
+    }
+    // Removes the piece on the square, if any, from the board and the bitboards
+    fn clear_square(&mut self, square: ChessCell) {
+        if let Some(piece) = self.board.square(square).piece() {
+            let index = square.as_index();
+            match piece.color {
+                White => self.white_bitboard.remove_piece(index),
+                Black => self.black_bitboard.remove_piece(index),
+            }
+            self.piece_bitboards[piece.kind.index()].remove_piece(index);
+        }
+        *self.board.square_mut(square) = Square::Empty;
+    }
+    pub fn color_bitboard(&self, color: PieceColor) -> BitBoard {
+        match color {
+            White => self.white_bitboard,
+            Black => self.black_bitboard,
+        }
+    }
+    pub fn occupied(&self) -> BitBoard {
+        BitBoard(self.white_bitboard.0 | self.black_bitboard.0)
+    }
+    // The pieces of the given color and kind
+    pub fn pieces(&self, color: PieceColor, kind: PieceKind) -> BitBoard {
+        BitBoard(self.color_bitboard(color).0 & self.piece_bitboards[kind.index()].0)
     }
     pub fn is_terminal(&self) -> bool {
         let moves = generate_moves(self);
 
         moves.is_empty()
     }
+    pub fn has_legal_move(&self) -> bool {
+        !self.is_terminal()
+    }
 
     pub fn terminal_eval(&self) -> i32 {
-        match self.square_is_attacked(self.king_location_of(self.to_move), self.to_move.opposite())
-        {
-            true => i32::MIN / 2,
+        match self.is_in_check() {
+            true => -MATE_SCORE,
             false => 0,
         }
     }
-    pub fn get_game_winner(&self) -> GameResult {
-        let potentially_mated_king = self.king_location_of(self.to_move);
-        match self.square_is_attacked(potentially_mated_king, self.to_move.opposite()) {
-            true => GameResult::Winner(self.to_move.opposite()),
-            false => GameResult::Draw,
+    // Returns the result if the game is over by the rules, or None if play continues.
+    pub fn get_game_winner(&self) -> Option<GameResult> {
+        if self.is_terminal() {
+            return match self.is_in_check() {
+                true => Some(GameResult::Winner(self.to_move.opposite())),
+                false => Some(GameResult::Draw(DrawReason::Stalemate)),
+            };
+        }
+        if self.is_insufficient_material() {
+            return Some(GameResult::Draw(DrawReason::InsufficientMaterial));
+        }
+        if self.is_fifty_move_draw() {
+            return Some(GameResult::Draw(DrawReason::FiftyMoveRule));
         }
+        if self.is_threefold_repetition() {
+            return Some(GameResult::Draw(DrawReason::Repetition));
+        }
+        None
+    }
+    // True when neither side can possibly checkmate: only kings and at most one minor piece are
+    // left, or every remaining minor piece is a bishop on the same square color.
+    pub fn is_insufficient_material(&self) -> bool {
+        let bitboard = |kind: PieceKind| self.piece_bitboards[kind.index()].0;
+        if bitboard(Pawn) | bitboard(Rook) | bitboard(Queen) != 0 {
+            return false;
+        }
+        let knights = bitboard(Knight).count_ones();
+        let dark_bishops = (bitboard(Bishop) & DARK_SQUARES.0).count_ones();
+        let light_bishops = (bitboard(Bishop) & !DARK_SQUARES.0).count_ones();
+        match (knights, dark_bishops + light_bishops) {
+            (0, 0) | (1, 0) | (0, 1) => true,
+            (0, _) => dark_bishops == 0 || light_bishops == 0,
+            _ => false,
+        }
+    }
+    // Returns the number of earlier occurrences of the current position. Only positions since the
+    // last capture or pawn move with the same side to move can be identical.
+    pub fn repetition_count(&self) -> usize {
+        self.zobrist_history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|&&zobrist_key| zobrist_key == self.zobrist_key)
+            .count()
+    }
+    // The search treats a single repetition as a draw, since whatever could be achieved from the
+    // position could already have been achieved the first time it occurred.
+    pub fn is_repetition(&self) -> bool {
+        self.repetition_count() >= 1
+    }
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetition_count() >= 2
+    }
+    // A checkmate delivered on the hundredth halfmove still wins, so this must be checked after mate
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= 100
     }
     pub fn get_piece_positions(&self, color: PieceColor) -> Vec<ChessCell> {
         let mut piece_positions = Vec::new();
-        let mut bitboard = match color {
-            White => self.white_bitboard,
-            Black => self.black_bitboard,
-        };
+        let mut bitboard = self.color_bitboard(color);
         while bitboard.0 != 0 {
             let position = bitboard.0.trailing_zeros();
             let cell = ChessCell::from_index(position as usize);
@@ -284,6 +441,50 @@ impl BoardState {
         self.zobrist_key = zobrist_key;
     }
 
+    // The key of the position in Polyglot opening books. Unlike the zobrist key, the en passant
+    // file only counts when a pawn of the side to move can actually capture en passant.
+    pub fn polyglot_key(&self) -> u64 {
+        let mut polyglot_key: u64 = 0;
+
+        let all_piece_positions = self
+            .get_piece_positions(White)
+            .into_iter()
+            .chain(self.get_piece_positions(Black));
+
+        for piece_position in all_piece_positions {
+            let piece = self.board.square(piece_position).piece().unwrap();
+            polyglot_key ^= PolyglotOracle::piece_bitstring(piece, piece_position.as_index());
+        }
+
+        let all_castling_rights = self
+            .available_castling_types(White)
+            .into_iter()
+            .chain(self.available_castling_types(Black));
+
+        for castling_right in all_castling_rights {
+            polyglot_key ^= PolyglotOracle::castling_right_bitstring(castling_right);
+        }
+
+        if let Some(en_passant) = self.en_passant {
+            let capturing_pawn = Piece::pawn(self.to_move);
+            let pawn_rank = self.to_move.en_passant_attacking_rank();
+            let can_capture = [en_passant.1 - 1, en_passant.1 + 1]
+                .into_iter()
+                .any(|file| {
+                    self.board.square(ChessCell(pawn_rank, file)).piece() == Some(capturing_pawn)
+                });
+            if can_capture {
+                polyglot_key ^= PolyglotOracle::en_passant_bitstring(en_passant.1);
+            }
+        }
+
+        if self.to_move == White {
+            polyglot_key ^= PolyglotOracle::white_to_move_bitstring();
+        }
+
+        polyglot_key
+    }
+
     fn set_zobrist_key_from_move(
         &mut self,
         mov: ChessMove,
@@ -297,14 +498,30 @@ impl BoardState {
 
         incremented_zobrist_key ^= ZobristOracle::black_to_move_bitstring();
 
+        let landing_piece = mov.promotion.unwrap_or(moving_piece);
         incremented_zobrist_key ^=
             ZobristOracle::piece_bitstring(moving_piece, mov.start.as_index());
         incremented_zobrist_key ^=
-            ZobristOracle::piece_bitstring(moving_piece, mov.dest.as_index());
+            ZobristOracle::piece_bitstring(landing_piece, mov.dest.as_index());
 
+        let changes_file = mov.start.1 != mov.dest.1;
         if let Some(captured_piece) = captured_piece {
             incremented_zobrist_key ^=
                 ZobristOracle::piece_bitstring(captured_piece, mov.dest.as_index());
+        } else if moving_piece.kind == Pawn && changes_file {
+            let en_passant_capture = ChessCell(mov.start.0, mov.dest.1);
+            let captured_pawn = Piece::pawn(moving_piece.color.opposite());
+            incremented_zobrist_key ^=
+                ZobristOracle::piece_bitstring(captured_pawn, en_passant_capture.as_index());
+        }
+
+        if moving_piece.kind == King && mov.start.1.abs_diff(mov.dest.1) == 2 {
+            let rook_move = castling_rook_move(mov.dest);
+            let rook = Piece::rook(moving_piece.color);
+            incremented_zobrist_key ^=
+                ZobristOracle::piece_bitstring(rook, rook_move.start.as_index());
+            incremented_zobrist_key ^=
+                ZobristOracle::piece_bitstring(rook, rook_move.dest.as_index());
         }
 
         if let Some(old_file) = previous_en_passant_file {
@@ -321,28 +538,48 @@ impl BoardState {
 
         self.zobrist_key ^= incremented_zobrist_key;
     }
-    // First, a list of all enemy pieces that x-ray the target is generated with a lookup table.
-    // If this list is empty, the square is safe.
-    // Returns true if any of the enemy pieces attack the target.
+    // The pieces of the given color attacking the square. Rather than generating their moves, the
+    // attacks are looked up backwards from the square: a knight on the square would attack exactly
+    // the knights attacking it, and a slider on the square sees up to the first blocker each way.
+    pub fn attackers_to(&self, square: ChessCell, color: PieceColor) -> BitBoard {
+        self.attackers_to_with_occupancy(square, color, self.occupied())
+    }
+    // Same as attackers_to, with the sliders only blocked by the pieces in occupied, to look
+    // through pieces that are about to move
+    pub fn attackers_to_with_occupancy(
+        &self,
+        square: ChessCell,
+        color: PieceColor,
+        occupied: BitBoard,
+    ) -> BitBoard {
+        let index = square.as_index();
+        let pieces = |kind| self.pieces(color, kind).0;
+        let rooks_and_queens = pieces(Rook) | pieces(Queen);
+        let bishops_and_queens = pieces(Bishop) | pieces(Queen);
+        BitBoard(
+            (pawn_attacks(color.opposite(), index).0 & pieces(Pawn))
+                | (knight_attacks(index).0 & pieces(Knight))
+                | (king_attacks(index).0 & pieces(King))
+                | (rook_attacks(index, occupied).0 & rooks_and_queens)
+                | (bishop_attacks(index, occupied).0 & bishops_and_queens),
+        )
+    }
+    // Same as checking attackers_to for pieces, but stops at the first kind of piece found
     pub fn square_is_attacked(&self, target_square: ChessCell, attacker: PieceColor) -> bool {
-        let ray_attackers = self.ray_attackers(target_square, attacker);
-        if ray_attackers.is_empty() {
-            return false;
-        }
-        let mut enemy_moves: Vec<ChessMove> = Vec::new();
-        for (piece, position) in ray_attackers {
-            // TODO: Fix looking at already checked moves
-            generate_pseudo_moves_for_piece(piece, self, position, &mut enemy_moves);
-            let square_is_attacked = enemy_moves
-                .iter()
-                .map(|mov| mov.dest)
-                .any(|attacked_square| attacked_square == target_square);
-            if square_is_attacked {
-                return true;
-            }
-            enemy_moves.clear()
-        }
-        false
+        let index = target_square.as_index();
+        let pieces = |kind| self.pieces(attacker, kind).0;
+        knight_attacks(index).0 & pieces(Knight) != 0
+            || pawn_attacks(attacker.opposite(), index).0 & pieces(Pawn) != 0
+            || king_attacks(index).0 & pieces(King) != 0
+            || rook_attacks(index, self.occupied()).0 & (pieces(Rook) | pieces(Queen)) != 0
+            || bishop_attacks(index, self.occupied()).0 & (pieces(Bishop) | pieces(Queen)) != 0
+    }
+    // The pieces giving check to the side to move
+    pub fn checkers(&self) -> BitBoard {
+        self.attackers_to(self.king_location_of(self.to_move), self.to_move.opposite())
+    }
+    pub fn is_in_check(&self) -> bool {
+        self.square_is_attacked(self.king_location_of(self.to_move), self.to_move.opposite())
     }
     // Given an arbitrary position, determine if the position is legal given that the player next to move is self.to_move.
     // This method does not make any assumptions about how the move was made.
@@ -353,38 +590,12 @@ impl BoardState {
         };
         !self.square_is_attacked(king_location, self.to_move)
     }
-    // Searches a lookup table and returns all pieces that attack the target assuming an empty board.
-    pub fn ray_attackers(
-        &self,
-        target_square: ChessCell,
-        color: PieceColor,
-    ) -> Vec<(Piece, ChessCell)> {
-        let target_idx = target_square.as_index();
-        let mut ray_attackers: Vec<(Piece, ChessCell)> = Vec::new();
-        for attacker in self.get_piece_positions(color) {
-            let attacker_idx = attacker.as_index();
-            let attacking_square = self.board.square(attacker);
-            let attacking_piece = attacking_square.piece().unwrap();
-            let attacked_squares = match (attacking_piece.color, attacking_piece.kind) {
-                (White, Pawn) => WHITE_PAWN_RAY_ATTACKS[attacker_idx],
-                (Black, Pawn) => BLACK_PAWN_RAY_ATTACKS[attacker_idx],
-                (_, Knight) => KNIGHT_RAY_ATTACKS[attacker_idx],
-                (_, Bishop) => BISHOP_RAY_ATTACKS[attacker_idx],
-                (_, Rook) => ROOK_RAY_ATTACKS[attacker_idx],
-                (_, Queen) => QUEEN_RAY_ATTACKS[attacker_idx],
-                (_, King) => KING_RAY_ATTACKS[attacker_idx],
-            };
-            if attacked_squares.contains(&target_idx) {
-                ray_attackers.push((attacking_piece, attacker));
-            }
-        }
-        ray_attackers
-    }
     pub fn empty_game() -> BoardState {
         let board = ChessBoard::empty();
         let to_move = White;
         let white_bitboard = BitBoard(0);
         let black_bitboard = BitBoard(0);
+        let piece_bitboards = [BitBoard(0); 6];
         let white_king_location = ChessCell(100, 100);
         let black_king_location = ChessCell(100, 100);
         let castling_rights = CastlingRights::all_castling_rights();
@@ -393,6 +604,7 @@ impl BoardState {
             to_move,
             white_bitboard,
             black_bitboard,
+            piece_bitboards,
             white_king_location,
             black_king_location,
             last_move: None,
@@ -400,6 +612,10 @@ impl BoardState {
             eval: 0,
             castling_rights,
             zobrist_key: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_history: Vec::new(),
+            undo_stack: Vec::new(),
         }
     }
     pub fn new_game() -> BoardState {
@@ -407,6 +623,7 @@ impl BoardState {
         let to_move = White;
         let white_bitboard = WHITE_STARTING_BITBOARD;
         let black_bitboard = BLACK_STARTING_BITBOARD;
+        let piece_bitboards = get_piece_bitboards(&board);
         let white_king_location = E1;
         let black_king_location = E8;
         let castling_rights = CastlingRights::all_castling_rights();
@@ -417,6 +634,7 @@ impl BoardState {
             to_move,
             white_bitboard,
             black_bitboard,
+            piece_bitboards,
             white_king_location,
             black_king_location,
             last_move: None,
@@ -424,54 +642,139 @@ impl BoardState {
             eval: 0,
             castling_rights,
             zobrist_key: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_history: Vec::new(),
+            undo_stack: Vec::new(),
         };
-        board_state.eval = evaluate(&board_state);
+        board_state.eval = piece_square_eval(&board_state);
         board_state.set_zobrist_key_from_scratch();
         board_state
     }
-    pub fn from_fen(fen: &str) -> Result<BoardState, &str> {
+    pub fn from_fen(fen: &str) -> Result<BoardState, FenError> {
         let fen_parts: Vec<&str> = fen.split_ascii_whitespace().collect();
         if fen_parts.len() != 6 {
-            return Err("Failed to parse FEN string: FEN string did not have length 6");
+            return Err(FenError::FieldCount {
+                found: fen_parts.len(),
+                text: fen.to_string(),
+            });
         }
-        let fen_board = fen_parts[0];
+        let fen_board = fen_parts[fen::PIECE_PLACEMENT_FIELD];
         let board = fen::board_from_fen(fen_board)?;
         let (white_king_location, black_king_location) = find_kings(&board)?;
-        let fen_to_move = fen_parts[1];
+        fen::validate_pawns(&board)?;
+        let fen_to_move = fen_parts[fen::SIDE_TO_MOVE_FIELD];
         let to_move = fen::to_move_from_fen(fen_to_move)?;
-        let fen_castling_rights = fen_parts[2];
+        let fen_castling_rights = fen_parts[fen::CASTLING_RIGHTS_FIELD];
         let castling_rights = castling_rights_from_fen(fen_castling_rights)?;
-        let fen_en_passant_square = fen_parts[3];
-        let _en_passant_square = en_passant_square_from_fen(fen_en_passant_square)?;
-        let _halfmove_clock = fen_parts[4];
-        let _fullmove_clock = fen_parts[5];
-        if white_king_location.0 > BOARD_END
-            || white_king_location.1 > BOARD_END
-            || black_king_location.0 > BOARD_END
-            || black_king_location.1 > BOARD_END
-        {
-            return Err("Failed to parse FEN string: Both kings were not on the board");
-        }
+        fen::validate_castling_rights(&board, castling_rights)?;
+        let fen_en_passant_square = fen_parts[fen::EN_PASSANT_FIELD];
+        let en_passant_square = en_passant_square_from_fen(fen_en_passant_square)?;
+        fen::validate_en_passant_square(&board, to_move, en_passant_square)?;
+        let halfmove_clock = fen::move_counter_from_fen(
+            fen_parts[fen::HALFMOVE_CLOCK_FIELD],
+            fen::HALFMOVE_CLOCK_FIELD,
+        )?;
+        let fullmove_number = fen::move_counter_from_fen(
+            fen_parts[fen::FULLMOVE_NUMBER_FIELD],
+            fen::FULLMOVE_NUMBER_FIELD,
+        )?;
         let (white_bitboard, black_bitboard) = get_bitboards(&board);
+        let piece_bitboards = get_piece_bitboards(&board);
         let mut board_state = BoardState {
             board,
             to_move,
             white_bitboard,
             black_bitboard,
+            piece_bitboards,
             white_king_location,
             black_king_location,
             last_move: None,
-            en_passant: _en_passant_square,
+            en_passant: en_passant_square,
             eval: 0,
             castling_rights,
             zobrist_key: 0,
+            halfmove_clock,
+            fullmove_number,
+            zobrist_history: Vec::new(),
+            undo_stack: Vec::new(),
         };
-        board_state.eval = evaluate(&board_state) * board_state.to_move.signum();
+        // The side that just moved cannot have left its king in check
+        if !board_state.is_valid_move() {
+            return Err(FenError::SideNotToMoveInCheck {
+                field: fen::SIDE_TO_MOVE_FIELD,
+                text: fen_to_move.to_string(),
+            });
+        }
+        board_state.eval = piece_square_eval(&board_state) * board_state.to_move.signum();
         board_state.set_zobrist_key_from_scratch();
         Ok(board_state)
     }
+    pub fn to_fen(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            fen::board_to_fen(&self.board),
+            fen::to_move_to_fen(self.to_move),
+            fen::castling_rights_to_fen(self.castling_rights),
+            fen::en_passant_square_to_fen(self.en_passant),
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+    pub fn move_to_san(&self, mov: ChessMove) -> String {
+        san::move_to_san(self, mov)
+    }
+    pub fn parse_san(&self, san: &str) -> Result<ChessMove, SanError> {
+        san::parse_san(self, san)
+    }
+    // Finds the legal move matching long algebraic notation such as e2e4 or e7e8q.
+    // The promotion piece takes the colour of the side to move.
+    pub fn parse_uci_move(&self, uci_move: &str) -> Option<ChessMove> {
+        let start = uci_move.get(0..2)?.parse::<ChessCell>().ok()?;
+        let dest = uci_move.get(2..4)?.parse::<ChessCell>().ok()?;
+        let promotion = match uci_move.get(4..)? {
+            "" => None,
+            "n" => Some(Piece::knight(self.to_move)),
+            "b" => Some(Piece::bishop(self.to_move)),
+            "r" => Some(Piece::rook(self.to_move)),
+            "q" => Some(Piece::queen(self.to_move)),
+            _ => return None,
+        };
+        let mov = ChessMove {
+            start,
+            dest,
+            promotion,
+        };
+        generate_moves(self).contains(&mov).then_some(mov)
+    }
+}
+impl std::fmt::Display for BoardState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_fen())
+    }
+}
+impl FromStr for BoardState {
+    type Err = FenError;
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        BoardState::from_fen(fen)
+    }
 }
 
+// The rook move that accompanies the king landing on castling_dest
+fn castling_rook_move(castling_dest: ChessCell) -> ChessMove {
+    let (rook_start, rook_dest) = match castling_dest {
+        G1 => (H1, F1),
+        C1 => (A1, D1),
+        G8 => (H8, F8),
+        C8 => (A8, D8),
+        _ => unreachable!(),
+    };
+    ChessMove {
+        start: rook_start,
+        dest: rook_dest,
+        promotion: None,
+    }
+}
 // Returns (white_bitboard, black_bitboard)
 pub fn get_bitboards(board: &ChessBoard) -> (BitBoard, BitBoard) {
     let mut white_bitboard: u64 = 0;
@@ -490,27 +793,48 @@ pub fn get_bitboards(board: &ChessBoard) -> (BitBoard, BitBoard) {
     }
     (BitBoard(white_bitboard), BitBoard(black_bitboard))
 }
+// Returns the bitboards of each piece kind, indexed by PieceKind::index
+pub fn get_piece_bitboards(board: &ChessBoard) -> [BitBoard; 6] {
+    let mut piece_bitboards = [BitBoard(0); 6];
+    for rank in RANK_1..=RANK_8 {
+        for file in A_FILE..=H_FILE {
+            let cell = ChessCell(rank, file);
+            if let Square::Full(piece) = board.square(cell) {
+                piece_bitboards[piece.kind.index()].add_piece(cell.as_index());
+            }
+        }
+    }
+    piece_bitboards
+}
 // Returns (white_king_location, black_king_location)
-fn find_kings(board: &ChessBoard) -> Result<(ChessCell, ChessCell), &'static str> {
-    let mut white_king_location: Option<ChessCell> = None;
-    let mut black_king_location: Option<ChessCell> = None;
+fn find_kings(board: &ChessBoard) -> Result<(ChessCell, ChessCell), FenError> {
+    let mut white_king_locations = Vec::new();
+    let mut black_king_locations = Vec::new();
     for rank in RANK_1..=RANK_8 {
         for file in A_FILE..=H_FILE {
             let square = board.square(ChessCell(rank, file));
             if let Square::Full(piece) = square {
                 if piece.kind == King {
                     match piece.color {
-                        White => white_king_location = Some(ChessCell(rank, file)),
-                        Black => black_king_location = Some(ChessCell(rank, file)),
+                        White => white_king_locations.push(ChessCell(rank, file)),
+                        Black => black_king_locations.push(ChessCell(rank, file)),
                     }
                 }
             }
         }
     }
-    if white_king_location.is_none() || black_king_location.is_none() {
-        return Err("Failed to parse FEN string: Could not find both kings");
+    match (
+        white_king_locations.as_slice(),
+        black_king_locations.as_slice(),
+    ) {
+        (&[white_king_location], &[black_king_location]) => {
+            Ok((white_king_location, black_king_location))
+        }
+        _ => Err(FenError::KingCount {
+            field: fen::PIECE_PLACEMENT_FIELD,
+            text: fen::board_to_fen(board),
+        }),
     }
-    Ok((white_king_location.unwrap(), black_king_location.unwrap()))
 }
 #[cfg(test)]
 mod tests {
@@ -523,6 +847,76 @@ mod tests {
         assert_eq!(parsed_cell, cell);
     }
     #[test]
+    fn parse_uci_move_resolves_promotion_colour() {
+        let board_state = BoardState::from_fen("7k/8/8/8/8/8/p7/7K b - - 0 1").unwrap();
+
+        assert_eq!(
+            board_state.parse_uci_move("a2a1n"),
+            Some((A2, A1, Piece::knight(Black)).into())
+        );
+    }
+    #[test]
+    fn parse_uci_move_rejects_illegal_and_malformed_moves() {
+        let board_state = BoardState::new_game();
+
+        assert_eq!(board_state.parse_uci_move("e2e4"), Some((E2, E4).into()));
+        for uci_move in ["e2e5", "e1g1", "e2e4q", "e2", "e2e4x", "z2e4", "e7e5"] {
+            assert_eq!(board_state.parse_uci_move(uci_move), None, "{uci_move}");
+        }
+    }
+    #[test]
+    fn polyglot_keys_match_the_reference_positions() {
+        // The positions and keys given in the Polyglot book format specification
+        let reference_keys = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                0x463b96181691fc9c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                0x823c9b50fd114196,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+                0x0756b94461c50fb0,
+            ),
+            (
+                "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2",
+                0x662fafb965db29d4,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+                0x22a48b5a8e47ff78,
+            ),
+            (
+                "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR b kq - 0 3",
+                0x652a607ca3f242c1,
+            ),
+            (
+                "rnbq1bnr/ppp1pkpp/8/3pPp2/8/8/PPPPKPPP/RNBQ1BNR w - - 0 4",
+                0x00fdd303c946bdd9,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3",
+                0x3c8123ea7b067637,
+            ),
+            (
+                "rnbqkbnr/p1pppppp/8/8/P6P/R1p5/1P1PPPP1/1NBQKBNR b Kkq - 0 4",
+                0x5c3f9b829b279560,
+            ),
+        ];
+        for (fen, polyglot_key) in reference_keys {
+            let board_state = BoardState::from_fen(fen).unwrap();
+            assert_eq!(board_state.polyglot_key(), polyglot_key, "{fen}");
+        }
+    }
+    #[test]
+    fn parse_chess_cell_outside_board_fails() {
+        for cell in ["a9", "a0", "i1", "e", "e10"] {
+            assert!(ChessCell::from_str(cell).is_err(), "{cell}");
+        }
+    }
+    #[test]
     fn create_board_from_valid_fen_succeeds() {
         let fen_board_state: BoardState =
             BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
@@ -575,6 +969,37 @@ mod tests {
         assert_eq!(black_bitboard.0, 0xFFFF << 48);
     }
     #[test]
+    fn piece_bitboards_follow_captures_castling_en_passant_and_promotions() {
+        let mut board_state = BoardState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        let original = board_state.clone();
+        let moves: [ChessMove; 6] = [
+            (E1, G1).into(),
+            (H3, G2).into(),
+            (A2, A4).into(),
+            (B4, A3).into(),
+            (E5, F7).into(),
+            (G2, H1, Piece::queen(Black)).into(),
+        ];
+        for mov in moves {
+            board_state.make_move(mov);
+            assert_eq!(
+                board_state.piece_bitboards,
+                get_piece_bitboards(&board_state.board)
+            );
+            assert_eq!(
+                (board_state.white_bitboard, board_state.black_bitboard),
+                get_bitboards(&board_state.board)
+            );
+        }
+        for _ in moves {
+            board_state.unmake_move();
+        }
+        assert_eq!(board_state, original);
+    }
+    #[test]
     fn get_piece_positions_for_starting_position() {
         let board_state = BoardState::new_game();
         let white_positions = board_state.get_piece_positions(White);
@@ -611,6 +1036,53 @@ mod tests {
         }
     }
     #[test]
+    fn pawn_attacks_empty_diagonal_squares() {
+        let board_state = BoardState::from_fen("4k3/8/8/8/8/8/6p1/4K2R w K - 0 1").unwrap();
+
+        assert!(board_state.square_is_attacked(F1, Black));
+        assert!(board_state.square_is_attacked(H1, Black));
+        assert!(!board_state.square_is_attacked(G1, Black));
+    }
+    #[test]
+    fn attackers_to_finds_every_piece_of_the_color_attacking_the_square() {
+        let board_state =
+            BoardState::from_fen("4k3/8/2n5/3p1b2/1R2Pq2/8/3N4/B3K2r w - - 0 1").unwrap();
+
+        let black_attackers: Vec<ChessCell> = board_state.attackers_to(E4, Black).collect();
+        let white_attackers: Vec<ChessCell> = board_state.attackers_to(E4, White).collect();
+
+        assert_eq!(black_attackers, vec![F4, D5, F5]);
+        assert_eq!(white_attackers, vec![D2, B4]);
+        // The queen on f4 is blocked by the pawn on e4
+        assert_eq!(
+            board_state.attackers_to(D4, Black).collect::<Vec<_>>(),
+            vec![C6]
+        );
+        assert_eq!(
+            board_state.attackers_to(E1, Black).collect::<Vec<_>>(),
+            vec![H1]
+        );
+        assert_eq!(board_state.checkers(), board_state.attackers_to(E1, Black));
+        assert!(board_state.is_in_check());
+    }
+    #[test]
+    fn attackers_to_with_occupancy_looks_through_removed_pieces() {
+        let board_state = BoardState::from_fen("4k3/8/8/8/8/8/8/R2NK3 w - - 0 1").unwrap();
+        let without_knight = BitBoard(board_state.occupied().0 & !(1 << D1.as_index()));
+
+        assert_eq!(
+            board_state.attackers_to(B1, White).collect::<Vec<_>>(),
+            vec![A1]
+        );
+        assert!(!board_state.square_is_attacked(E1, White));
+        assert_eq!(
+            board_state
+                .attackers_to_with_occupancy(E1, White, without_knight)
+                .collect::<Vec<_>>(),
+            vec![A1]
+        );
+    }
+    #[test]
     fn is_empty_or_enemy_of_tests() {
         let board_state = BoardState::new_game();
         let board = board_state.board;
@@ -684,6 +1156,42 @@ mod tests {
         );
     }
 
+    fn assert_incremental_zobrist_key_matches_scratch(board_state: &BoardState, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+        let mut scratch_board = board_state.clone();
+        for mov in generate_moves(board_state) {
+            scratch_board.make_move(mov);
+            let incremental_zobrist_key = scratch_board.zobrist_key;
+            scratch_board.set_zobrist_key_from_scratch();
+            assert_eq!(
+                scratch_board.zobrist_key, incremental_zobrist_key,
+                "after {mov}"
+            );
+            assert_incremental_zobrist_key_matches_scratch(&scratch_board, depth - 1);
+            scratch_board.unmake_move();
+        }
+    }
+
+    #[test]
+    fn incremental_zobrist_key_matches_key_computed_from_scratch() {
+        let board_state = BoardState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        assert_incremental_zobrist_key_matches_scratch(&board_state, 2);
+    }
+
+    #[test]
+    fn different_pieces_on_same_square_have_different_zobrist_keys() {
+        let knight_on_b1 = BoardState::from_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1").unwrap();
+        let bishop_on_b1 = BoardState::from_fen("4k3/8/8/8/8/8/8/1B2K3 w - - 0 1").unwrap();
+
+        assert_ne!(knight_on_b1.zobrist_key, bishop_on_b1.zobrist_key);
+    }
+
     #[test]
     fn making_move_changes_zobrist_key() {
         let mut board_state = BoardState::new_game();
@@ -711,4 +1219,249 @@ mod tests {
         let zobrist_key_after = board_state.zobrist_key;
         assert_ne!(zobrist_key_before, zobrist_key_after);
     }
+
+    fn assert_make_unmake_round_trips(board_state: &BoardState, depth: u8) {
+        if depth == 0 {
+            return;
+        }
+        let mut scratch_board = board_state.clone();
+        for mov in generate_moves(board_state) {
+            scratch_board.make_move(mov);
+            assert_make_unmake_round_trips(&scratch_board, depth - 1);
+            scratch_board.unmake_move();
+            assert_eq!(
+                &scratch_board, board_state,
+                "unmaking {mov} did not restore the position"
+            );
+        }
+    }
+
+    #[test]
+    fn make_unmake_round_trip_restores_starting_position() {
+        assert_make_unmake_round_trips(&BoardState::new_game(), 3);
+    }
+
+    #[test]
+    fn make_unmake_round_trip_restores_position_with_castling_and_promotions() {
+        let board_state = BoardState::from_fen(
+            "r3k2r/pPppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPpP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        assert_make_unmake_round_trips(&board_state, 2);
+    }
+
+    #[test]
+    fn make_unmake_round_trip_restores_en_passant_capture() {
+        let mut board_state =
+            BoardState::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        let board_state_before = board_state.clone();
+
+        board_state.make_move((E5, F6).into());
+        assert!(board_state.board.square(F5).is_empty());
+        board_state.unmake_move();
+
+        assert_eq!(board_state, board_state_before);
+    }
+
+    #[test]
+    fn make_unmake_round_trip_restores_castling() {
+        let mut board_state = BoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1").unwrap();
+        let board_state_before = board_state.clone();
+
+        board_state.make_move(CastlingType::BlackQueenSide.into());
+        assert_eq!(
+            board_state.board.square(D8).piece(),
+            Some(Piece::rook(Black))
+        );
+        board_state.unmake_move();
+
+        assert_eq!(board_state, board_state_before);
+    }
+
+    #[test]
+    fn halfmove_clock_resets_on_pawn_moves_and_captures() {
+        let mut board_state = BoardState::new_game();
+
+        board_state.make_move((G1, F3).into());
+        board_state.make_move((B8, C6).into());
+        assert_eq!(board_state.halfmove_clock, 2);
+
+        board_state.make_move((E2, E3).into());
+        assert_eq!(board_state.halfmove_clock, 0);
+
+        board_state.unmake_move();
+        assert_eq!(board_state.halfmove_clock, 2);
+    }
+
+    #[test]
+    fn move_counters_are_parsed_from_fen() {
+        let board_state =
+            BoardState::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 1 2")
+                .unwrap();
+
+        assert_eq!(board_state.halfmove_clock, 1);
+        assert_eq!(board_state.fullmove_number, 2);
+        assert!(BoardState::from_fen("8/8/8/4k3/8/8/8/4K3 w - - x 1").is_err());
+    }
+
+    #[test]
+    fn fullmove_number_increments_after_black_moves() {
+        let mut board_state = BoardState::new_game();
+
+        board_state.make_move((G1, F3).into());
+        assert_eq!(board_state.fullmove_number, 1);
+        board_state.make_move((B8, C6).into());
+        assert_eq!(board_state.fullmove_number, 2);
+
+        board_state.unmake_move();
+        assert_eq!(board_state.fullmove_number, 1);
+    }
+
+    fn shuffle_knights(board_state: &mut BoardState) {
+        for mov in [(G1, F3), (G8, F6), (F3, G1), (F6, G8)] {
+            board_state.make_move(mov.into());
+        }
+    }
+
+    #[test]
+    fn repeating_the_position_three_times_is_a_draw() {
+        let mut board_state = BoardState::new_game();
+
+        shuffle_knights(&mut board_state);
+        assert!(board_state.is_repetition());
+        assert_eq!(board_state.get_game_winner(), None);
+
+        shuffle_knights(&mut board_state);
+        assert!(board_state.is_threefold_repetition());
+        assert_eq!(
+            board_state.get_game_winner(),
+            Some(GameResult::Draw(DrawReason::Repetition))
+        );
+    }
+
+    #[test]
+    fn repetitions_are_counted_from_the_last_pawn_move() {
+        let mut board_state = BoardState::new_game();
+
+        shuffle_knights(&mut board_state);
+        assert_eq!(board_state.repetition_count(), 1);
+
+        board_state.make_move((E2, E3).into());
+        board_state.make_move((E7, E6).into());
+        assert!(!board_state.is_repetition());
+        shuffle_knights(&mut board_state);
+        assert_eq!(board_state.repetition_count(), 1);
+    }
+
+    #[test]
+    fn hundred_halfmoves_without_progress_is_a_draw() {
+        let mut board_state = BoardState::from_fen("8/8/8/4k3/8/8/8/3QK3 w - - 99 80").unwrap();
+
+        assert_eq!(board_state.get_game_winner(), None);
+        board_state.make_move((D1, D2).into());
+
+        assert!(board_state.is_fifty_move_draw());
+        assert_eq!(
+            board_state.get_game_winner(),
+            Some(GameResult::Draw(DrawReason::FiftyMoveRule))
+        );
+    }
+
+    #[test]
+    fn checkmate_on_the_hundredth_halfmove_wins() {
+        let mut board_state = BoardState::from_fen("7k/8/6K1/8/8/8/Q7/8 w - - 99 80").unwrap();
+
+        board_state.make_move((A2, A8).into());
+
+        assert_eq!(
+            board_state.get_game_winner(),
+            Some(GameResult::Winner(White))
+        );
+    }
+
+    #[test]
+    fn stalemate_is_a_draw_by_stalemate() {
+        let board_state = BoardState::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+
+        assert_eq!(
+            board_state.get_game_winner(),
+            Some(GameResult::Draw(DrawReason::Stalemate))
+        );
+    }
+
+    #[test]
+    fn lone_minor_pieces_are_insufficient_material() {
+        for fen in [
+            "8/8/8/4k3/8/8/8/4K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/1N2K3 b - - 0 1",
+            "5b2/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+            "8/8/3b4/4k3/8/4B3/8/2B1K3 w - - 0 1",
+        ] {
+            let board_state = BoardState::from_fen(fen).unwrap();
+
+            assert!(board_state.is_insufficient_material(), "{fen}");
+            assert_eq!(
+                board_state.get_game_winner(),
+                Some(GameResult::Draw(DrawReason::InsufficientMaterial))
+            );
+        }
+    }
+
+    #[test]
+    fn mating_material_is_sufficient() {
+        for fen in [
+            "8/8/8/4k3/8/8/4P3/4K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/1NB1K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/2B1KB2 w - - 0 1",
+            "2b5/8/8/4k3/8/8/8/2B1K3 w - - 0 1",
+            "8/8/8/4k3/8/8/8/1N2K1N1 w - - 0 1",
+            "1n6/8/8/4k3/8/8/8/1N2K3 w - - 0 1",
+        ] {
+            let board_state = BoardState::from_fen(fen).unwrap();
+
+            assert!(!board_state.is_insufficient_material(), "{fen}");
+        }
+    }
+
+    const FEN_CORPUS: [&str; 8] = [
+        STARTING_FEN_STRING,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "8/8/8/4k3/8/8/8/3QK3 b - - 99 80",
+    ];
+
+    #[test]
+    fn fen_round_trips_through_board_state() {
+        for fen in FEN_CORPUS {
+            let board_state: BoardState = fen.parse().unwrap();
+
+            assert_eq!(board_state.to_fen(), fen);
+            assert_eq!(board_state.to_string(), fen);
+        }
+    }
+
+    #[test]
+    fn fen_after_moves_has_updated_fields() {
+        let mut board_state = BoardState::new_game();
+
+        board_state.make_move((E2, E4).into());
+        assert_eq!(
+            board_state.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+
+        board_state.make_move((G8, F6).into());
+        board_state.make_move((E1, E2).into());
+        assert_eq!(
+            board_state.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 2 2"
+        );
+    }
 }
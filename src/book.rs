@@ -0,0 +1,325 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::Path;
+
+use crate::board_elements::ChessCell;
+use crate::board_elements::ChessMove;
+use crate::board_elements::Piece;
+use crate::board_elements::PieceColor::*;
+use crate::board_elements::PieceKind::*;
+use crate::board_state::BoardState;
+use crate::constants::*;
+use crate::move_generation::generate_moves;
+
+// Every entry of a Polyglot .bin file is 16 big endian bytes: key, move, weight and learn
+pub const ENTRY_SIZE: usize = 16;
+// Book moves are played until this many plies of the game have been played
+pub const DEFAULT_MAX_PLY: u32 = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    // The polyglot key of the position, see BoardState::polyglot_key
+    pub key: u64,
+    pub raw_move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+impl BookEntry {
+    pub fn from_bytes(bytes: [u8; ENTRY_SIZE]) -> BookEntry {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            raw_move: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+    pub fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.raw_move.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+// How a move is picked when the book has several for the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BookSelection {
+    // Picks a move with probability proportional to its weight
+    #[default]
+    WeightedRandom,
+    // Always picks the move with the highest weight
+    Best,
+}
+
+// A Polyglot opening book, with its entries sorted by key as in the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningBook {
+    entries: Vec<BookEntry>,
+    pub selection: BookSelection,
+    pub max_ply: u32,
+}
+impl OpeningBook {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<OpeningBook> {
+        OpeningBook::from_bytes(&std::fs::read(path)?)
+    }
+    pub fn from_bytes(bytes: &[u8]) -> std::io::Result<OpeningBook> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Polyglot book size must be a multiple of {ENTRY_SIZE} bytes"),
+            ));
+        }
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        Ok(OpeningBook::from_entries(entries))
+    }
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> OpeningBook {
+        entries.sort_by_key(|entry| entry.key);
+        OpeningBook {
+            entries,
+            selection: BookSelection::default(),
+            max_ply: DEFAULT_MAX_PLY,
+        }
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|entry| entry.to_bytes())
+            .collect()
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+    pub fn entries(&self) -> &[BookEntry] {
+        &self.entries
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    // The legal book moves for the position and their weights, in book order
+    pub fn moves(&self, board_state: &BoardState) -> Vec<(ChessMove, u16)> {
+        let key = board_state.polyglot_key();
+        let first_entry = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[first_entry..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| Some((decode_move(board_state, entry.raw_move)?, entry.weight)))
+            .collect()
+    }
+    // Picks a book move for the position, or None if it is out of book or past max_ply.
+    // Moves with a weight of zero are never picked.
+    pub fn choose_move(&self, board_state: &BoardState) -> Option<ChessMove> {
+        if game_ply(board_state) >= self.max_ply {
+            return None;
+        }
+        let moves: Vec<(ChessMove, u16)> = self
+            .moves(board_state)
+            .into_iter()
+            .filter(|&(_, weight)| weight > 0)
+            .collect();
+        match self.selection {
+            BookSelection::Best => moves
+                .iter()
+                .rev()
+                .max_by_key(|(_, weight)| weight)
+                .map(|&(mov, _)| mov),
+            BookSelection::WeightedRandom => {
+                let total_weight: u64 = moves.iter().map(|&(_, weight)| weight as u64).sum();
+                if total_weight == 0 {
+                    return None;
+                }
+                let mut pick = random_u64() % total_weight;
+                moves
+                    .into_iter()
+                    .find_map(|(mov, weight)| match pick.checked_sub(weight as u64) {
+                        Some(remaining) => {
+                            pick = remaining;
+                            None
+                        }
+                        None => Some(mov),
+                    })
+            }
+        }
+    }
+}
+
+// Encodes a move the way Polyglot books store it: the destination square in bits 0-5, the start
+// square in bits 6-11 and the promotion piece in bits 12-14. Castling is written as the king
+// capturing its own rook.
+pub fn encode_move(board_state: &BoardState, mov: ChessMove) -> u16 {
+    let is_king_move = board_state
+        .board
+        .square(mov.start)
+        .piece()
+        .map(|piece| piece.kind)
+        == Some(King);
+    let dest = match mov.dest.1 {
+        G_FILE if is_king_move && mov.start.1 == E_FILE => ChessCell(mov.dest.0, H_FILE),
+        C_FILE if is_king_move && mov.start.1 == E_FILE => ChessCell(mov.dest.0, A_FILE),
+        _ => mov.dest,
+    };
+    let promotion = match mov.promotion.map(|piece| piece.kind) {
+        Some(Knight) => 1,
+        Some(Bishop) => 2,
+        Some(Rook) => 3,
+        Some(Queen) => 4,
+        _ => 0,
+    };
+    (dest.as_index() | mov.start.as_index() << 6 | promotion << 12) as u16
+}
+// Decodes a Polyglot move, returning None if it is not legal in the position
+pub fn decode_move(board_state: &BoardState, raw_move: u16) -> Option<ChessMove> {
+    let raw_move = raw_move as usize;
+    let start = ChessCell::from_index(raw_move >> 6 & 0x3f);
+    let mut dest = ChessCell::from_index(raw_move & 0x3f);
+    let promotion = match raw_move >> 12 & 0x7 {
+        0 => None,
+        1 => Some(Piece::knight(board_state.to_move)),
+        2 => Some(Piece::bishop(board_state.to_move)),
+        3 => Some(Piece::rook(board_state.to_move)),
+        4 => Some(Piece::queen(board_state.to_move)),
+        _ => return None,
+    };
+    let is_castling = board_state.board.square(start).piece()
+        == Some(Piece::king(board_state.to_move))
+        && board_state.board.square(dest).piece() == Some(Piece::rook(board_state.to_move));
+    if is_castling {
+        dest.1 = match dest.1 {
+            H_FILE => G_FILE,
+            _ => C_FILE,
+        };
+    }
+    let mov = ChessMove {
+        start,
+        dest,
+        promotion,
+    };
+    generate_moves(board_state).contains(&mov).then_some(mov)
+}
+
+// The number of plies played in the game, counted from the move counters
+fn game_ply(board_state: &BoardState) -> u32 {
+    let black_to_move = u32::from(board_state.to_move == Black);
+    board_state.fullmove_number.saturating_sub(1) * 2 + black_to_move
+}
+
+// A random number from the standard library's randomly seeded hasher, to avoid a dependency
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book_entry(board_state: &BoardState, mov: ChessMove, weight: u16) -> BookEntry {
+        BookEntry {
+            key: board_state.polyglot_key(),
+            raw_move: encode_move(board_state, mov),
+            weight,
+            learn: 0,
+        }
+    }
+
+    // A small book with two replies to 1. e4 and one to 1. d4
+    fn test_book() -> OpeningBook {
+        let start = BoardState::new_game();
+        let mut after_e4 = start.clone();
+        after_e4.make_move((E2, E4).into());
+        let entries = [
+            book_entry(&after_e4, (C7, C5).into(), 30),
+            book_entry(&start, (E2, E4).into(), 10),
+            book_entry(&start, (D2, D4).into(), 5),
+            book_entry(&after_e4, (E7, E5).into(), 20),
+            book_entry(&start, (G1, F3).into(), 0),
+        ];
+        let bytes = OpeningBook::from_entries(entries.to_vec()).to_bytes();
+        OpeningBook::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn book_moves_are_found_for_the_position() {
+        let book = test_book();
+
+        assert_eq!(book.len(), 5);
+        assert_eq!(
+            book.moves(&BoardState::new_game()),
+            vec![
+                ((E2, E4).into(), 10),
+                ((D2, D4).into(), 5),
+                ((G1, F3).into(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn best_selection_picks_the_heaviest_move() {
+        let mut book = test_book();
+        book.selection = BookSelection::Best;
+        let mut board_state = BoardState::new_game();
+
+        assert_eq!(book.choose_move(&board_state), Some((E2, E4).into()));
+        board_state.make_move((E2, E4).into());
+        assert_eq!(book.choose_move(&board_state), Some((C7, C5).into()));
+        board_state.make_move((C7, C5).into());
+        assert_eq!(book.choose_move(&board_state), None);
+    }
+
+    #[test]
+    fn weighted_random_selection_only_picks_moves_with_weight() {
+        let book = test_book();
+        let board_state = BoardState::new_game();
+
+        for _ in 0..50 {
+            let mov = book.choose_move(&board_state).unwrap();
+            assert!(mov == (E2, E4).into() || mov == (D2, D4).into());
+        }
+    }
+
+    #[test]
+    fn book_is_not_used_past_max_ply() {
+        let mut book = test_book();
+        book.max_ply = 1;
+        let mut board_state = BoardState::new_game();
+
+        assert!(book.choose_move(&board_state).is_some());
+        board_state.make_move((E2, E4).into());
+        assert_eq!(book.choose_move(&board_state), None);
+    }
+
+    #[test]
+    fn castling_is_encoded_as_king_takes_rook() {
+        let board_state =
+            BoardState::from_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1").unwrap();
+
+        let raw_move = encode_move(&board_state, (E1, G1).into());
+
+        assert_eq!(raw_move, (E1.as_index() << 6 | H1.as_index()) as u16);
+        assert_eq!(decode_move(&board_state, raw_move), Some((E1, G1).into()));
+        let raw_move = encode_move(&board_state, (E1, C1).into());
+        assert_eq!(decode_move(&board_state, raw_move), Some((E1, C1).into()));
+    }
+
+    #[test]
+    fn promotions_are_decoded_for_the_side_to_move() {
+        let board_state = BoardState::from_fen("7k/8/8/8/8/8/p7/7K b - - 0 1").unwrap();
+        let mov = (A2, A1, Piece::knight(Black)).into();
+
+        let raw_move = encode_move(&board_state, mov);
+
+        assert_eq!(raw_move >> 12, 1);
+        assert_eq!(decode_move(&board_state, raw_move), Some(mov));
+    }
+
+    #[test]
+    fn truncated_book_is_rejected() {
+        assert!(OpeningBook::from_bytes(&[0; ENTRY_SIZE + 3]).is_err());
+    }
+}
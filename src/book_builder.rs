@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+
+use crate::board_elements::ChessMove;
+use crate::board_elements::PieceColor;
+use crate::board_elements::PieceColor::*;
+use crate::board_state::BoardState;
+use crate::book::{encode_move, BookEntry, OpeningBook};
+use crate::pgn::{PgnGame, PgnResult};
+
+pub const DEFAULT_MAX_PLY: u32 = 20;
+
+// The results of the games a move was played in, from the point of view of the side playing it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MoveStatistics {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+impl MoveStatistics {
+    pub fn count(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+    // The average score from 0 to 1, counting a draw as half a win
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.count().max(1) as f64
+    }
+    // The Polyglot weight, which like the polyglot tool counts a win twice and a draw once
+    fn weight(&self) -> u64 {
+        2 * self.wins as u64 + self.draws as u64
+    }
+    fn record(&mut self, result: PgnResult, mover: PieceColor) {
+        match (result, mover) {
+            (PgnResult::WhiteWins, White) | (PgnResult::BlackWins, Black) => self.wins += 1,
+            (PgnResult::WhiteWins, Black) | (PgnResult::BlackWins, White) => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+}
+
+struct PositionStatistics {
+    // The first four FEN fields, which is all a book position needs
+    epd: String,
+    polyglot_key: u64,
+    moves: Vec<(ChessMove, MoveStatistics)>,
+}
+impl PositionStatistics {
+    fn board_state(&self) -> BoardState {
+        BoardState::from_fen(&format!("{} 0 1", self.epd)).unwrap()
+    }
+}
+
+// Collects move statistics from finished games, keyed by zobrist key so that transpositions
+// share their statistics, and writes the moves that pass the filters as an opening book.
+pub struct BookBuilder {
+    positions: HashMap<u64, PositionStatistics>,
+    // Moves are recorded for this many plies from the start of each game
+    pub max_ply: u32,
+    // Moves played in fewer games than this are left out of the book
+    pub min_count: u32,
+    // Moves scoring less than this, from 0 to 1, are left out of the book
+    pub min_score: f64,
+}
+impl BookBuilder {
+    pub fn new() -> BookBuilder {
+        BookBuilder {
+            positions: HashMap::new(),
+            max_ply: DEFAULT_MAX_PLY,
+            min_count: 1,
+            min_score: 0.0,
+        }
+    }
+    // Records the main line of the game. Returns false and records nothing if the game has no result.
+    pub fn add_game(&mut self, game: &PgnGame) -> bool {
+        if game.result == PgnResult::Unknown {
+            return false;
+        }
+        let mut board_state = game.starting_position.clone();
+        for pgn_move in game.moves.iter().take(self.max_ply as usize) {
+            let position = self
+                .positions
+                .entry(board_state.zobrist_key)
+                .or_insert_with(|| PositionStatistics {
+                    epd: epd_fields(&board_state),
+                    polyglot_key: board_state.polyglot_key(),
+                    moves: Vec::new(),
+                });
+            let statistics = match position
+                .moves
+                .iter_mut()
+                .find(|(mov, _)| *mov == pgn_move.mov)
+            {
+                Some((_, statistics)) => statistics,
+                None => {
+                    position
+                        .moves
+                        .push((pgn_move.mov, MoveStatistics::default()));
+                    &mut position.moves.last_mut().unwrap().1
+                }
+            };
+            statistics.record(game.result, board_state.to_move);
+            board_state.make_move(pgn_move.mov);
+        }
+        true
+    }
+    pub fn position_count(&self) -> usize {
+        self.positions.len()
+    }
+    // The statistics of every move recorded in the position, whether or not it passes the filters
+    pub fn statistics(&self, board_state: &BoardState) -> Vec<(ChessMove, MoveStatistics)> {
+        self.positions
+            .get(&board_state.zobrist_key)
+            .map(|position| position.moves.clone())
+            .unwrap_or_default()
+    }
+    // The positions with at least one move passing the filters, with those moves sorted by weight
+    fn selected_positions(&self) -> Vec<(&PositionStatistics, Vec<(ChessMove, MoveStatistics)>)> {
+        let mut selected_positions: Vec<_> = self
+            .positions
+            .values()
+            .filter_map(|position| {
+                let mut moves: Vec<(ChessMove, MoveStatistics)> = position
+                    .moves
+                    .iter()
+                    .filter(|(_, statistics)| {
+                        statistics.count() >= self.min_count && statistics.score() >= self.min_score
+                    })
+                    .copied()
+                    .collect();
+                moves.sort_by_key(|(_, statistics)| std::cmp::Reverse(statistics.weight()));
+                (!moves.is_empty()).then_some((position, moves))
+            })
+            .collect();
+        selected_positions.sort_by(|(first, _), (second, _)| first.epd.cmp(&second.epd));
+        selected_positions
+    }
+    // Builds a Polyglot book. Weights are scaled down if needed to fit in 16 bits.
+    pub fn to_polyglot(&self) -> OpeningBook {
+        let selected_positions = self.selected_positions();
+        let max_weight = selected_positions
+            .iter()
+            .flat_map(|(_, moves)| moves.iter().map(|(_, statistics)| statistics.weight()))
+            .max()
+            .unwrap_or(0);
+        let scale = |weight: u64| match max_weight > u16::MAX as u64 {
+            true => (weight * u16::MAX as u64 / max_weight) as u16,
+            false => weight as u16,
+        };
+        let mut entries = Vec::new();
+        for (position, moves) in selected_positions {
+            let board_state = position.board_state();
+            for (mov, statistics) in moves {
+                entries.push(BookEntry {
+                    key: position.polyglot_key,
+                    raw_move: encode_move(&board_state, mov),
+                    weight: scale(statistics.weight()),
+                    learn: 0,
+                });
+            }
+        }
+        OpeningBook::from_entries(entries)
+    }
+    // Writes one EPD record per position, with the book moves as bm and their statistics as c0,
+    // e.g. rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4 d4; c0 "e4 +3 =1 -0, d4 +1 =0 -1";
+    pub fn to_epd(&self) -> String {
+        let mut epd = String::new();
+        for (position, moves) in self.selected_positions() {
+            let board_state = position.board_state();
+            let sans: Vec<String> = moves
+                .iter()
+                .map(|&(mov, _)| board_state.move_to_san(mov))
+                .collect();
+            let statistics: Vec<String> = sans
+                .iter()
+                .zip(&moves)
+                .map(|(san, (_, statistics))| {
+                    format!(
+                        "{san} +{} ={} -{}",
+                        statistics.wins, statistics.draws, statistics.losses
+                    )
+                })
+                .collect();
+            epd.push_str(&format!(
+                "{} bm {}; c0 \"{}\";\n",
+                position.epd,
+                sans.join(" "),
+                statistics.join(", ")
+            ));
+        }
+        epd
+    }
+}
+
+fn epd_fields(board_state: &BoardState) -> String {
+    let fen = board_state.to_fen();
+    fen.split_whitespace()
+        .take(4)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::epd::parse_epd;
+
+    fn book_builder(games: &[&str]) -> BookBuilder {
+        let mut book_builder = BookBuilder::new();
+        for game in games {
+            assert!(book_builder.add_game(&game.parse().unwrap()));
+        }
+        book_builder
+    }
+
+    #[test]
+    fn results_are_counted_for_the_side_making_the_move() {
+        let book_builder = book_builder(&[
+            "1. e4 e5 2. Nf3 1-0",
+            "1. e4 c5 0-1",
+            "1. d4 d5 1/2-1/2",
+            "1. Nf3 Nf6 2. Nc3 Nc6 1-0",
+        ]);
+
+        assert_eq!(
+            book_builder.statistics(&BoardState::new_game()),
+            vec![
+                (
+                    (E2, E4).into(),
+                    MoveStatistics {
+                        wins: 1,
+                        draws: 0,
+                        losses: 1
+                    }
+                ),
+                (
+                    (D2, D4).into(),
+                    MoveStatistics {
+                        wins: 0,
+                        draws: 1,
+                        losses: 0
+                    }
+                ),
+                (
+                    (G1, F3).into(),
+                    MoveStatistics {
+                        wins: 1,
+                        draws: 0,
+                        losses: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn transpositions_share_statistics() {
+        let book_builder =
+            book_builder(&["1. Nf3 Nf6 2. Nc3 Nc6 1-0", "1. Nc3 Nf6 2. Nf3 Nc6 0-1"]);
+        let mut board_state = BoardState::new_game();
+        for mov in [(G1, F3), (G8, F6), (B1, C3)] {
+            board_state.make_move(mov.into());
+        }
+
+        assert_eq!(
+            book_builder.statistics(&board_state),
+            vec![(
+                (B8, C6).into(),
+                MoveStatistics {
+                    wins: 1,
+                    draws: 0,
+                    losses: 1
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn games_without_result_and_moves_past_max_ply_are_ignored() {
+        let mut book_builder = book_builder(&["1. e4 e5 2. Nf3 Nc6 1-0"]);
+        book_builder.max_ply = 2;
+        book_builder.add_game(&"1. d4 d5 2. c4 e6 1-0".parse().unwrap());
+
+        assert!(!book_builder.add_game(&"1. c4 *".parse().unwrap()));
+        // Four positions from the first game, and only the position after 1. d4 is new in the second
+        assert_eq!(book_builder.position_count(), 5);
+    }
+
+    #[test]
+    fn polyglot_book_contains_moves_passing_the_filters() {
+        let mut book_builder = book_builder(&[
+            "1. e4 e5 1-0",
+            "1. e4 c5 1/2-1/2",
+            "1. d4 d5 0-1",
+            "1. d4 Nf6 1-0",
+            "1. c4 e5 1-0",
+        ]);
+        book_builder.min_count = 2;
+        book_builder.min_score = 0.5;
+
+        let book = book_builder.to_polyglot();
+
+        assert_eq!(book.len(), 2);
+        assert_eq!(
+            book.moves(&BoardState::new_game()),
+            vec![((E2, E4).into(), 3), ((D2, D4).into(), 2)]
+        );
+    }
+
+    #[test]
+    fn epd_book_lists_moves_and_statistics() {
+        let mut book_builder = BookBuilder::new();
+        book_builder.max_ply = 1;
+        for game in ["1. e4 e5 1-0", "1. e4 c5 0-1", "1. d4 d5 1-0"] {
+            book_builder.add_game(&game.parse().unwrap());
+        }
+
+        let epd = book_builder.to_epd();
+
+        assert_eq!(
+            epd,
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - bm e4 d4; c0 \"e4 +1 =0 -1, d4 +1 =0 -0\";\n"
+        );
+        let record = parse_epd(epd.trim()).unwrap();
+        assert_eq!(record.best_moves, vec![(E2, E4).into(), (D2, D4).into()]);
+    }
+}
@@ -20,6 +20,20 @@ pub const RANK_7: usize = 8;
 pub const RANK_8: usize = 9;
 pub const WHITE_STARTING_BITBOARD: BitBoard = BitBoard(0xFFFF);
 pub const BLACK_STARTING_BITBOARD: BitBoard = BitBoard(WHITE_STARTING_BITBOARD.0 << 48);
+// a1, c1, ..., b2, d2, ...
+pub const DARK_SQUARES: BitBoard = BitBoard(0xAA55_AA55_AA55_AA55);
+// The eval of being checkmated, before it is adjusted for the distance to the mate
+pub const MATE_SCORE: i32 = -(i32::MIN / 2);
+// Evals at least this large in magnitude are forced mates
+pub const MATE_THRESHOLD: i32 = MATE_SCORE - 1000;
+// The eval of a tablebase win, before it is adjusted for the distance. Above any material eval
+// and far below the mate scores.
+pub const TABLEBASE_WIN_SCORE: i32 = 20_000;
+// Evals at least this large in magnitude are tablebase wins or forced mates
+pub const TABLEBASE_THRESHOLD: i32 = TABLEBASE_WIN_SCORE - 1000;
+// The eval of an endgame known to be won, like KRK or a winning KPK position. Above any material
+// eval and below the tablebase wins.
+pub const KNOWN_WIN_SCORE: i32 = 10_000;
 pub const STARTING_FEN_STRING: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
 pub const A1: ChessCell = ChessCell(RANK_1, A_FILE);
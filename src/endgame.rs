@@ -0,0 +1,427 @@
+use std::sync::OnceLock;
+
+use crate::board_elements::Piece;
+use crate::board_elements::PieceColor;
+use crate::board_elements::PieceColor::*;
+use crate::board_elements::PieceKind;
+use crate::board_elements::PieceKind::*;
+use crate::board_state::BoardState;
+use crate::constants::KNOWN_WIN_SCORE;
+
+// Endgames the piece-square tables get wrong, each with an evaluator of its own. They are
+// picked by the material of both sides, the stronger side given first.
+const ENDGAMES: [Endgame; 9] = [
+    Endgame::new("KP", "K", evaluate_kpk),
+    Endgame::new("KQ", "K", evaluate_kxk),
+    Endgame::new("KR", "K", evaluate_kxk),
+    Endgame::new("KBN", "K", evaluate_kbnk),
+    Endgame::new("KR", "KB", evaluate_krkb),
+    Endgame::new("KR", "KN", evaluate_krkn),
+    Endgame::new("KBP", "K", evaluate_wrong_rook_pawn),
+    Endgame::new("KBPP", "K", evaluate_wrong_rook_pawn),
+    Endgame::new("KBPPP", "K", evaluate_wrong_rook_pawn),
+];
+// No endgame in the table has more pieces than this
+const MAX_ENDGAME_PIECES: u32 = 6;
+
+// The number of pawns, knights, bishops, rooks and queens of one side
+type Material = [u8; 5];
+
+struct Endgame {
+    strong: Material,
+    weak: Material,
+    // Gives the eval from the point of view of the stronger side, or None to fall back on the
+    // piece-square tables
+    evaluate: fn(&BoardState, PieceColor) -> Option<i32>,
+}
+impl Endgame {
+    const fn new(
+        strong: &str,
+        weak: &str,
+        evaluate: fn(&BoardState, PieceColor) -> Option<i32>,
+    ) -> Endgame {
+        Endgame {
+            strong: material(strong),
+            weak: material(weak),
+            evaluate,
+        }
+    }
+}
+const fn material(pieces: &str) -> Material {
+    let mut material = [0; 5];
+    let bytes = pieces.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let kind = match bytes[i] {
+            b'P' => Pawn,
+            b'N' => Knight,
+            b'B' => Bishop,
+            b'R' => Rook,
+            b'Q' => Queen,
+            _ => King,
+        };
+        if !matches!(kind, King) {
+            material[kind.index()] += 1;
+        }
+        i += 1;
+    }
+    material
+}
+fn material_of(board_state: &BoardState, color: PieceColor) -> Material {
+    let mut material = [0; 5];
+    for piece in pieces_of(board_state, color) {
+        if piece.kind != King {
+            material[piece.kind.index()] += 1;
+        }
+    }
+    material
+}
+
+// The eval from the point of view of the side to move if the position is one of the known
+// endgames, None otherwise
+pub fn evaluate_endgame(board_state: &BoardState) -> Option<i32> {
+    let occupied = board_state.white_bitboard.0 | board_state.black_bitboard.0;
+    if occupied.count_ones() > MAX_ENDGAME_PIECES {
+        return None;
+    }
+    let white = material_of(board_state, White);
+    let black = material_of(board_state, Black);
+    ENDGAMES.iter().find_map(|endgame| {
+        let strong = if endgame.strong == white && endgame.weak == black {
+            White
+        } else if endgame.strong == black && endgame.weak == white {
+            Black
+        } else {
+            return None;
+        };
+        let eval = (endgame.evaluate)(board_state, strong)?;
+        Some(eval * strong.signum() * board_state.to_move.signum())
+    })
+}
+
+fn evaluate_kpk(board_state: &BoardState, strong: PieceColor) -> Option<i32> {
+    let strong_king = relative_square(king_square(board_state, strong), strong);
+    let weak_king = relative_square(king_square(board_state, strong.opposite()), strong);
+    let pawn = relative_square(squares_of(board_state, strong, Pawn)[0], strong);
+    if !kpk_is_win(strong_king, pawn, weak_king, board_state.to_move == strong) {
+        return Some(0);
+    }
+    // Pushing the pawn is progress
+    Some(KNOWN_WIN_SCORE + Piece::pawn(strong).value() + 10 * (pawn / 8) as i32)
+}
+// KQK and KRK: drive the lone king to the edge with the help of the other king
+fn evaluate_kxk(board_state: &BoardState, strong: PieceColor) -> Option<i32> {
+    let strong_king = king_square(board_state, strong);
+    let weak_king = king_square(board_state, strong.opposite());
+    let material: i32 = pieces_of(board_state, strong)
+        .filter(|piece| piece.kind != King)
+        .map(Piece::value)
+        .sum();
+    Some(KNOWN_WIN_SCORE + material + push_to_edge(weak_king) + push_close(strong_king, weak_king))
+}
+// The lone king can only be mated in a corner of the bishop's color
+fn evaluate_kbnk(board_state: &BoardState, strong: PieceColor) -> Option<i32> {
+    let strong_king = king_square(board_state, strong);
+    let weak_king = king_square(board_state, strong.opposite());
+    let bishop = squares_of(board_state, strong, Bishop)[0];
+    let corners = if is_dark_square(bishop) {
+        [0, 63]
+    } else {
+        [7, 56]
+    };
+    let corner_distance = corners
+        .iter()
+        .map(|&corner| manhattan_distance(weak_king, corner))
+        .min()
+        .unwrap();
+    Some(
+        KNOWN_WIN_SCORE
+            + Piece::bishop(strong).value()
+            + Piece::knight(strong).value()
+            + 20 * (14 - corner_distance as i32)
+            + push_close(strong_king, weak_king),
+    )
+}
+// Usually drawn, with small chances for the rook when the lone king is on the edge
+fn evaluate_krkb(board_state: &BoardState, strong: PieceColor) -> Option<i32> {
+    let weak_king = king_square(board_state, strong.opposite());
+    Some(push_to_edge(weak_king) / 2)
+}
+// Usually drawn, with better chances when the knight is cut off from its king
+fn evaluate_krkn(board_state: &BoardState, strong: PieceColor) -> Option<i32> {
+    let weak_king = king_square(board_state, strong.opposite());
+    let knight = squares_of(board_state, strong.opposite(), Knight)[0];
+    Some(push_to_edge(weak_king) / 2 + 10 * distance(weak_king, knight) as i32)
+}
+// Rook pawns with a bishop that does not control the promotion square cannot win once the lone
+// king reaches the corner
+fn evaluate_wrong_rook_pawn(board_state: &BoardState, strong: PieceColor) -> Option<i32> {
+    let pawns: Vec<usize> = squares_of(board_state, strong, Pawn)
+        .into_iter()
+        .map(|pawn| relative_square(pawn, strong))
+        .collect();
+    let file = pawns[0] % 8;
+    if (file != 0 && file != 7) || pawns.iter().any(|pawn| pawn % 8 != file) {
+        return None;
+    }
+    let promotion_square = 56 + file;
+    let bishop = relative_square(squares_of(board_state, strong, Bishop)[0], strong);
+    let weak_king = relative_square(king_square(board_state, strong.opposite()), strong);
+    if is_dark_square(bishop) != is_dark_square(promotion_square)
+        && distance(weak_king, promotion_square) <= 1
+    {
+        return Some(0);
+    }
+    None
+}
+
+fn pieces_of(board_state: &BoardState, color: PieceColor) -> impl Iterator<Item = Piece> + '_ {
+    board_state
+        .get_piece_positions(color)
+        .into_iter()
+        .filter_map(|cell| board_state.board.square(cell).piece())
+}
+fn squares_of(board_state: &BoardState, color: PieceColor, kind: PieceKind) -> Vec<usize> {
+    board_state
+        .get_piece_positions(color)
+        .into_iter()
+        .filter(|&cell| board_state.board.square(cell).piece() == Some(Piece { color, kind }))
+        .map(|cell| cell.as_index())
+        .collect()
+}
+fn king_square(board_state: &BoardState, color: PieceColor) -> usize {
+    board_state.king_location_of(color).as_index()
+}
+// The square as seen by the given side, so that its pawns always move up the board
+fn relative_square(square: usize, color: PieceColor) -> usize {
+    match color {
+        White => square,
+        Black => square ^ 56,
+    }
+}
+fn is_dark_square(square: usize) -> bool {
+    (square / 8 + square % 8).is_multiple_of(2)
+}
+// The number of king moves between two squares
+fn distance(a: usize, b: usize) -> usize {
+    let rank_distance = (a / 8).abs_diff(b / 8);
+    let file_distance = (a % 8).abs_diff(b % 8);
+    rank_distance.max(file_distance)
+}
+fn manhattan_distance(a: usize, b: usize) -> usize {
+    (a / 8).abs_diff(b / 8) + (a % 8).abs_diff(b % 8)
+}
+fn push_to_edge(square: usize) -> i32 {
+    let rank_to_edge = (square / 8).min(7 - square / 8);
+    let file_to_edge = (square % 8).min(7 - square % 8);
+    20 * (6 - (rank_to_edge + file_to_edge) as i32)
+}
+fn push_close(a: usize, b: usize) -> i32 {
+    20 * (7 - distance(a, b) as i32)
+}
+
+// The KPK bitbase, built once by retrograde analysis. Positions are stored with the pawn
+// belonging to white, on the files a to d and the ranks 2 to 7, and one bit per position tells
+// whether white wins.
+static KPK_BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+const KPK_POSITIONS: usize = 2 * 24 * 64 * 64;
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+// Whether the side with the pawn wins, with the squares seen from that side
+pub fn kpk_is_win(strong_king: usize, pawn: usize, weak_king: usize, strong_to_move: bool) -> bool {
+    let (strong_king, pawn, weak_king) = if pawn % 8 >= 4 {
+        (strong_king ^ 7, pawn ^ 7, weak_king ^ 7)
+    } else {
+        (strong_king, pawn, weak_king)
+    };
+    let to_move = if strong_to_move { White } else { Black };
+    let index = kpk_index(to_move, strong_king, weak_king, pawn);
+    let bitbase = KPK_BITBASE.get_or_init(generate_kpk_bitbase);
+    bitbase[index / 64] & (1 << (index % 64)) != 0
+}
+fn kpk_index(to_move: PieceColor, white_king: usize, black_king: usize, pawn: usize) -> usize {
+    white_king | black_king << 6 | to_move.index() << 12 | (pawn % 8) << 13 | (6 - pawn / 8) << 15
+}
+fn generate_kpk_bitbase() -> Vec<u64> {
+    let mut results: Vec<u8> = (0..KPK_POSITIONS).map(initial_kpk_result).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..KPK_POSITIONS {
+            if results[index] == UNKNOWN {
+                results[index] = classify_kpk(index, &results);
+                changed |= results[index] != UNKNOWN;
+            }
+        }
+    }
+    let mut bitbase = vec![0; KPK_POSITIONS / 64];
+    for (index, &result) in results.iter().enumerate() {
+        if result == WIN {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bitbase
+}
+fn decode_kpk_index(index: usize) -> (PieceColor, usize, usize, usize) {
+    let white_king = index & 63;
+    let black_king = (index >> 6) & 63;
+    let to_move = if (index >> 12) & 1 == 0 { White } else { Black };
+    let pawn = (6 - (index >> 15)) * 8 + ((index >> 13) & 3);
+    (to_move, white_king, black_king, pawn)
+}
+// Positions that are illegal or decided right away. Everything else is left to the iterations.
+fn initial_kpk_result(index: usize) -> u8 {
+    let (to_move, white_king, black_king, pawn) = decode_kpk_index(index);
+    let pawn_attacks =
+        |square: usize| square / 8 == pawn / 8 + 1 && (square % 8).abs_diff(pawn % 8) == 1;
+    let white_king_attacks = |square: usize| distance(white_king, square) == 1;
+    if distance(white_king, black_king) <= 1
+        || white_king == pawn
+        || black_king == pawn
+        || (to_move == White && pawn_attacks(black_king))
+    {
+        return INVALID;
+    }
+    let promotion_square = pawn + 8;
+    // The pawn promotes and the queen cannot be taken
+    if to_move == White
+        && pawn / 8 == 6
+        && white_king != promotion_square
+        && (distance(black_king, promotion_square) > 1
+            || distance(white_king, promotion_square) == 1)
+    {
+        return WIN;
+    }
+    // Stalemate, or the lone king takes the pawn
+    if to_move == Black {
+        let mut king_moves = king_moves(black_king);
+        let stalemate = king_moves
+            .clone()
+            .all(|square| white_king_attacks(square) || pawn_attacks(square));
+        if stalemate || king_moves.any(|square| square == pawn && !white_king_attacks(square)) {
+            return DRAW;
+        }
+    }
+    UNKNOWN
+}
+// White wins if any move wins, black draws if any move draws
+fn classify_kpk(index: usize, results: &[u8]) -> u8 {
+    let (to_move, white_king, black_king, pawn) = decode_kpk_index(index);
+    let (good, bad) = match to_move {
+        White => (WIN, DRAW),
+        Black => (DRAW, WIN),
+    };
+    let mut outcomes = INVALID;
+    match to_move {
+        White => {
+            for square in king_moves(white_king) {
+                outcomes |= results[kpk_index(Black, square, black_king, pawn)];
+            }
+            let push = pawn + 8;
+            if pawn / 8 < 6 {
+                outcomes |= results[kpk_index(Black, white_king, black_king, push)];
+            }
+            if pawn / 8 == 1 && push != white_king && push != black_king {
+                outcomes |= results[kpk_index(Black, white_king, black_king, push + 8)];
+            }
+        }
+        Black => {
+            for square in king_moves(black_king) {
+                outcomes |= results[kpk_index(White, white_king, square, pawn)];
+            }
+        }
+    }
+    if outcomes & good != 0 {
+        good
+    } else if outcomes & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+fn king_moves(square: usize) -> impl Iterator<Item = usize> + Clone {
+    let (rank, file) = (square / 8, square % 8);
+    (rank.saturating_sub(1)..=(rank + 1).min(7))
+        .flat_map(move |rank| {
+            (file.saturating_sub(1)..=(file + 1).min(7)).map(move |file| rank * 8 + file)
+        })
+        .filter(move |&other| other != square)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::evaluate;
+
+    fn eval_of(fen: &str) -> i32 {
+        evaluate(&BoardState::from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn king_in_front_of_the_pawn_on_the_sixth_rank_wins_whoever_moves() {
+        assert!(eval_of("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1") > KNOWN_WIN_SCORE);
+        assert!(eval_of("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1") < -KNOWN_WIN_SCORE);
+    }
+
+    #[test]
+    fn pawn_on_the_seventh_wins_only_with_the_move() {
+        assert_eq!(eval_of("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), 0);
+        assert!(eval_of("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1") > KNOWN_WIN_SCORE);
+    }
+
+    #[test]
+    fn rook_pawn_against_king_in_the_corner_is_drawn() {
+        assert_eq!(eval_of("k7/8/8/8/P7/8/8/K7 w - - 0 1"), 0);
+        assert_eq!(eval_of("8/8/8/8/8/p7/8/K3k3 b - - 0 1"), 0);
+    }
+
+    #[test]
+    fn pawn_outside_the_square_of_the_king_runs_through() {
+        assert!(eval_of("7k/8/8/P7/8/8/8/7K w - - 0 1") > KNOWN_WIN_SCORE);
+        assert!(eval_of("7K/8/8/8/p7/8/8/7k b - - 0 1") > KNOWN_WIN_SCORE);
+        assert!(eval_of("7K/8/8/8/p7/8/8/7k w - - 0 1") < -KNOWN_WIN_SCORE);
+    }
+
+    #[test]
+    fn lone_king_is_better_off_in_the_center() {
+        let on_the_edge = eval_of("7k/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let in_the_center = eval_of("8/8/8/4k3/8/8/8/R3K3 w - - 0 1");
+        assert!(in_the_center > KNOWN_WIN_SCORE);
+        assert!(on_the_edge > in_the_center);
+        assert!(eval_of("8/8/8/4k3/8/8/8/R3K3 b - - 0 1") < -KNOWN_WIN_SCORE);
+        assert!(eval_of("8/8/8/4K3/8/8/q7/4k3 w - - 0 1") < -KNOWN_WIN_SCORE);
+    }
+
+    #[test]
+    fn bishop_and_knight_mate_in_the_corner_of_the_bishops_color() {
+        // The bishop on c1 is dark squared, so a1 and h8 are the mating corners
+        let right_corner = eval_of("7k/8/8/8/8/8/8/2B1KN2 w - - 0 1");
+        let wrong_corner = eval_of("k7/8/8/8/8/8/8/2B1KN2 w - - 0 1");
+        assert!(wrong_corner > KNOWN_WIN_SCORE);
+        assert!(right_corner > wrong_corner);
+    }
+
+    #[test]
+    fn rook_against_minor_piece_is_drawish() {
+        assert!(eval_of("4k3/8/2b5/8/8/8/8/R3K3 w - - 0 1").abs() < 100);
+        assert!(eval_of("4k3/8/8/8/8/2n5/8/R3K3 b - - 0 1").abs() < 100);
+    }
+
+    #[test]
+    fn wrong_rook_pawn_is_a_draw_with_the_king_in_the_corner() {
+        // The a8 corner is light and the bishop on c1 is dark squared
+        assert_eq!(eval_of("k7/8/8/8/P7/P7/8/2B1K3 w - - 0 1"), 0);
+        assert!(eval_of("k7/8/8/8/P7/P7/8/3BK3 w - - 0 1") > 300);
+        assert!(eval_of("7k/8/8/8/P7/P7/8/2B1K3 w - - 0 1") > 300);
+    }
+
+    #[test]
+    fn search_stand_pat_uses_the_endgame_evaluators() {
+        let board_state = BoardState::from_fen("k7/8/8/8/P7/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(board_state.pov_eval(), 0);
+        assert!(board_state.eval > 0);
+    }
+}
@@ -0,0 +1,309 @@
+use std::str::FromStr;
+
+use crate::board_elements::ChessMove;
+use crate::board_state::BoardState;
+use crate::fen::FenError;
+use crate::san::SanError;
+use crate::search::{moves_to_mate, SearchResult};
+
+// EPD records have the first four FEN fields, followed by the operations
+const POSITION_FIELD_COUNT: usize = 4;
+
+// A position from an EPD test suite, with the operations the engine is judged by
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpdRecord {
+    pub board_state: BoardState,
+    // Every operation as written, in order, with quotes removed from string operands
+    pub operations: Vec<(String, Vec<String>)>,
+    // bm: the engine passes if it plays one of these
+    pub best_moves: Vec<ChessMove>,
+    // am: the engine passes if it plays none of these
+    pub avoid_moves: Vec<ChessMove>,
+    pub id: Option<String>,
+    pub comment: Option<String>,
+    // ce: the centipawn evaluation from the point of view of the side to move
+    pub centipawn_eval: Option<i32>,
+    // dm: the side to move mates in this many moves
+    pub direct_mate: Option<i32>,
+}
+impl EpdRecord {
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+    // Judges the move the engine chose, and for dm records the mate distance it found
+    pub fn judge(&self, search_result: &SearchResult) -> EpdVerdict {
+        let played_move = search_result.best_move;
+        let passed = played_move.is_some_and(|mov| {
+            (self.best_moves.is_empty() || self.best_moves.contains(&mov))
+                && !self.avoid_moves.contains(&mov)
+        });
+        EpdVerdict {
+            passed,
+            mate_distance_correct: self
+                .direct_mate
+                .map(|moves| moves_to_mate(search_result.eval) == Some(moves)),
+        }
+    }
+}
+impl FromStr for EpdRecord {
+    type Err = EpdError;
+    fn from_str(epd: &str) -> Result<Self, Self::Err> {
+        parse_epd(epd)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpdVerdict {
+    pub passed: bool,
+    // None when the record has no dm operation
+    pub mate_distance_correct: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpdError {
+    FieldCount(String),
+    InvalidFen(FenError),
+    InvalidOperation(String),
+    InvalidMove { opcode: String, error: SanError },
+    InvalidOperand { opcode: String, text: String },
+}
+impl std::fmt::Display for EpdError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EpdError::FieldCount(epd) => {
+                write!(f, "EPD record \"{epd}\" has fewer than 4 position fields")
+            }
+            EpdError::InvalidFen(error) => write!(f, "Invalid EPD position: {error}"),
+            EpdError::InvalidOperation(operation) => {
+                write!(f, "Invalid EPD operation \"{operation}\"")
+            }
+            EpdError::InvalidMove { opcode, error } => {
+                write!(f, "Invalid move in EPD operation {opcode}: {error}")
+            }
+            EpdError::InvalidOperand { opcode, text } => {
+                write!(f, "Invalid operand \"{text}\" for EPD operation {opcode}")
+            }
+        }
+    }
+}
+impl std::error::Error for EpdError {}
+
+// Parses a record such as: r1b1k2r/... w kq - bm Nxe5 Qd2; id "WAC.042";
+// The hmvc and fmvn operations set the move counters, which default to 0 and 1.
+pub fn parse_epd(epd: &str) -> Result<EpdRecord, EpdError> {
+    let epd = epd.trim();
+    let mut position_fields = Vec::new();
+    let mut rest = epd;
+    for _ in 0..POSITION_FIELD_COUNT {
+        let (field, remaining) = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .unwrap_or((rest.trim_start(), ""));
+        if field.is_empty() {
+            return Err(EpdError::FieldCount(epd.to_string()));
+        }
+        position_fields.push(field);
+        rest = remaining;
+    }
+    let operations = parse_operations(rest)?;
+
+    let operand = |opcode: &str| {
+        operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .and_then(|(_, operands)| operands.first())
+    };
+    let halfmove_clock = operand("hmvc").map_or("0", String::as_str);
+    let fullmove_number = operand("fmvn").map_or("1", String::as_str);
+    let fen = format!(
+        "{} {halfmove_clock} {fullmove_number}",
+        position_fields.join(" ")
+    );
+    let board_state = BoardState::from_fen(&fen).map_err(EpdError::InvalidFen)?;
+
+    let mut record = EpdRecord {
+        board_state,
+        operations: Vec::new(),
+        best_moves: Vec::new(),
+        avoid_moves: Vec::new(),
+        id: None,
+        comment: None,
+        centipawn_eval: None,
+        direct_mate: None,
+    };
+    for (opcode, operands) in &operations {
+        let invalid_operand = || EpdError::InvalidOperand {
+            opcode: opcode.clone(),
+            text: operands.join(" "),
+        };
+        match opcode.as_str() {
+            "bm" => record.best_moves = parse_moves(&record.board_state, opcode, operands)?,
+            "am" => record.avoid_moves = parse_moves(&record.board_state, opcode, operands)?,
+            "id" => record.id = operands.first().cloned(),
+            "c0" => record.comment = operands.first().cloned(),
+            "ce" => {
+                let eval = operands.first().and_then(|eval| eval.parse().ok());
+                record.centipawn_eval = Some(eval.ok_or_else(invalid_operand)?);
+            }
+            "dm" => {
+                let moves = operands.first().and_then(|moves| moves.parse().ok());
+                record.direct_mate = Some(moves.ok_or_else(invalid_operand)?);
+            }
+            _ => (),
+        }
+    }
+    record.operations = operations;
+    Ok(record)
+}
+
+// Splits the operations on semicolons outside of quoted strings, into an opcode and its operands
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            char if char.is_whitespace() => (),
+            ';' => {
+                let operation = std::mem::take(&mut words);
+                if let Some((opcode, operands)) = operation.split_first() {
+                    operations.push((opcode.clone(), operands.to_vec()));
+                }
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(char) => string.push(char),
+                        None => return Err(EpdError::InvalidOperation(text.trim().to_string())),
+                    }
+                }
+                words.push(string);
+            }
+            _ => {
+                let mut word = String::from(char);
+                while let Some(char) = chars.next_if(|char| !char.is_whitespace() && *char != ';') {
+                    word.push(char);
+                }
+                words.push(word);
+            }
+        }
+    }
+    // The semicolon after the last operation is sometimes left out
+    if let Some((opcode, operands)) = words.split_first() {
+        operations.push((opcode.clone(), operands.to_vec()));
+    }
+    if let Some((opcode, _)) = operations.iter().find(|(opcode, _)| {
+        !opcode
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+    }) {
+        return Err(EpdError::InvalidOperation(opcode.clone()));
+    }
+    Ok(operations)
+}
+
+// Moves are written in SAN, though some suites use long algebraic notation instead
+fn parse_moves(
+    board_state: &BoardState,
+    opcode: &str,
+    operands: &[String],
+) -> Result<Vec<ChessMove>, EpdError> {
+    operands
+        .iter()
+        .map(|operand| {
+            board_state.parse_san(operand).or_else(|error| {
+                board_state
+                    .parse_uci_move(operand)
+                    .ok_or(EpdError::InvalidMove {
+                        opcode: opcode.to_string(),
+                        error,
+                    })
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn operations_are_parsed() {
+        let record: EpdRecord = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001"; c0 "Mate; in 3"; ce 32767;"#
+            .parse()
+            .unwrap();
+
+        assert_eq!(record.best_moves, vec![(G3, G6).into()]);
+        assert_eq!(record.id.as_deref(), Some("WAC.001"));
+        assert_eq!(record.comment.as_deref(), Some("Mate; in 3"));
+        assert_eq!(record.centipawn_eval, Some(32767));
+        assert_eq!(record.operands("id"), Some(&["WAC.001".to_string()][..]));
+        assert_eq!(record.board_state.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn several_best_and_avoid_moves_are_allowed() {
+        let record =
+            parse_epd("4k3/8/8/8/8/8/4P3/4K3 w - - bm e4 e3; am Kd1 Kf1; hmvc 7; fmvn 40").unwrap();
+
+        assert_eq!(record.best_moves, vec![(E2, E4).into(), (E2, E3).into()]);
+        assert_eq!(record.avoid_moves, vec![(E1, D1).into(), (E1, F1).into()]);
+        assert_eq!(record.board_state.halfmove_clock, 7);
+        assert_eq!(record.board_state.fullmove_number, 40);
+    }
+
+    #[test]
+    fn verdict_checks_best_move_and_mate_distance() {
+        let record = parse_epd("7k/8/6K1/8/8/8/Q7/8 w - - bm Qa8#; dm 1;").unwrap();
+        let mating_result = SearchResult {
+            best_move: Some((A2, A8).into()),
+            eval: MATE_SCORE - 1,
+            ..Default::default()
+        };
+        let other_result = SearchResult {
+            best_move: Some((A2, B2).into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            record.judge(&mating_result),
+            EpdVerdict {
+                passed: true,
+                mate_distance_correct: Some(true)
+            }
+        );
+        assert_eq!(
+            record.judge(&other_result),
+            EpdVerdict {
+                passed: false,
+                mate_distance_correct: Some(false)
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_records_are_rejected() {
+        assert!(matches!(
+            parse_epd("4k3/8/8/8/8/8/4P3/4K3 w -"),
+            Err(EpdError::FieldCount(_))
+        ));
+        assert!(matches!(
+            parse_epd("4k3/8/8/8/8/8/4P3/4K3 w - - bm e5;"),
+            Err(EpdError::InvalidMove { .. })
+        ));
+        assert!(matches!(
+            parse_epd("4k3/8/8/8/8/8/4P3/4K3 w - - dm two;"),
+            Err(EpdError::InvalidOperand { .. })
+        ));
+        assert!(matches!(
+            parse_epd("4k3/8/8/8/8/9/4P3/4K3 w - - bm e4;"),
+            Err(EpdError::InvalidFen(_))
+        ));
+    }
+}
@@ -2,7 +2,14 @@ use crate::board_elements::Piece;
 use crate::board_elements::PieceColor;
 use crate::board_elements::PieceColor::*;
 use crate::board_state::BoardState;
+use crate::endgame::evaluate_endgame;
+// The eval from the point of view of the side to move, from the endgame evaluators when one of
+// them knows the position
 pub fn evaluate(board_state: &BoardState) -> i32 {
+    evaluate_endgame(board_state).unwrap_or_else(|| piece_square_eval(board_state))
+}
+// Material and piece-square tables only, which is what the incremental eval keeps track of
+pub fn piece_square_eval(board_state: &BoardState) -> i32 {
     let mut evaluation: i32 = 0;
     evaluation += get_player_eval(board_state, White);
     evaluation -= get_player_eval(board_state, Black);
@@ -5,53 +5,179 @@ use crate::board_elements::ChessCell;
 use crate::board_elements::Piece;
 use crate::board_elements::PieceColor;
 use crate::board_elements::PieceColor::{Black, White};
+use crate::board_elements::PieceKind::Pawn;
 use crate::board_elements::Square;
 use crate::chess_board::ChessBoard;
 use crate::constants::*;
-pub fn board_from_fen(fen_board: &str) -> Result<ChessBoard, &str> {
+pub const PIECE_PLACEMENT_FIELD: usize = 0;
+pub const SIDE_TO_MOVE_FIELD: usize = 1;
+pub const CASTLING_RIGHTS_FIELD: usize = 2;
+pub const EN_PASSANT_FIELD: usize = 3;
+pub const HALFMOVE_CLOCK_FIELD: usize = 4;
+pub const FULLMOVE_NUMBER_FIELD: usize = 5;
+const FIELD_NAMES: [&str; 6] = [
+    "piece placement",
+    "side to move",
+    "castling rights",
+    "en passant square",
+    "halfmove clock",
+    "fullmove number",
+];
+
+// The reason a FEN string was rejected. Apart from FieldCount, every variant holds the index of the
+// offending field, counting from 0 for the piece placement, and the text of that field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    FieldCount { found: usize, text: String },
+    InvalidPiece { field: usize, text: String },
+    RankCount { field: usize, text: String },
+    RankLength { field: usize, text: String },
+    InvalidSideToMove { field: usize, text: String },
+    InvalidCastlingRights { field: usize, text: String },
+    InvalidEnPassantSquare { field: usize, text: String },
+    InvalidMoveCounter { field: usize, text: String },
+    KingCount { field: usize, text: String },
+    PawnOnBackRank { field: usize, text: String },
+    SideNotToMoveInCheck { field: usize, text: String },
+    CastlingRightsWithoutPieces { field: usize, text: String },
+    ImpossibleEnPassantSquare { field: usize, text: String },
+}
+impl FenError {
+    // The index of the offending field, or None if the FEN could not be split into fields
+    pub fn field(&self) -> Option<usize> {
+        match self {
+            FenError::FieldCount { .. } => None,
+            FenError::InvalidPiece { field, .. }
+            | FenError::RankCount { field, .. }
+            | FenError::RankLength { field, .. }
+            | FenError::InvalidSideToMove { field, .. }
+            | FenError::InvalidCastlingRights { field, .. }
+            | FenError::InvalidEnPassantSquare { field, .. }
+            | FenError::InvalidMoveCounter { field, .. }
+            | FenError::KingCount { field, .. }
+            | FenError::PawnOnBackRank { field, .. }
+            | FenError::SideNotToMoveInCheck { field, .. }
+            | FenError::CastlingRightsWithoutPieces { field, .. }
+            | FenError::ImpossibleEnPassantSquare { field, .. } => Some(*field),
+        }
+    }
+    // The offending text, which is the whole FEN for FieldCount
+    pub fn text(&self) -> &str {
+        match self {
+            FenError::FieldCount { text, .. }
+            | FenError::InvalidPiece { text, .. }
+            | FenError::RankCount { text, .. }
+            | FenError::RankLength { text, .. }
+            | FenError::InvalidSideToMove { text, .. }
+            | FenError::InvalidCastlingRights { text, .. }
+            | FenError::InvalidEnPassantSquare { text, .. }
+            | FenError::InvalidMoveCounter { text, .. }
+            | FenError::KingCount { text, .. }
+            | FenError::PawnOnBackRank { text, .. }
+            | FenError::SideNotToMoveInCheck { text, .. }
+            | FenError::CastlingRightsWithoutPieces { text, .. }
+            | FenError::ImpossibleEnPassantSquare { text, .. } => text,
+        }
+    }
+    fn cause(&self) -> &'static str {
+        match self {
+            FenError::FieldCount { .. } => "FEN must have 6 fields",
+            FenError::InvalidPiece { .. } => {
+                "character is not a chess piece or a digit from 1 to 8"
+            }
+            FenError::RankCount { .. } => "piece placement must have 8 ranks",
+            FenError::RankLength { .. } => "rank does not have 8 squares",
+            FenError::InvalidSideToMove { .. } => "side to move must be w or b",
+            FenError::InvalidCastlingRights { .. } => {
+                "castling rights must be - or distinct letters from KQkq"
+            }
+            FenError::InvalidEnPassantSquare { .. } => "en passant square must be - or a square",
+            FenError::InvalidMoveCounter { .. } => "move counter must be a non-negative number",
+            FenError::KingCount { .. } => "each side must have exactly one king",
+            FenError::PawnOnBackRank { .. } => "pawns cannot stand on the first or eighth rank",
+            FenError::SideNotToMoveInCheck { .. } => "the side not to move is in check",
+            FenError::CastlingRightsWithoutPieces { .. } => {
+                "castling rights require the king and rook on their starting squares"
+            }
+            FenError::ImpossibleEnPassantSquare { .. } => {
+                "en passant square does not follow a double pawn push"
+            }
+        }
+    }
+}
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.field() {
+            Some(field) => write!(
+                f,
+                "Failed to parse FEN string: Invalid {} \"{}\": {}",
+                FIELD_NAMES[field],
+                self.text(),
+                self.cause()
+            ),
+            None => write!(
+                f,
+                "Failed to parse FEN string: \"{}\": {}, found {}",
+                self.text(),
+                self.cause(),
+                match self {
+                    FenError::FieldCount { found, .. } => *found,
+                    _ => 0,
+                }
+            ),
+        }
+    }
+}
+impl std::error::Error for FenError {}
+
+pub fn board_from_fen(fen_board: &str) -> Result<ChessBoard, FenError> {
     let mut board = ChessBoard::empty();
     let fen_ranks: Vec<&str> = fen_board.split('/').collect();
 
     if fen_ranks.len() != 8 {
-        return Err("Failed to parse FEN string: piece placements did not contain 8 ranks.");
+        return Err(FenError::RankCount {
+            field: PIECE_PLACEMENT_FIELD,
+            text: fen_board.to_string(),
+        });
     }
-    let mut board_rank = RANK_1;
-    let mut board_file = A_FILE;
-    for fen_rank in fen_ranks.iter().rev() {
+    for (board_rank, fen_rank) in (RANK_1..).zip(fen_ranks.iter().rev()) {
+        let rank_length_error = || FenError::RankLength {
+            field: PIECE_PLACEMENT_FIELD,
+            text: fen_rank.to_string(),
+        };
+        let mut board_file = A_FILE;
         for square in fen_rank.chars() {
-            if square.is_ascii_digit() {
-                let skipped_squares = square.to_digit(10).unwrap() as usize;
-                if board_file + skipped_squares > 10 {
-                    return Err("Failed to parse FEN string: Rank went out of bounds");
-                }
-                board_file += skipped_squares;
-            } else {
-                let piece = Piece::try_from(square);
-                if piece.is_err() {
-                    return Err("Failed to parse FEN string: Character was not a chess piece");
+            if board_file > H_FILE {
+                return Err(rank_length_error());
+            }
+            match square.to_digit(10) {
+                Some(skipped_squares @ 1..=8) => board_file += skipped_squares as usize,
+                _ => {
+                    let piece = Piece::try_from(square).map_err(|_| FenError::InvalidPiece {
+                        field: PIECE_PLACEMENT_FIELD,
+                        text: square.to_string(),
+                    })?;
+                    *board.square_mut(ChessCell(board_rank, board_file)) = Square::Full(piece);
+                    board_file += 1;
                 }
-                let piece = piece.unwrap();
-                *board.square_mut(ChessCell(board_rank, board_file)) = Square::Full(piece);
-                board_file += 1;
             }
         }
-        if board_file != BOARD_END + 1 {
-            return Err("Failed to parse FEN string: Rank did not have length 8");
+        if board_file != H_FILE + 1 {
+            return Err(rank_length_error());
         }
-        board_rank += 1;
-        board_file = BOARD_START;
     }
     Ok(board)
 }
-pub fn castling_rights_from_fen(fen_castling_rights: &str) -> Result<CastlingRights, &'static str> {
-    if fen_castling_rights.len() > 4 {
-        return Err("Failed to parse FEN string: Castling rights string was too long");
-    }
-    if !fen_castling_rights
-        .chars()
-        .all(|c| matches!(c, 'q' | 'k' | 'Q' | 'K' | '-'))
-    {
-        return Err("Failed to parse FEN string: Castling rights contained invalid characters");
+pub fn castling_rights_from_fen(fen_castling_rights: &str) -> Result<CastlingRights, FenError> {
+    let is_valid = fen_castling_rights == "-"
+        || fen_castling_rights.chars().enumerate().all(|(index, c)| {
+            matches!(c, 'q' | 'k' | 'Q' | 'K') && !fen_castling_rights[..index].contains(c)
+        });
+    if !is_valid {
+        return Err(FenError::InvalidCastlingRights {
+            field: CASTLING_RIGHTS_FIELD,
+            text: fen_castling_rights.to_string(),
+        });
     }
     Ok(CastlingRights {
         white_king_side_castling: fen_castling_rights.contains('K'),
@@ -60,21 +186,340 @@ pub fn castling_rights_from_fen(fen_castling_rights: &str) -> Result<CastlingRig
         black_queen_side_castling: fen_castling_rights.contains('q'),
     })
 }
-pub fn to_move_from_fen(fen_to_move: &str) -> Result<PieceColor, &'static str> {
+pub fn to_move_from_fen(fen_to_move: &str) -> Result<PieceColor, FenError> {
     match fen_to_move {
         "w" => Ok(White),
         "b" => Ok(Black),
-        _ => Err("Failed to parse FEN string: player to move was not black or white"),
+        _ => Err(FenError::InvalidSideToMove {
+            field: SIDE_TO_MOVE_FIELD,
+            text: fen_to_move.to_string(),
+        }),
     }
 }
 pub fn en_passant_square_from_fen(
     fen_en_passant_square: &str,
-) -> Result<Option<ChessCell>, &'static str> {
+) -> Result<Option<ChessCell>, FenError> {
     if fen_en_passant_square == "-" {
-        Ok(None)
-    } else if ChessCell::from_str(fen_en_passant_square).is_ok() {
-        Ok(Some(ChessCell::from_str(fen_en_passant_square).unwrap()))
-    } else {
-        return Err("Failed to parse FEN string: En passant value was not valid FEN");
+        return Ok(None);
+    }
+    ChessCell::from_str(fen_en_passant_square)
+        .map(Some)
+        .map_err(|_| FenError::InvalidEnPassantSquare {
+            field: EN_PASSANT_FIELD,
+            text: fen_en_passant_square.to_string(),
+        })
+}
+// field is the index of the halfmove clock or the fullmove number
+pub fn move_counter_from_fen(fen_move_counter: &str, field: usize) -> Result<u32, FenError> {
+    fen_move_counter
+        .parse()
+        .map_err(|_| FenError::InvalidMoveCounter {
+            field,
+            text: fen_move_counter.to_string(),
+        })
+}
+// Checks that no pawn stands on the first or eighth rank
+pub fn validate_pawns(board: &ChessBoard) -> Result<(), FenError> {
+    for rank in [RANK_1, RANK_8] {
+        for file in A_FILE..=H_FILE {
+            let cell = ChessCell(rank, file);
+            if board
+                .square(cell)
+                .piece()
+                .is_some_and(|piece| piece.kind == Pawn)
+            {
+                return Err(FenError::PawnOnBackRank {
+                    field: PIECE_PLACEMENT_FIELD,
+                    text: cell.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+// Checks that the king and rook of every castling right are on their starting squares
+pub fn validate_castling_rights(
+    board: &ChessBoard,
+    castling_rights: CastlingRights,
+) -> Result<(), FenError> {
+    let required_pieces = [
+        (castling_rights.white_king_side_castling, 'K', E1, H1, White),
+        (
+            castling_rights.white_queen_side_castling,
+            'Q',
+            E1,
+            A1,
+            White,
+        ),
+        (castling_rights.black_king_side_castling, 'k', E8, H8, Black),
+        (
+            castling_rights.black_queen_side_castling,
+            'q',
+            E8,
+            A8,
+            Black,
+        ),
+    ];
+    for (has_right, letter, king_cell, rook_cell, color) in required_pieces {
+        let pieces_are_in_place = board.square(king_cell).piece() == Some(Piece::king(color))
+            && board.square(rook_cell).piece() == Some(Piece::rook(color));
+        if has_right && !pieces_are_in_place {
+            return Err(FenError::CastlingRightsWithoutPieces {
+                field: CASTLING_RIGHTS_FIELD,
+                text: letter.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+// Checks that the en passant square could have been passed by a pawn that just moved two squares:
+// it and the square the pawn came from are empty, and the pawn stands in front of it.
+pub fn validate_en_passant_square(
+    board: &ChessBoard,
+    to_move: PieceColor,
+    en_passant_square: Option<ChessCell>,
+) -> Result<(), FenError> {
+    let Some(en_passant_square) = en_passant_square else {
+        return Ok(());
+    };
+    let pushed_color = to_move.opposite();
+    let ChessCell(rank, file) = en_passant_square;
+    let pawn_start = ChessCell((rank as i32 - pushed_color.signum()) as usize, file);
+    let pawn_location = ChessCell((rank as i32 + pushed_color.signum()) as usize, file);
+    let is_possible = rank == pushed_color.en_passant_rank()
+        && board.square(en_passant_square).is_empty()
+        && board.square(pawn_start).is_empty()
+        && board.square(pawn_location).piece() == Some(Piece::pawn(pushed_color));
+    if !is_possible {
+        return Err(FenError::ImpossibleEnPassantSquare {
+            field: EN_PASSANT_FIELD,
+            text: en_passant_square.to_string(),
+        });
+    }
+    Ok(())
+}
+pub fn board_to_fen(board: &ChessBoard) -> String {
+    let mut fen_ranks = Vec::with_capacity(8);
+    for rank in (RANK_1..=RANK_8).rev() {
+        let mut fen_rank = String::new();
+        let mut empty_squares = 0;
+        for file in A_FILE..=H_FILE {
+            match board.square(ChessCell(rank, file)).piece() {
+                Some(piece) => {
+                    if empty_squares > 0 {
+                        fen_rank.push_str(&empty_squares.to_string());
+                        empty_squares = 0;
+                    }
+                    fen_rank.push(piece.into());
+                }
+                None => empty_squares += 1,
+            }
+        }
+        if empty_squares > 0 {
+            fen_rank.push_str(&empty_squares.to_string());
+        }
+        fen_ranks.push(fen_rank);
+    }
+    fen_ranks.join("/")
+}
+pub fn castling_rights_to_fen(castling_rights: CastlingRights) -> String {
+    let fen_castling_rights: String = [
+        (castling_rights.white_king_side_castling, 'K'),
+        (castling_rights.white_queen_side_castling, 'Q'),
+        (castling_rights.black_king_side_castling, 'k'),
+        (castling_rights.black_queen_side_castling, 'q'),
+    ]
+    .into_iter()
+    .filter(|(has_right, _)| *has_right)
+    .map(|(_, letter)| letter)
+    .collect();
+    match fen_castling_rights.is_empty() {
+        true => "-".to_string(),
+        false => fen_castling_rights,
+    }
+}
+pub fn to_move_to_fen(to_move: PieceColor) -> &'static str {
+    match to_move {
+        White => "w",
+        Black => "b",
+    }
+}
+pub fn en_passant_square_to_fen(en_passant_square: Option<ChessCell>) -> String {
+    match en_passant_square {
+        Some(en_passant_square) => en_passant_square.to_string(),
+        None => "-".to_string(),
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_state::BoardState;
+
+    fn fen_error(fen: &str) -> FenError {
+        BoardState::from_fen(fen).unwrap_err()
+    }
+
+    #[test]
+    fn malformed_fields_are_reported_with_field_and_text() {
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+                FenError::FieldCount {
+                    found: 4,
+                    text: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::RankCount {
+                    field: 0,
+                    text: "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::InvalidPiece {
+                    field: 0,
+                    text: "9".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::InvalidPiece {
+                    field: 0,
+                    text: "x".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::RankLength {
+                    field: 0,
+                    text: "ppppppppp".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                FenError::InvalidSideToMove {
+                    field: 1,
+                    text: "x".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1",
+                FenError::InvalidCastlingRights {
+                    field: 2,
+                    text: "KKkq".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+                FenError::InvalidEnPassantSquare {
+                    field: 3,
+                    text: "e9".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1",
+                FenError::InvalidMoveCounter {
+                    field: 4,
+                    text: "-1".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 one",
+                FenError::InvalidMoveCounter {
+                    field: 5,
+                    text: "one".to_string(),
+                },
+            ),
+        ];
+        for (fen, expected_error) in cases {
+            assert_eq!(fen_error(fen), expected_error, "{fen}");
+        }
+    }
+
+    #[test]
+    fn impossible_positions_are_rejected() {
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKKNR w kq - 0 1",
+                FenError::KingCount {
+                    field: 0,
+                    text: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKKNR".to_string(),
+                },
+            ),
+            (
+                "8/8/8/4k3/8/8/8/8 w - - 0 1",
+                FenError::KingCount {
+                    field: 0,
+                    text: "8/8/8/4k3/8/8/8/8".to_string(),
+                },
+            ),
+            (
+                "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::PawnOnBackRank {
+                    field: 0,
+                    text: "a8".to_string(),
+                },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2p b - - 0 1",
+                FenError::PawnOnBackRank {
+                    field: 0,
+                    text: "h1".to_string(),
+                },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R3K2R w KQk - 0 1",
+                FenError::CastlingRightsWithoutPieces {
+                    field: 2,
+                    text: "k".to_string(),
+                },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R4K1R w K - 0 1",
+                FenError::CastlingRightsWithoutPieces {
+                    field: 2,
+                    text: "K".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e6 0 1",
+                FenError::ImpossibleEnPassantSquare {
+                    field: 3,
+                    text: "e6".to_string(),
+                },
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1",
+                FenError::ImpossibleEnPassantSquare {
+                    field: 3,
+                    text: "e3".to_string(),
+                },
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2r b - - 0 1",
+                FenError::SideNotToMoveInCheck {
+                    field: 1,
+                    text: "b".to_string(),
+                },
+            ),
+        ];
+        for (fen, expected_error) in cases {
+            assert_eq!(fen_error(fen), expected_error, "{fen}");
+        }
+    }
+
+    #[test]
+    fn fen_error_is_a_displayable_error() {
+        let error: Box<dyn std::error::Error> = Box::new(fen_error(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 one",
+        ));
+
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse FEN string: Invalid fullmove number \"one\": \
+             move counter must be a non-negative number"
+        );
     }
 }
@@ -0,0 +1,53 @@
+mod attacks;
+mod board_elements;
+mod board_state;
+mod book;
+mod book_builder;
+mod chess_board;
+mod constants;
+mod endgame;
+mod epd;
+mod evaluation;
+mod fen;
+mod move_generation;
+mod move_scoring;
+mod perft;
+mod pgn;
+mod san;
+mod search;
+mod syzygy;
+mod transposition_table;
+mod uci;
+mod xboard;
+mod zobrist_hashing;
+
+pub use board_elements::{
+    BitBoard, CastlingRights, CastlingType, ChessCell, ChessMove, DrawReason, GameResult, Piece,
+    PieceColor, PieceKind, Square,
+};
+pub use board_state::BoardState;
+pub use book::{BookEntry, BookSelection, OpeningBook};
+pub use book_builder::BookBuilder;
+pub use chess_board::ChessBoard;
+pub use constants::{
+    A1, A2, A3, A4, A5, A6, A7, A8, B1, B2, B3, B4, B5, B6, B7, B8, C1, C2, C3, C4, C5, C6, C7, C8,
+    D1, D2, D3, D4, D5, D6, D7, D8, E1, E2, E3, E4, E5, E6, E7, E8, F1, F2, F3, F4, F5, F6, F7, F8,
+    G1, G2, G3, G4, G5, G6, G7, G8, H1, H2, H3, H4, H5, H6, H7, H8, STARTING_FEN_STRING,
+};
+pub use epd::{parse_epd, EpdError, EpdRecord, EpdVerdict};
+pub use evaluation::evaluate;
+pub use fen::FenError;
+pub use move_generation::generate_moves;
+pub use perft::{divide, perft};
+pub use pgn::{
+    parse_game, search_comment, PgnError, PgnGame, PgnMove, PgnReader, PgnResult, PgnVariation,
+};
+pub use san::{move_to_san, parse_san, SanError};
+pub use search::{
+    moves_to_mate, search, search_with_limits, search_with_tablebase, Clock, SearchLimits,
+    SearchResult, DEFAULT_DEPTH,
+};
+pub use syzygy::{Tablebase, Wdl};
+pub use transposition_table::TranspositionTable;
+pub use uci::{pv_to_uci, run as run_uci, ENGINE_NAME};
+pub use xboard::run as run_xboard;
@@ -1,55 +1,207 @@
-use crate::{board_state::BoardState, move_generation::generate_moves};
-pub mod board_elements;
-pub mod board_state;
-pub mod constants;
-pub mod fen;
-pub mod move_generation;
-use board_elements::PieceColor;
-pub mod chess_board;
-pub mod evaluation;
-pub mod move_scoring;
-mod ray_attacks;
-pub mod search;
-mod zobrist_hashing;
-const DEPTH: u8 = 4;
-use crate::board_elements::PieceColor::*;
+use mango_chess::{
+    divide, moves_to_mate, parse_epd, perft, run_uci, run_xboard, search_with_limits, BoardState,
+    BookBuilder, PgnReader, SearchLimits, TranspositionTable, DEFAULT_DEPTH, STARTING_FEN_STRING,
+};
+use std::io::{BufRead, Read};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
 
-pub enum GameResult {
-    Winner(PieceColor),
-    Draw,
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("--uci") => run_uci(std::io::stdin().lock(), std::io::stdout()),
+        Some("--xboard") => run_xboard(std::io::stdin().lock(), std::io::stdout()),
+        Some(command @ ("perft" | "divide")) => {
+            if let Err(error) = run_perft(command, &args[2..]) {
+                eprintln!("{error}");
+                eprintln!("Usage: mango_chess {command} <depth> [fen]");
+                std::process::exit(1);
+            }
+        }
+        Some("epd") => {
+            if let Err(error) = run_epd_suite(&args[2..]) {
+                eprintln!("{error}");
+                eprintln!("Usage: mango_chess epd <file> [depth <depth> | movetime <ms>]");
+                std::process::exit(1);
+            }
+        }
+        Some("makebook") => {
+            if let Err(error) = run_make_book(&args[2..]) {
+                eprintln!("{error}");
+                eprintln!(
+                    "Usage: mango_chess makebook <pgn file> <book.bin | book.epd> \
+                     [plies <plies>] [min-count <games>] [min-score <0 to 1>]"
+                );
+                std::process::exit(1);
+            }
+        }
+        _ => run_detected_protocol(),
+    }
 }
-impl std::fmt::Display for GameResult {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            GameResult::Winner(game_winner) => {
-                write!(f, "Checkmate! {game_winner} takes the crown.")
+// Runs "makebook <pgn file> <output> [options]", building a book from the games in the PGN file.
+// The book is written as EPD if the output ends in .epd or .txt, and as a Polyglot book otherwise.
+fn run_make_book(args: &[String]) -> Result<(), String> {
+    let [pgn_path, output_path, options @ ..] = args else {
+        return Err("Expected a PGN file and an output file".to_string());
+    };
+    let mut book_builder = BookBuilder::new();
+    for option in options.chunks(2) {
+        let [name, value] = option else {
+            return Err(format!("Expected a value for {}", option[0]));
+        };
+        let invalid_value = || format!("Invalid value for {name}: {value}");
+        match name.as_str() {
+            "plies" => book_builder.max_ply = value.parse().map_err(|_| invalid_value())?,
+            "min-count" => book_builder.min_count = value.parse().map_err(|_| invalid_value())?,
+            "min-score" => book_builder.min_score = value.parse().map_err(|_| invalid_value())?,
+            _ => return Err(format!("Unknown option: {name}")),
+        }
+    }
+
+    let pgn_file = std::fs::File::open(pgn_path).map_err(|error| format!("{pgn_path}: {error}"))?;
+    let (mut games, mut skipped_games) = (0, 0);
+    for (game_index, game) in PgnReader::new(std::io::BufReader::new(pgn_file)).enumerate() {
+        match game {
+            Ok(game) if book_builder.add_game(&game) => games += 1,
+            Ok(_) => skipped_games += 1,
+            Err(error) => {
+                eprintln!("Skipping game {}: {error}", game_index + 1);
+                skipped_games += 1;
             }
-            GameResult::Draw => write!(f, "The game ends in a draw."),
         }
     }
+
+    let is_epd = output_path.ends_with(".epd") || output_path.ends_with(".txt");
+    let written = match is_epd {
+        true => std::fs::write(output_path, book_builder.to_epd()),
+        false => book_builder.to_polyglot().save(output_path),
+    };
+    written.map_err(|error| format!("{output_path}: {error}"))?;
+    println!(
+        "Built {output_path} from {games} games ({skipped_games} skipped), {} positions seen",
+        book_builder.position_count()
+    );
+    Ok(())
 }
-fn main() {
-    let mut board_state = BoardState::new_game();
-    let mut moves = 0;
-    loop {
-        println!("{}", board_state.board);
-        if generate_moves(&board_state).is_empty() {
-            let game_winner = board_state.get_game_winner();
-            println!("{game_winner}");
-            return;
-        }
-        let (best_eval, best_move) = search::search(&board_state, DEPTH);
-        moves += 1;
-        let best_move = best_move.unwrap();
-        let absolute_eval = best_eval
-            * match board_state.to_move {
-                White => 1,
-                Black => -1,
-            };
-        println!(
-            "Evaluation is {} with the move {}. Total moves: {}",
-            absolute_eval, best_move, moves
+// Runs "epd <file> [depth <depth> | movetime <ms>]", searching every position of the suite and
+// reporting whether the engine found the best move, and for dm records the right mate distance
+fn run_epd_suite(args: &[String]) -> Result<(), String> {
+    let path = args.first().ok_or("Expected an EPD file")?;
+    let limits = match (args.get(1).map(String::as_str), args.get(2)) {
+        (None, _) => SearchLimits::depth(DEFAULT_DEPTH),
+        (Some("depth"), Some(depth)) => {
+            SearchLimits::depth(depth.parse().map_err(|_| "Expected a depth")?)
+        }
+        (Some("movetime"), Some(movetime)) => SearchLimits::movetime(Duration::from_millis(
+            movetime.parse().map_err(|_| "Expected a movetime")?,
+        )),
+        _ => return Err("Expected a depth or movetime limit".to_string()),
+    };
+    let suite = std::fs::read_to_string(path).map_err(|error| format!("{path}: {error}"))?;
+
+    let stop = AtomicBool::new(false);
+    let mut transposition_table = TranspositionTable::default();
+    let (mut positions, mut skipped, mut passed) = (0, 0, 0);
+    let (mut mate_positions, mut mates_correct) = (0, 0);
+    for (line_index, line) in suite.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = match parse_epd(line) {
+            Ok(record) => record,
+            Err(error) => {
+                println!("line {}: skipped, {error}", line_index + 1);
+                skipped += 1;
+                continue;
+            }
+        };
+        positions += 1;
+        let name = record
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("line {}", line_index + 1));
+        transposition_table.clear();
+        let result = search_with_limits(
+            &record.board_state,
+            &limits,
+            &stop,
+            &mut transposition_table,
+            |_| (),
+        );
+        let verdict = record.judge(&result);
+        let played = result.best_move.map_or("none".to_string(), |mov| {
+            record.board_state.move_to_san(mov)
+        });
+        let mut report = format!(
+            "{name}: {} with {played}",
+            if verdict.passed { "pass" } else { "FAIL" }
         );
-        board_state.make_move(best_move);
+        if let Some(mate_distance_correct) = verdict.mate_distance_correct {
+            mate_positions += 1;
+            mates_correct += usize::from(mate_distance_correct);
+            let found_mate = moves_to_mate(result.eval)
+                .map_or("no mate".to_string(), |moves| format!("mate {moves}"));
+            report.push_str(&format!(
+                ", {found_mate} (expected mate {})",
+                record.direct_mate.unwrap()
+            ));
+        }
+        passed += usize::from(verdict.passed);
+        println!("{report}");
+    }
+    println!();
+    println!("Passed {passed}/{positions}");
+    if skipped > 0 {
+        println!("Skipped {skipped} lines that could not be parsed");
+    }
+    if mate_positions > 0 {
+        println!("Mate distance correct {mates_correct}/{mate_positions}");
+    }
+    Ok(())
+}
+// Runs "perft <depth> [fen]" or "divide <depth> [fen]", starting from the initial position if no FEN is given
+fn run_perft(command: &str, args: &[String]) -> Result<(), String> {
+    let depth: u8 = args
+        .first()
+        .and_then(|depth| depth.parse().ok())
+        .ok_or("Expected a depth")?;
+    let fen = match args.len() {
+        1 => STARTING_FEN_STRING.to_string(),
+        _ => args[1..].join(" "),
+    };
+    let board_state = BoardState::from_fen(&fen).map_err(|error| error.to_string())?;
+    let start_time = Instant::now();
+    let nodes = match command {
+        "divide" => {
+            let divided = divide(&board_state, depth);
+            for (mov, nodes) in &divided {
+                println!("{}: {nodes}", mov.to_uci());
+            }
+            divided.iter().map(|(_, nodes)| nodes).sum()
+        }
+        _ => perft(&board_state, depth),
+    };
+    let elapsed = start_time.elapsed();
+    println!();
+    println!("Nodes searched: {nodes}");
+    println!(
+        "Time: {} ms ({:.0} nps)",
+        elapsed.as_millis(),
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
+// Picks the protocol from the first command the GUI sends, which is "xboard" for CECP and "uci" for UCI
+fn run_detected_protocol() {
+    let mut stdin = std::io::stdin().lock();
+    let mut first_line = String::new();
+    if stdin.read_line(&mut first_line).is_err() {
+        return;
+    }
+    let is_xboard = first_line.trim() == "xboard";
+    let input = std::io::Cursor::new(first_line).chain(stdin);
+    match is_xboard {
+        true => run_xboard(input, std::io::stdout()),
+        false => run_uci(input, std::io::stdout()),
     }
 }
@@ -1,25 +1,109 @@
+use crate::attacks::between;
+use crate::attacks::bishop_attacks;
+use crate::attacks::king_attacks;
+use crate::attacks::knight_attacks;
+use crate::attacks::pawn_attacks;
+use crate::attacks::queen_attacks;
+use crate::attacks::rook_attacks;
+use crate::board_elements::BitBoard;
 use crate::board_elements::ChessCell;
 use crate::board_elements::ChessMove;
 use crate::board_elements::Piece;
 use crate::board_elements::PieceColor;
 use crate::board_elements::PieceKind::*;
-use crate::board_elements::Square;
 use crate::board_state::BoardState;
-use crate::ray_attacks::KING_RAY_ATTACKS;
-use crate::ray_attacks::KNIGHT_RAY_ATTACKS;
-const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
-const ROOK_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+// Generates the legal moves directly, without trying them on the board. The pieces giving check
+// and the pieces pinned to the king are found once, and every other move is restricted to the
+// squares that keep the king safe.
 pub fn generate_moves(board_state: &BoardState) -> Vec<ChessMove> {
-    let mut valid_moves: Vec<ChessMove> = Vec::with_capacity(16);
-    let potential_moves = generate_pseudo_moves_for_player(board_state);
-    for mov in potential_moves {
-        let mut copy_board = board_state.clone();
-        copy_board.make_move(mov);
-        if copy_board.is_valid_move() {
-            valid_moves.push(mov);
+    let color = board_state.to_move;
+    let enemy = color.opposite();
+    let king = board_state.king_location_of(color);
+    let king_index = king.as_index();
+    let mut legal_moves: Vec<ChessMove> = Vec::with_capacity(48);
+
+    // The king cannot hide from a slider on the squares behind itself, so it is taken off the
+    // board when looking at its destinations
+    let without_king = BitBoard(board_state.occupied().0 & !(1 << king_index));
+    for dest in not_own(board_state, color, king_attacks(king_index)) {
+        if board_state
+            .attackers_to_with_occupancy(dest, enemy, without_king)
+            .0
+            == 0
+        {
+            legal_moves.push((king, dest).into());
+        }
+    }
+
+    let checkers = board_state.checkers().0;
+    // Only the king can get out of a double check
+    if checkers.count_ones() > 1 {
+        return legal_moves;
+    }
+    // Out of a single check, the other pieces must take the checker or block it
+    let check_mask = match checkers {
+        0 => !0,
+        _ => checkers | between(king_index, checkers.trailing_zeros() as usize).0,
+    };
+    let pin_rays = pin_rays(board_state, king_index);
+    let mut piece_moves: Vec<ChessMove> = Vec::with_capacity(28);
+    for kind in [Pawn, Knight, Bishop, Rook, Queen] {
+        let piece = Piece { color, kind };
+        for position in board_state.pieces(color, kind) {
+            let allowed = check_mask & pin_rays[position.as_index()];
+            generate_pseudo_moves_for_piece(piece, board_state, position, &mut piece_moves);
+            legal_moves.extend(
+                piece_moves
+                    .drain(..)
+                    .filter(|mov| allowed & (1 << mov.dest.as_index()) != 0),
+            );
         }
     }
-    valid_moves
+
+    if checkers == 0 {
+        legal_moves.extend(generate_castling_moves(board_state));
+    }
+    for mov in generate_en_passant_moves(board_state) {
+        if en_passant_is_legal(board_state, mov) {
+            legal_moves.push(mov);
+        }
+    }
+    legal_moves
+}
+// The squares a piece on each square may move to without exposing its own king. A piece pinned
+// to the king may only move between the king and the pinning piece, or capture the pinner.
+fn pin_rays(board_state: &BoardState, king: usize) -> [u64; 64] {
+    let color = board_state.to_move;
+    let enemy = color.opposite();
+    let enemies = board_state.color_bitboard(enemy);
+    let rooks_and_queens = board_state.pieces(enemy, Rook).0 | board_state.pieces(enemy, Queen).0;
+    let bishops_and_queens =
+        board_state.pieces(enemy, Bishop).0 | board_state.pieces(enemy, Queen).0;
+    // The enemy sliders that would attack the king if only enemy pieces could block them
+    let snipers = (rook_attacks(king, enemies).0 & rooks_and_queens)
+        | (bishop_attacks(king, enemies).0 & bishops_and_queens);
+
+    let mut pin_rays = [!0; 64];
+    for sniper in BitBoard(snipers) {
+        let ray = between(king, sniper.as_index()).0;
+        let blockers = ray & board_state.occupied().0;
+        if blockers.count_ones() == 1 && blockers & board_state.color_bitboard(color).0 != 0 {
+            pin_rays[blockers.trailing_zeros() as usize] = ray | 1 << sniper.as_index();
+        }
+    }
+    pin_rays
+}
+// En passant takes two pawns off the same rank at once, which can uncover an attack on the king
+// that no pin accounts for, so the occupancy after the capture is checked directly
+fn en_passant_is_legal(board_state: &BoardState, mov: ChessMove) -> bool {
+    let color = board_state.to_move;
+    let captured = 1 << ChessCell(mov.start.0, mov.dest.1).as_index();
+    let occupied = (board_state.occupied().0 & !(1 << mov.start.as_index()) & !captured)
+        | 1 << mov.dest.as_index();
+    let king = board_state.king_location_of(color);
+    let attackers =
+        board_state.attackers_to_with_occupancy(king, color.opposite(), BitBoard(occupied));
+    attackers.0 & !captured == 0
 }
 pub fn generate_castling_moves(board_state: &BoardState) -> Vec<ChessMove> {
     let mut castling_moves = Vec::new();
@@ -30,9 +114,19 @@ pub fn generate_castling_moves(board_state: &BoardState) -> Vec<ChessMove> {
         let step_two_cell = ChessCell(king_rank, (king_file as i8 + direction * 2) as usize);
         let step_one = board_state.board.square(step_one_cell);
         let step_two = board_state.board.square(step_two_cell);
+        // When castling queen side the rook also passes the b-file, which must be empty but may be attacked
+        let rook_path_is_clear = direction == 1
+            || board_state
+                .board
+                .square(ChessCell(
+                    king_rank,
+                    (king_file as i8 + direction * 3) as usize,
+                ))
+                .is_empty();
 
         if step_one.is_empty()
             && step_two.is_empty()
+            && rook_path_is_clear
             && !board_state.square_is_attacked(
                 board_state.king_location_of(board_state.to_move),
                 board_state.to_move.opposite(),
@@ -45,18 +139,40 @@ pub fn generate_castling_moves(board_state: &BoardState) -> Vec<ChessMove> {
     }
     castling_moves
 }
+#[cfg(test)]
 pub fn generate_pseudo_moves_for_player(board_state: &BoardState) -> Vec<ChessMove> {
-    let piece_positions = board_state.get_piece_positions(board_state.to_move);
-    let mut potential_moves: Vec<ChessMove> = Vec::with_capacity(16);
-
-    for position in piece_positions {
-        let piece = board_state.board.square(position).piece().unwrap();
-        generate_pseudo_moves_for_piece(piece, board_state, position, &mut potential_moves);
-    }
+    let mut potential_moves = generate_piece_moves(board_state);
     potential_moves.extend(generate_castling_moves(board_state));
     potential_moves.extend(generate_en_passant_moves(board_state));
     potential_moves
 }
+// Generates the pseudo legal captures and promotions, the moves searched by the quiescence search
+pub fn generate_pseudo_captures_for_player(board_state: &BoardState) -> Vec<ChessMove> {
+    let mut potential_moves = generate_piece_moves(board_state);
+    potential_moves.retain(|mov| {
+        mov.promotion.is_some()
+            || board_state
+                .board
+                .square(mov.dest)
+                .is_enemy_of(board_state.to_move)
+    });
+    potential_moves.extend(generate_en_passant_moves(board_state));
+    potential_moves
+}
+// The pseudo legal moves of every piece of the side to move, without castling and en passant
+fn generate_piece_moves(board_state: &BoardState) -> Vec<ChessMove> {
+    let mut potential_moves: Vec<ChessMove> = Vec::with_capacity(32);
+    for kind in [Pawn, Knight, Bishop, Rook, Queen, King] {
+        let piece = Piece {
+            color: board_state.to_move,
+            kind,
+        };
+        for position in board_state.pieces(piece.color, kind) {
+            generate_pseudo_moves_for_piece(piece, board_state, position, &mut potential_moves);
+        }
+    }
+    potential_moves
+}
 pub fn generate_pseudo_moves_for_piece(
     piece: Piece,
     board_state: &BoardState,
@@ -80,31 +196,19 @@ fn pawn_moves(
     position: ChessCell,
     moves: &mut Vec<ChessMove>,
 ) {
-    let mut temp_moves: Vec<ChessMove> = vec![];
+    let occupied = board_state.occupied();
+    let enemies = board_state.color_bitboard(color.opposite());
+    let mut destinations = BitBoard(pawn_attacks(color, position.as_index()).0 & enemies.0);
+
     let push_direction = color.signum();
     let ChessCell(rank, file) = position;
-
-    let left_cap = ChessCell((rank as i32 + push_direction) as usize, file - 1);
-    let target = board_state.board.square(left_cap);
-    if target.is_enemy_of(color) {
-        temp_moves.push((position, left_cap).into())
-    }
-
-    let right_cap = ChessCell((rank as i32 + push_direction) as usize, file + 1);
-    let target = board_state.board.square(right_cap);
-    if target.is_enemy_of(color) {
-        temp_moves.push((position, right_cap).into())
-    }
-
     let one_forward = ChessCell((rank as i32 + push_direction) as usize, file);
-    let target = board_state.board.square(one_forward);
-    if target.is_empty() {
-        temp_moves.push((position, one_forward).into());
+    if !occupied.contains(one_forward) {
+        destinations.add_piece(one_forward.as_index());
         if rank == color.pawn_starting_rank() {
-            let two_forward = ChessCell((rank as i32 + push_direction) as usize, file);
-            let target = board_state.board.square(two_forward);
-            if target.is_empty() {
-                temp_moves.push((position, two_forward).into());
+            let two_forward = ChessCell((rank as i32 + push_direction * 2) as usize, file);
+            if !occupied.contains(two_forward) {
+                destinations.add_piece(two_forward.as_index());
             }
         }
     }
@@ -116,21 +220,18 @@ fn pawn_moves(
             Piece::rook(color),
             Piece::queen(color),
         ];
-        temp_moves = temp_moves
-            .into_iter()
-            .flat_map(|mov| {
-                promotion_pieces
-                    .iter()
-                    .map(move |promotion_piece| ChessMove {
-                        start: mov.start,
-                        dest: mov.dest,
-                        promotion: Some(*promotion_piece),
-                    })
-            })
-            .collect()
+        for dest in destinations {
+            for promotion_piece in promotion_pieces {
+                moves.push(ChessMove {
+                    start: position,
+                    dest,
+                    promotion: Some(promotion_piece),
+                });
+            }
+        }
+    } else {
+        push_moves(position, destinations, moves);
     }
-
-    moves.append(&mut temp_moves);
 }
 
 pub fn knight_moves(
@@ -139,14 +240,8 @@ pub fn knight_moves(
     position: ChessCell,
     moves: &mut Vec<ChessMove>,
 ) {
-    let destinations = KNIGHT_RAY_ATTACKS[position.as_index()];
-    for destination in destinations {
-        let target = ChessCell::from_index(*destination);
-        let attacked_square = board_state.board.square(target);
-        if attacked_square.is_empty_or_enemy_of(color) {
-            moves.push((position, target).into());
-        }
-    }
+    let attacks = knight_attacks(position.as_index());
+    push_moves(position, not_own(board_state, color, attacks), moves);
 }
 pub fn bishop_moves(
     color: PieceColor,
@@ -154,28 +249,8 @@ pub fn bishop_moves(
     position: ChessCell,
     moves: &mut Vec<ChessMove>,
 ) {
-    for direction in &BISHOP_DIRECTIONS {
-        let mut distance = 1;
-        loop {
-            let dest_rank = (position.0 as i32 + direction.0 * distance) as usize;
-            let dest_file = (position.1 as i32 + direction.1 * distance) as usize;
-            let dest = ChessCell(dest_rank, dest_file);
-            let target = board_state.board.square(dest);
-            match target {
-                Square::Aether => break,
-                Square::Empty => {
-                    moves.push((position, dest).into());
-                    distance += 1;
-                }
-                Square::Full(_) => {
-                    if target.is_enemy_of(color) {
-                        moves.push((position, dest).into());
-                    }
-                    break;
-                }
-            }
-        }
-    }
+    let attacks = bishop_attacks(position.as_index(), board_state.occupied());
+    push_moves(position, not_own(board_state, color, attacks), moves);
 }
 pub fn rook_moves(
     color: PieceColor,
@@ -183,28 +258,8 @@ pub fn rook_moves(
     position: ChessCell,
     moves: &mut Vec<ChessMove>,
 ) {
-    for direction in &ROOK_DIRECTIONS {
-        let mut distance = 1;
-        loop {
-            let dest_rank = (position.0 as i32 + direction.0 * distance) as usize;
-            let dest_file = (position.1 as i32 + direction.1 * distance) as usize;
-            let dest = ChessCell(dest_rank, dest_file);
-            let target = board_state.board.square(dest);
-            match target {
-                Square::Aether => break,
-                Square::Empty => {
-                    moves.push((position, dest).into());
-                    distance += 1
-                }
-                Square::Full(_) => {
-                    if target.is_enemy_of(color) {
-                        moves.push((position, dest).into());
-                    }
-                    break;
-                }
-            }
-        }
-    }
+    let attacks = rook_attacks(position.as_index(), board_state.occupied());
+    push_moves(position, not_own(board_state, color, attacks), moves);
 }
 pub fn queen_moves(
     color: PieceColor,
@@ -212,8 +267,8 @@ pub fn queen_moves(
     position: ChessCell,
     moves: &mut Vec<ChessMove>,
 ) {
-    bishop_moves(color, board_state, position, moves);
-    rook_moves(color, board_state, position, moves);
+    let attacks = queen_attacks(position.as_index(), board_state.occupied());
+    push_moves(position, not_own(board_state, color, attacks), moves);
 }
 pub fn king_moves(
     color: PieceColor,
@@ -221,13 +276,15 @@ pub fn king_moves(
     position: ChessCell,
     moves: &mut Vec<ChessMove>,
 ) {
-    let destinations = KING_RAY_ATTACKS[position.as_index()];
-    for destination in destinations {
-        let target = ChessCell::from_index(*destination);
-        if board_state.board.square(target).is_empty_or_enemy_of(color) {
-            moves.push((position, target).into());
-        }
-    }
+    let attacks = king_attacks(position.as_index());
+    push_moves(position, not_own(board_state, color, attacks), moves);
+}
+// The attacked squares that are empty or hold an enemy piece
+fn not_own(board_state: &BoardState, color: PieceColor, attacks: BitBoard) -> BitBoard {
+    BitBoard(attacks.0 & !board_state.color_bitboard(color).0)
+}
+fn push_moves(position: ChessCell, destinations: BitBoard, moves: &mut Vec<ChessMove>) {
+    moves.extend(destinations.map(|dest| ChessMove::from((position, dest))));
 }
 pub fn generate_en_passant_moves(board_state: &BoardState) -> Vec<ChessMove> {
     let mut en_passant_moves = Vec::new();
@@ -268,9 +325,89 @@ mod tests {
         board_state::BoardState,
     };
 
-    use super::generate_moves;
+    use super::{
+        generate_moves, generate_pseudo_captures_for_player, generate_pseudo_moves_for_player,
+    };
+    use crate::board_elements::{ChessCell, Square};
     use crate::constants::*;
 
+    // The mailbox generator the magic bitboards replaced, kept to cross-check them. Walks every
+    // ray of a sliding piece over the 12x12 board until it leaves the board or hits a piece.
+    fn mailbox_slider_moves(board_state: &BoardState) -> Vec<ChessMove> {
+        let bishop_directions = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+        let rook_directions = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+        let mut moves = Vec::new();
+        for position in board_state.get_piece_positions(board_state.to_move) {
+            let piece = board_state.board.square(position).piece().unwrap();
+            let directions: Vec<(i32, i32)> = match piece.kind {
+                PieceKind::Bishop => bishop_directions.to_vec(),
+                PieceKind::Rook => rook_directions.to_vec(),
+                PieceKind::Queen => [bishop_directions, rook_directions].concat(),
+                _ => continue,
+            };
+            for (rank_step, file_step) in directions {
+                let mut distance = 1;
+                loop {
+                    let dest = ChessCell(
+                        (position.0 as i32 + rank_step * distance) as usize,
+                        (position.1 as i32 + file_step * distance) as usize,
+                    );
+                    let target = board_state.board.square(dest);
+                    match target {
+                        Square::Aether => break,
+                        Square::Empty => moves.push((position, dest).into()),
+                        Square::Full(_) => {
+                            if target.is_enemy_of(piece.color) {
+                                moves.push((position, dest).into());
+                            }
+                            break;
+                        }
+                    }
+                    distance += 1;
+                }
+            }
+        }
+        moves
+    }
+
+    fn assert_sliders_match_mailbox(board_state: &mut BoardState, depth: u8) {
+        let mut expected = mailbox_slider_moves(board_state);
+        let mut generated: Vec<ChessMove> = generate_pseudo_moves_for_player(board_state)
+            .into_iter()
+            .filter(|mov| {
+                let kind = board_state.board.square(mov.start).piece().unwrap().kind;
+                matches!(kind, PieceKind::Bishop | PieceKind::Rook | PieceKind::Queen)
+            })
+            .collect();
+        let key = |mov: &ChessMove| (mov.start.as_index(), mov.dest.as_index());
+        expected.sort_by_key(key);
+        generated.sort_by_key(key);
+        assert_eq!(generated, expected, "{}", board_state.to_fen());
+        if depth == 0 {
+            return;
+        }
+        for mov in generate_moves(board_state) {
+            board_state.make_move(mov);
+            assert_sliders_match_mailbox(board_state, depth - 1);
+            board_state.unmake_move();
+        }
+    }
+
+    #[test]
+    fn magic_slider_moves_match_the_mailbox_generator_across_perft_trees() {
+        let fens = [
+            STARTING_FEN_STRING,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in fens {
+            let mut board_state = BoardState::from_fen(fen).unwrap();
+            assert_sliders_match_mailbox(&mut board_state, 2);
+        }
+    }
+
     #[test]
     fn generate_moves_from_starting_position() {
         let board_state = BoardState::new_game();
@@ -286,6 +423,26 @@ mod tests {
         assert_eq!(legal_moves.len(), 29);
     }
 
+    #[test]
+    fn queen_side_castling_requires_empty_b_file() {
+        let board_state = BoardState::from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(!legal_moves.contains(&(E1, C1).into()));
+        assert!(legal_moves.contains(&(E1, G1).into()));
+    }
+
+    #[test]
+    fn pawns_on_starting_rank_can_move_two_squares() {
+        let board_state = BoardState::new_game();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(legal_moves.contains(&(E2, E4).into()));
+        assert!(legal_moves.contains(&(A2, A4).into()));
+    }
+
     #[test]
     fn en_passant_with_one_pawn_can_capture() {
         let mut board_state = BoardState::new_game();
@@ -403,4 +560,148 @@ mod tests {
             assert!(legal_moves.contains(&mov))
         }
     }
+
+    // The legal moves as the pseudo moves that do not leave the king in check once played
+    fn legal_moves_by_make_unmake(board_state: &mut BoardState) -> Vec<ChessMove> {
+        let mut legal_moves = Vec::new();
+        for mov in generate_pseudo_moves_for_player(board_state) {
+            board_state.make_move(mov);
+            if board_state.is_valid_move() {
+                legal_moves.push(mov);
+            }
+            board_state.unmake_move();
+        }
+        legal_moves
+    }
+
+    fn assert_legal_moves_match_make_unmake(board_state: &mut BoardState, depth: u8) {
+        let key = |mov: &ChessMove| {
+            let promotion = mov.promotion.map(|piece| piece.kind.index());
+            (mov.start.as_index(), mov.dest.as_index(), promotion)
+        };
+        let mut expected = legal_moves_by_make_unmake(board_state);
+        let mut generated = generate_moves(board_state);
+        expected.sort_by_key(key);
+        generated.sort_by_key(key);
+        assert_eq!(generated, expected, "{}", board_state.to_fen());
+        if depth == 0 {
+            return;
+        }
+        for mov in generated {
+            board_state.make_move(mov);
+            assert_legal_moves_match_make_unmake(board_state, depth - 1);
+            board_state.unmake_move();
+        }
+    }
+
+    #[test]
+    fn legal_moves_match_trying_every_pseudo_move_across_perft_trees() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in fens {
+            let mut board_state = BoardState::from_fen(fen).unwrap();
+            assert_legal_moves_match_make_unmake(&mut board_state, 2);
+        }
+    }
+
+    #[test]
+    fn only_the_king_moves_out_of_double_check() {
+        // The knight on f6 and the rook on e1 both give check
+        let board_state = BoardState::from_fen("3qk3/8/5N2/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(!legal_moves.is_empty());
+        assert!(legal_moves.iter().all(|mov| mov.start == E8));
+    }
+
+    #[test]
+    fn single_check_is_met_by_capturing_or_blocking() {
+        let board_state = BoardState::from_fen("4k3/8/8/8/8/1N6/8/r3K2R w K - 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        let knight_moves: Vec<&ChessMove> =
+            legal_moves.iter().filter(|mov| mov.start == B3).collect();
+        assert_eq!(knight_moves, vec![&(B3, A1).into(), &(B3, C1).into()]);
+        assert!(!legal_moves.contains(&(E1, G1).into()));
+        // The king cannot step back along the checking rank
+        assert!(!legal_moves.contains(&(E1, F1).into()));
+        assert!(legal_moves.contains(&(E1, E2).into()));
+    }
+
+    #[test]
+    fn pinned_pieces_only_move_along_the_pin() {
+        let board_state = BoardState::from_fen("4k3/4r3/8/8/1b6/8/3NR3/4K3 w - - 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(legal_moves.iter().all(|mov| mov.start != D2));
+        let rook_moves: Vec<&ChessMove> =
+            legal_moves.iter().filter(|mov| mov.start == E2).collect();
+        assert_eq!(rook_moves.len(), 5);
+        assert!(rook_moves.iter().all(|mov| mov.dest.1 == E_FILE));
+    }
+
+    #[test]
+    fn en_passant_that_uncovers_a_rook_on_the_rank_is_illegal() {
+        let board_state = BoardState::from_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(!legal_moves.contains(&(B5, C6).into()));
+        assert!(legal_moves.contains(&(B5, B6).into()));
+    }
+
+    #[test]
+    fn en_passant_can_capture_the_checking_pawn() {
+        let board_state = BoardState::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(legal_moves.contains(&(E4, D3).into()));
+    }
+
+    #[test]
+    fn castling_through_an_attacked_square_is_illegal() {
+        let board_state = BoardState::from_fen("4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1").unwrap();
+
+        let legal_moves = generate_moves(&board_state);
+
+        assert!(!legal_moves.contains(&(E1, G1).into()));
+        assert!(legal_moves.contains(&(E1, C1).into()));
+    }
+
+    #[test]
+    fn capture_generator_finds_every_capture_in_kiwipete() {
+        let board_state = BoardState::from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+
+        let captures = generate_pseudo_captures_for_player(&board_state);
+
+        assert_eq!(captures.len(), 8);
+        assert!(captures.contains(&(E5, F7).into()));
+        assert!(captures.contains(&(D5, E6).into()));
+    }
+
+    #[test]
+    fn capture_generator_includes_quiet_promotions_and_en_passant() {
+        let promotion_board_state =
+            BoardState::from_fen("8/8/1k6/8/8/8/3K2p1/5R2 b - - 0 1").unwrap();
+        let en_passant_board_state =
+            BoardState::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+
+        let promotions = generate_pseudo_captures_for_player(&promotion_board_state);
+        let en_passant_captures = generate_pseudo_captures_for_player(&en_passant_board_state);
+
+        assert_eq!(promotions.len(), 8);
+        assert_eq!(en_passant_captures, vec![(E5, F6).into()]);
+    }
 }
@@ -11,6 +11,9 @@ pub fn move_score(board_state: &BoardState, mov: ChessMove) -> i32 {
     if let Some(captured_piece) = board_state.board.square(mov.dest).piece() {
         move_score += captured_piece.value() - moving_piece.value() / 10;
     }
+    if let Some(promotion) = mov.promotion {
+        move_score += promotion.value() - moving_piece.value();
+    }
     move_score
 }
 #[inline]
@@ -0,0 +1,146 @@
+use crate::board_elements::ChessMove;
+use crate::board_state::BoardState;
+use crate::move_generation::generate_moves;
+
+// Counts the leaf nodes of the legal move tree of the given depth.
+pub fn perft(board_state: &BoardState, depth: u8) -> u64 {
+    let mut board_state = board_state.clone();
+    count_nodes(&mut board_state, depth)
+}
+
+// Same as perft, but reports the node count below each legal move at the root.
+pub fn divide(board_state: &BoardState, depth: u8) -> Vec<(ChessMove, u64)> {
+    let mut scratch_board = board_state.clone();
+    generate_moves(board_state)
+        .into_iter()
+        .map(|mov| {
+            scratch_board.make_move(mov);
+            let nodes = count_nodes(&mut scratch_board, depth.saturating_sub(1));
+            scratch_board.unmake_move();
+            (mov, nodes)
+        })
+        .collect()
+}
+
+fn count_nodes(board_state: &mut BoardState, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = generate_moves(board_state);
+    // The moves are legal, so the last ply need not be played out
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mov in moves {
+        board_state.make_move(mov);
+        nodes += count_nodes(board_state, depth - 1);
+        board_state.unmake_move();
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::STARTING_FEN_STRING;
+
+    // Reference positions and node counts from https://www.chessprogramming.org/Perft_Results
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_4_MIRRORED: &str =
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    const POSITION_6: &str =
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+    fn assert_perft(fen: &str, expected_nodes: &[u64]) {
+        let board_state = BoardState::from_fen(fen).unwrap();
+        for (depth, &expected) in (1..).zip(expected_nodes) {
+            assert_eq!(
+                perft(&board_state, depth),
+                expected,
+                "perft({depth}) of {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn perft_starting_position() {
+        assert_perft(STARTING_FEN_STRING, &[20, 400, 8_902]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_starting_position_deep() {
+        assert_perft(STARTING_FEN_STRING, &[20, 400, 8_902, 197_281, 4_865_609]);
+    }
+
+    #[test]
+    fn perft_kiwipete() {
+        assert_perft(KIWIPETE, &[48, 2_039]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_kiwipete_deep() {
+        assert_perft(KIWIPETE, &[48, 2_039, 97_862, 4_085_603]);
+    }
+
+    #[test]
+    fn perft_position_3() {
+        assert_perft(POSITION_3, &[14, 191, 2_812]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_position_3_deep() {
+        assert_perft(POSITION_3, &[14, 191, 2_812, 43_238, 674_624]);
+    }
+
+    #[test]
+    fn perft_position_4() {
+        assert_perft(POSITION_4, &[6, 264, 9_467]);
+        assert_perft(POSITION_4_MIRRORED, &[6, 264, 9_467]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_position_4_deep() {
+        assert_perft(POSITION_4, &[6, 264, 9_467, 422_333]);
+        assert_perft(POSITION_4_MIRRORED, &[6, 264, 9_467, 422_333]);
+    }
+
+    #[test]
+    fn perft_position_5() {
+        assert_perft(POSITION_5, &[44, 1_486]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_position_5_deep() {
+        assert_perft(POSITION_5, &[44, 1_486, 62_379, 2_103_487]);
+    }
+
+    #[test]
+    fn perft_position_6() {
+        assert_perft(POSITION_6, &[46, 2_079]);
+    }
+
+    #[test]
+    #[ignore]
+    fn perft_position_6_deep() {
+        assert_perft(POSITION_6, &[46, 2_079, 89_890, 3_894_594]);
+    }
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let board_state = BoardState::from_fen(KIWIPETE).unwrap();
+
+        let divided = divide(&board_state, 2);
+
+        assert_eq!(divided.len(), 48);
+        assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+    }
+}
@@ -0,0 +1,805 @@
+use std::io::BufRead;
+use std::str::FromStr;
+
+use crate::board_elements::ChessMove;
+use crate::board_elements::GameResult;
+use crate::board_elements::PieceColor::*;
+use crate::board_state::BoardState;
+use crate::constants::STARTING_FEN_STRING;
+use crate::fen::FenError;
+use crate::san::SanError;
+use crate::search::{moves_to_mate, SearchResult};
+
+// Movetext lines are wrapped before this many characters, as the PGN standard asks
+const MAX_LINE_LENGTH: usize = 80;
+
+// The result recorded for a game, either as the final movetext token or in the Result tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PgnResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+impl PgnResult {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PgnResult::WhiteWins => "1-0",
+            PgnResult::BlackWins => "0-1",
+            PgnResult::Draw => "1/2-1/2",
+            PgnResult::Unknown => "*",
+        }
+    }
+    fn from_token(token: &str) -> Option<PgnResult> {
+        match token {
+            "1-0" => Some(PgnResult::WhiteWins),
+            "0-1" => Some(PgnResult::BlackWins),
+            "1/2-1/2" => Some(PgnResult::Draw),
+            "*" => Some(PgnResult::Unknown),
+            _ => None,
+        }
+    }
+}
+impl From<GameResult> for PgnResult {
+    fn from(game_result: GameResult) -> PgnResult {
+        match game_result {
+            GameResult::Winner(White) => PgnResult::WhiteWins,
+            GameResult::Winner(Black) => PgnResult::BlackWins,
+            GameResult::Draw(_) => PgnResult::Draw,
+        }
+    }
+}
+impl std::fmt::Display for PgnResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// A move of the movetext together with its annotations. Each variation is an alternative to this
+// move, played from the position before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub mov: ChessMove,
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    pub variations: Vec<PgnVariation>,
+}
+impl From<ChessMove> for PgnMove {
+    fn from(mov: ChessMove) -> PgnMove {
+        PgnMove {
+            mov,
+            nags: Vec::new(),
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnVariation {
+    // A comment before the first move of the variation
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnGame {
+    // The tag pairs in the order they appear
+    pub tags: Vec<(String, String)>,
+    pub starting_position: BoardState,
+    // A comment before the first move of the game
+    pub comment: Option<String>,
+    pub moves: Vec<PgnMove>,
+    pub result: PgnResult,
+}
+impl PgnGame {
+    // An unfinished game from starting_position, with placeholder values for the required tags.
+    // FEN and SetUp tags are added when the game does not start from the initial position.
+    pub fn new(starting_position: BoardState) -> PgnGame {
+        let mut tags = vec![
+            ("Event".to_string(), "?".to_string()),
+            ("Site".to_string(), "?".to_string()),
+            ("Date".to_string(), "????.??.??".to_string()),
+            ("Round".to_string(), "?".to_string()),
+            ("White".to_string(), "?".to_string()),
+            ("Black".to_string(), "?".to_string()),
+            ("Result".to_string(), PgnResult::Unknown.to_string()),
+        ];
+        let fen = starting_position.to_fen();
+        if fen != STARTING_FEN_STRING {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), fen));
+        }
+        PgnGame {
+            tags,
+            starting_position,
+            comment: None,
+            moves: Vec::new(),
+            result: PgnResult::Unknown,
+        }
+    }
+    // Replaces the value of the tag, or adds the tag if the game does not have it
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some((_, existing_value)) => *existing_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+    // Sets the result token and the Result tag
+    pub fn set_result(&mut self, result: PgnResult) {
+        self.result = result;
+        self.set_tag("Result", result.as_str());
+    }
+    pub fn push_move(&mut self, mov: ChessMove, comment: Option<String>) {
+        self.moves.push(PgnMove {
+            comment,
+            ..mov.into()
+        });
+    }
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+    pub fn mainline(&self) -> Vec<ChessMove> {
+        self.moves.iter().map(|pgn_move| pgn_move.mov).collect()
+    }
+    // The position after the last move of the main line
+    pub fn final_position(&self) -> BoardState {
+        let mut board_state = self.starting_position.clone();
+        for pgn_move in &self.moves {
+            board_state.make_move(pgn_move.mov);
+        }
+        board_state
+    }
+}
+// Writes the game in export format: tag pairs, a blank line, then the movetext wrapped at 80 characters
+impl std::fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            let escaped_value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{name} \"{escaped_value}\"]")?;
+        }
+        writeln!(f)?;
+
+        let mut movetext = Vec::new();
+        if let Some(comment) = &self.comment {
+            movetext.push(format!("{{{comment}}}"));
+        }
+        write_line(&mut movetext, &self.starting_position, &self.moves);
+        movetext.push(self.result.to_string());
+
+        let mut line_length = 0;
+        for token in movetext {
+            if line_length > 0 && line_length + 1 + token.len() >= MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{token}")?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+// Appends the movetext tokens for a line of moves played from board_state. Black's moves get a move
+// number when they start the line or follow a comment or variation.
+fn write_line(movetext: &mut Vec<String>, board_state: &BoardState, moves: &[PgnMove]) {
+    let mut board_state = board_state.clone();
+    let mut needs_move_number = true;
+    for pgn_move in moves {
+        match board_state.to_move {
+            White => movetext.push(format!("{}.", board_state.fullmove_number)),
+            Black if needs_move_number => {
+                movetext.push(format!("{}...", board_state.fullmove_number))
+            }
+            Black => (),
+        }
+        movetext.push(board_state.move_to_san(pgn_move.mov));
+        movetext.extend(pgn_move.nags.iter().map(|nag| format!("${nag}")));
+        if let Some(comment) = &pgn_move.comment {
+            movetext.push(format!("{{{comment}}}"));
+        }
+        for variation in &pgn_move.variations {
+            let variation_start = movetext.len();
+            if let Some(comment) = &variation.comment {
+                movetext.push(format!("{{{comment}}}"));
+            }
+            write_line(movetext, &board_state, &variation.moves);
+            match movetext.get_mut(variation_start..) {
+                Some([first_token, .., last_token]) => {
+                    first_token.insert(0, '(');
+                    last_token.push(')');
+                }
+                _ => movetext.push("()".to_string()),
+            }
+        }
+        needs_move_number = pgn_move.comment.is_some() || !pgn_move.variations.is_empty();
+        board_state.make_move(pgn_move.mov);
+    }
+}
+// A comment such as +0.35/6 1.20s describing the search that chose a move, with the eval in pawns
+// from the point of view of the side making the move, or as +M3 for a mate in 3.
+pub fn search_comment(search_result: &SearchResult) -> String {
+    let score = match moves_to_mate(search_result.eval) {
+        Some(moves) if moves > 0 => format!("+M{moves}"),
+        Some(moves) => format!("-M{}", -moves),
+        None => format!("{:+.2}", search_result.eval as f64 / 100.0),
+    };
+    format!(
+        "{score}/{} {:.2}s",
+        search_result.depth,
+        search_result.elapsed.as_secs_f64()
+    )
+}
+
+impl FromStr for PgnGame {
+    type Err = PgnError;
+    fn from_str(pgn: &str) -> Result<Self, Self::Err> {
+        parse_game(pgn)
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(std::io::Error),
+    InvalidTag(String),
+    InvalidFen(FenError),
+    InvalidMove { move_number: u32, error: SanError },
+    UnexpectedToken(String),
+    UnterminatedComment,
+    UnterminatedVariation,
+}
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PgnError::Io(error) => write!(f, "Failed to read PGN: {error}"),
+            PgnError::InvalidTag(tag) => write!(f, "Invalid PGN tag pair \"{tag}\""),
+            PgnError::InvalidFen(error) => write!(f, "Invalid FEN tag: {error}"),
+            PgnError::InvalidMove { move_number, error } => {
+                write!(f, "Invalid move at move {move_number}: {error}")
+            }
+            PgnError::UnexpectedToken(token) => write!(f, "Unexpected PGN token \"{token}\""),
+            PgnError::UnterminatedComment => write!(f, "PGN comment is missing its closing brace"),
+            PgnError::UnterminatedVariation => {
+                write!(f, "PGN variation is missing its closing parenthesis")
+            }
+        }
+    }
+}
+impl std::error::Error for PgnError {}
+impl From<std::io::Error> for PgnError {
+    fn from(error: std::io::Error) -> PgnError {
+        PgnError::Io(error)
+    }
+}
+
+// Reads the games of a PGN file one at a time. A game that fails to parse yields an error, and
+// reading carries on with the next game.
+pub struct PgnReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+    // The first line of the next game, read while looking for the end of the previous one
+    next_game_line: Option<String>,
+}
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            next_game_line: None,
+        }
+    }
+    // Collects the text of the next game. A game ends where a tag pair follows movetext.
+    fn next_game_text(&mut self) -> Option<Result<String, PgnError>> {
+        let mut text = String::new();
+        let mut has_movetext = false;
+        let mut in_comment = false;
+        loop {
+            let line = match self.next_game_line.take() {
+                Some(line) => line,
+                None => match self.lines.next() {
+                    Some(Ok(line)) => line,
+                    Some(Err(error)) => return Some(Err(error.into())),
+                    None => break,
+                },
+            };
+            let trimmed_line = line.trim_start();
+            if !in_comment && has_movetext && trimmed_line.starts_with('[') {
+                self.next_game_line = Some(line);
+                break;
+            }
+            if !in_comment && !trimmed_line.is_empty() && !trimmed_line.starts_with('[') {
+                has_movetext = true;
+            }
+            for char in line.chars() {
+                match char {
+                    '{' => in_comment = true,
+                    '}' => in_comment = false,
+                    ';' if !in_comment => break,
+                    _ => (),
+                }
+            }
+            text.push_str(&line);
+            text.push('\n');
+        }
+        match text.trim().is_empty() {
+            true => None,
+            false => Some(Ok(text)),
+        }
+    }
+}
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_game_text()?.and_then(|text| parse_game(&text)))
+    }
+}
+
+// Parses a single game, replaying every move and variation to check that it is legal
+pub fn parse_game(pgn: &str) -> Result<PgnGame, PgnError> {
+    let mut tokens = tokenize(pgn)?.into_iter().peekable();
+
+    let mut tags = Vec::new();
+    while let Some(Token::Tag(name, value)) = tokens.peek() {
+        tags.push((name.clone(), value.clone()));
+        tokens.next();
+    }
+    let tag_value = |name: &str| {
+        tags.iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let starting_position = match (tag_value("FEN"), tag_value("SetUp")) {
+        (Some(fen), setup) if setup != Some("0") => {
+            BoardState::from_fen(fen).map_err(PgnError::InvalidFen)?
+        }
+        _ => BoardState::new_game(),
+    };
+
+    let mut board_state = starting_position.clone();
+    let (comment, moves) = parse_line(&mut tokens, &mut board_state, false)?;
+    let result = match tokens.next() {
+        Some(Token::Result(result)) => result,
+        Some(token) => return Err(PgnError::UnexpectedToken(token.to_string())),
+        None => tag_value("Result")
+            .and_then(PgnResult::from_token)
+            .unwrap_or(PgnResult::Unknown),
+    };
+    if let Some(token) = tokens.next() {
+        return Err(PgnError::UnexpectedToken(token.to_string()));
+    }
+    Ok(PgnGame {
+        tags,
+        starting_position,
+        comment,
+        moves,
+        result,
+    })
+}
+
+// Parses moves until the end of the line, playing them on board_state. Returns the comment before
+// the first move along with the moves. A variation must end with a closing parenthesis, which is
+// consumed, while the main line ends at the result token or the end of the game. Variations are
+// replayed by taking back the move they replace and playing it again afterwards.
+fn parse_line(
+    tokens: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    board_state: &mut BoardState,
+    is_variation: bool,
+) -> Result<(Option<String>, Vec<PgnMove>), PgnError> {
+    let mut leading_comment: Option<String> = None;
+    let mut moves: Vec<PgnMove> = Vec::new();
+    loop {
+        let token = match tokens.peek() {
+            Some(Token::Result(_)) | None if !is_variation => break,
+            None => return Err(PgnError::UnterminatedVariation),
+            Some(_) => tokens.next().unwrap(),
+        };
+        match token {
+            Token::San(san) => {
+                let mov = board_state
+                    .parse_san(&san)
+                    .map_err(|error| PgnError::InvalidMove {
+                        move_number: board_state.fullmove_number,
+                        error,
+                    })?;
+                board_state.make_move(mov);
+                moves.push(mov.into());
+            }
+            Token::Nag(nag) => match moves.last_mut() {
+                Some(last_move) => last_move.nags.push(nag),
+                None => return Err(PgnError::UnexpectedToken(format!("${nag}"))),
+            },
+            Token::Comment(comment) => {
+                let existing_comment = match moves.last_mut() {
+                    Some(last_move) => &mut last_move.comment,
+                    None => &mut leading_comment,
+                };
+                *existing_comment = match existing_comment.take() {
+                    Some(existing) => Some(format!("{existing} {comment}")),
+                    None => Some(comment),
+                };
+            }
+            Token::VariationStart => {
+                let Some(last_move) = moves.last_mut() else {
+                    return Err(PgnError::UnexpectedToken("(".to_string()));
+                };
+                board_state.unmake_move();
+                let (comment, variation_moves) = parse_line(tokens, board_state, true)?;
+                for _ in &variation_moves {
+                    board_state.unmake_move();
+                }
+                board_state.make_move(last_move.mov);
+                last_move.variations.push(PgnVariation {
+                    comment,
+                    moves: variation_moves,
+                });
+            }
+            Token::VariationEnd if is_variation => break,
+            token => return Err(PgnError::UnexpectedToken(token.to_string())),
+        }
+    }
+    Ok((leading_comment, moves))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    San(String),
+    Nag(u8),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+    Result(PgnResult),
+}
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Tag(name, value) => write!(f, "[{name} \"{value}\"]"),
+            Token::San(san) => write!(f, "{san}"),
+            Token::Nag(nag) => write!(f, "${nag}"),
+            Token::Comment(comment) => write!(f, "{{{comment}}}"),
+            Token::VariationStart => write!(f, "("),
+            Token::VariationEnd => write!(f, ")"),
+            Token::Result(result) => write!(f, "{result}"),
+        }
+    }
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut at_line_start = true;
+    while let Some(char) = chars.next() {
+        let was_at_line_start = at_line_start;
+        at_line_start = char == '\n';
+        match char {
+            // Escaped lines are ignored
+            '%' if was_at_line_start => {
+                chars.by_ref().find(|&char| char == '\n');
+                at_line_start = true;
+            }
+            char if char.is_whitespace() => (),
+            '[' => {
+                // A closing bracket inside the quoted value does not end the tag
+                let mut tag = String::new();
+                let mut in_value = false;
+                while let Some(char) = chars.next() {
+                    if char == ']' && !in_value {
+                        break;
+                    }
+                    tag.push(char);
+                    match char {
+                        '"' => in_value = !in_value,
+                        '\\' if in_value => tag.extend(chars.next()),
+                        _ => (),
+                    }
+                }
+                tokens.push(parse_tag(&tag)?);
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(char) => comment.push(char),
+                        None => return Err(PgnError::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&char| char != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                at_line_start = true;
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            '$' => {
+                let mut nag = String::new();
+                while let Some(digit) = chars.next_if(|char| char.is_ascii_digit()) {
+                    nag.push(digit);
+                }
+                let nag = nag
+                    .parse::<u8>()
+                    .map_err(|_| PgnError::UnexpectedToken(format!("${nag}")))?;
+                tokens.push(Token::Nag(nag));
+            }
+            _ => {
+                let mut word = String::from(char);
+                while let Some(char) =
+                    chars.next_if(|char| !char.is_whitespace() && !"{}()[];$".contains(*char))
+                {
+                    word.push(char);
+                }
+                tokens.extend(word_tokens(&word)?);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// Splits a word of movetext such as 12.Nf3!? into a SAN move and its NAG, dropping the move number
+fn word_tokens(word: &str) -> Result<Vec<Token>, PgnError> {
+    if let Some(result) = PgnResult::from_token(word) {
+        return Ok(vec![Token::Result(result)]);
+    }
+    let word = match word.trim_start_matches(|char: char| char.is_ascii_digit()) {
+        without_number if without_number.starts_with('.') => without_number.trim_start_matches('.'),
+        _ => word,
+    };
+    if word.is_empty() {
+        return Ok(Vec::new());
+    }
+    let san = word.trim_end_matches(['!', '?']);
+    let nag = match &word[san.len()..] {
+        "" => None,
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        annotation => return Err(PgnError::UnexpectedToken(annotation.to_string())),
+    };
+    if san.is_empty() {
+        return Err(PgnError::UnexpectedToken(word.to_string()));
+    }
+    let mut tokens = vec![Token::San(san.to_string())];
+    tokens.extend(nag.map(Token::Nag));
+    Ok(tokens)
+}
+
+// Parses the inside of a tag pair such as Event "Casual game", unescaping the value
+fn parse_tag(tag: &str) -> Result<Token, PgnError> {
+    let invalid_tag = || PgnError::InvalidTag(tag.to_string());
+    let (name, quoted_value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid_tag)?;
+    let quoted_value = quoted_value.trim();
+    let inner_value = quoted_value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid_tag)?;
+    let mut value = String::new();
+    let mut inner_chars = inner_value.chars();
+    while let Some(char) = inner_chars.next() {
+        match char {
+            '\\' => value.push(inner_chars.next().ok_or_else(invalid_tag)?),
+            char => value.push(char),
+        }
+    }
+    Ok(Token::Tag(name.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    const SCHOLARS_MATE: &str = r#"[Event "Casual game"]
+[Site "Copenhagen \"DK\""]
+[Result "1-0"]
+
+1. e4 e5 2. Bc4 {Eyeing f7} Nc6 3. Qh5 Nf6?? $18 4. Qxf7# 1-0
+"#;
+
+    #[test]
+    fn tags_moves_and_result_are_parsed() {
+        let game: PgnGame = SCHOLARS_MATE.parse().unwrap();
+
+        assert_eq!(game.tag("Event"), Some("Casual game"));
+        assert_eq!(game.tag("Site"), Some("Copenhagen \"DK\""));
+        assert_eq!(game.moves.len(), 7);
+        assert_eq!(game.moves[6].mov, (H5, F7).into());
+        assert_eq!(game.result, PgnResult::WhiteWins);
+        assert!(game.final_position().get_game_winner().is_some());
+    }
+
+    #[test]
+    fn comments_and_nags_are_attached_to_the_preceding_move() {
+        let game: PgnGame = SCHOLARS_MATE.parse().unwrap();
+
+        assert_eq!(game.moves[2].comment.as_deref(), Some("Eyeing f7"));
+        assert_eq!(game.moves[5].nags, vec![4, 18]);
+    }
+
+    #[test]
+    fn nested_variations_are_replayed_from_the_position_before_the_move() {
+        let pgn =
+            "1. e4 (1. d4 d5 (1... Nf6 2. c4) 2. c4) 1... c5 (1... e5; Open game\n2. Nf3) 2. Nf3 *";
+
+        let game: PgnGame = pgn.parse().unwrap();
+
+        assert_eq!(
+            game.mainline(),
+            vec![(E2, E4).into(), (C7, C5).into(), (G1, F3).into()]
+        );
+        let d4_line = &game.moves[0].variations[0].moves;
+        assert_eq!(d4_line[0].mov, (D2, D4).into());
+        assert_eq!(d4_line[1].variations[0].moves[1].mov, (C2, C4).into());
+        assert_eq!(
+            game.moves[1].variations[0].moves[0].comment.as_deref(),
+            Some("Open game")
+        );
+        assert_eq!(game.result, PgnResult::Unknown);
+    }
+
+    #[test]
+    fn comment_before_a_variation_is_kept() {
+        let pgn = "1. e4 ({Queen's pawn} 1. d4 d5) 1... e5 *";
+
+        let game: PgnGame = pgn.parse().unwrap();
+
+        let variation = &game.moves[0].variations[0];
+        assert_eq!(variation.comment.as_deref(), Some("Queen's pawn"));
+        assert_eq!(variation.moves.len(), 2);
+        assert!(game.to_string().ends_with(&format!("{pgn}\n")));
+    }
+
+    #[test]
+    fn tag_values_may_contain_brackets_and_escapes() {
+        let pgn = r#"[Event "Blitz [3+2] \"open\" \\ \]"]
+
+1. e4 *"#;
+
+        let game: PgnGame = pgn.parse().unwrap();
+
+        assert_eq!(game.tag("Event"), Some(r#"Blitz [3+2] "open" \ ]"#));
+        assert_eq!(game.moves.len(), 1);
+    }
+
+    #[test]
+    fn fen_tag_sets_the_starting_position() {
+        let pgn = r#"[SetUp "1"]
+[FEN "7k/8/6K1/8/8/8/Q7/8 w - - 0 1"]
+
+1. Qa8# 1-0"#;
+
+        let game: PgnGame = pgn.parse().unwrap();
+
+        assert_eq!(game.mainline(), vec![(A2, A8).into()]);
+        assert_eq!(game.final_position().fullmove_number, 1);
+    }
+
+    #[test]
+    fn illegal_moves_are_reported_with_the_move_number() {
+        let error = "1. e4 e5 2. Ke3 *".parse::<PgnGame>().unwrap_err();
+
+        assert!(matches!(
+            error,
+            PgnError::InvalidMove {
+                move_number: 2,
+                error: SanError::IllegalMove(_)
+            }
+        ));
+    }
+
+    #[test]
+    fn reader_recovers_from_a_broken_game() {
+        let broken_games = r#"
+[Event "Broken"]
+
+1. e5 *
+
+[Event "Third"]
+
+1. d4 d5 1/2-1/2
+"#;
+        let pgn = format!("{SCHOLARS_MATE}{broken_games}");
+
+        let games: Vec<Result<PgnGame, PgnError>> = PgnReader::new(pgn.as_bytes()).collect();
+
+        assert_eq!(games.len(), 3);
+        assert!(games[1].is_err());
+        assert_eq!(games[2].as_ref().unwrap().result, PgnResult::Draw);
+    }
+
+    #[test]
+    fn written_game_lists_tags_then_numbered_san_moves() {
+        let mut game = PgnGame::new(BoardState::new_game());
+        game.set_tag("White", "mango_chess");
+        for mov in [
+            (E2, E4),
+            (E7, E5),
+            (D1, H5),
+            (B8, C6),
+            (F1, C4),
+            (G8, F6),
+            (H5, F7),
+        ] {
+            game.push_move(mov.into(), None);
+        }
+        game.moves[5].nags.push(4);
+        game.set_result(PgnResult::WhiteWins);
+
+        let pgn = game.to_string();
+
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]"));
+        assert!(pgn.contains("[White \"mango_chess\"]\n"));
+        assert!(pgn.contains("[Result \"1-0\"]\n\n"));
+        assert!(pgn.ends_with("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 $4 4. Qxf7# 1-0\n"));
+        assert!(!pgn.contains("FEN"));
+    }
+
+    #[test]
+    fn written_game_can_be_read_back() {
+        let pgn = "1. e4 {Best by test} 1... c5 (1... e5 2. Nf3 (2. f4 exf4) 2... Nc6) 2. Nf3 d6 *";
+        let game: PgnGame = pgn.parse().unwrap();
+
+        let written_game = game.to_string();
+
+        assert!(written_game.ends_with(&format!("{pgn}\n")));
+        assert_eq!(written_game.parse::<PgnGame>().unwrap(), game);
+    }
+
+    #[test]
+    fn game_from_a_position_records_the_fen_and_black_move_number() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 12";
+        let mut game = PgnGame::new(BoardState::from_fen(fen).unwrap());
+        game.push_move((E8, D7).into(), Some("+0.50/3 0.01s".to_string()));
+        game.push_move((E2, E4).into(), None);
+
+        let pgn = game.to_string();
+
+        assert!(pgn.contains(&format!("[SetUp \"1\"]\n[FEN \"{fen}\"]\n")));
+        assert!(pgn.ends_with("12... Kd7 {+0.50/3 0.01s} 13. e4 *\n"));
+        assert_eq!(pgn.parse::<PgnGame>().unwrap().moves.len(), 2);
+    }
+
+    #[test]
+    fn long_movetext_is_wrapped() {
+        let mut game = PgnGame::new(BoardState::new_game());
+        for _ in 0..10 {
+            for mov in [(G1, F3), (G8, F6), (F3, G1), (F6, G8)] {
+                game.push_move(mov.into(), None);
+            }
+        }
+
+        let pgn = game.to_string();
+
+        assert!(pgn.lines().all(|line| line.len() < MAX_LINE_LENGTH));
+        assert_eq!(pgn.parse::<PgnGame>().unwrap().moves.len(), 40);
+    }
+
+    #[test]
+    fn search_comment_shows_eval_depth_and_time() {
+        let search_result = SearchResult {
+            eval: 35,
+            depth: 6,
+            elapsed: std::time::Duration::from_millis(1200),
+            ..Default::default()
+        };
+        let mating_result = SearchResult {
+            eval: MATE_SCORE - 5,
+            depth: 4,
+            ..Default::default()
+        };
+
+        assert_eq!(search_comment(&search_result), "+0.35/6 1.20s");
+        assert_eq!(search_comment(&mating_result), "+M3/4 0.00s");
+    }
+}
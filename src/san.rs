@@ -0,0 +1,378 @@
+use crate::board_elements::ChessCell;
+use crate::board_elements::ChessMove;
+use crate::board_elements::Piece;
+use crate::board_elements::PieceKind;
+use crate::board_elements::PieceKind::*;
+use crate::board_state::BoardState;
+use crate::constants::*;
+use crate::move_generation::generate_moves;
+
+// The reason a SAN move could not be read in a position. Each variant holds the offending text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+impl std::fmt::Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::InvalidSyntax(san) => write!(f, "\"{san}\" is not a move in SAN"),
+            SanError::IllegalMove(san) => write!(f, "\"{san}\" is not a legal move"),
+            SanError::AmbiguousMove(san) => {
+                write!(f, "\"{san}\" matches more than one legal move")
+            }
+        }
+    }
+}
+impl std::error::Error for SanError {}
+
+// Formats a legal move in Standard Algebraic Notation, e.g. Nbd7, exd6, O-O-O or e8=Q+
+pub fn move_to_san(board_state: &BoardState, mov: ChessMove) -> String {
+    let moving_piece = board_state.board.square(mov.start).piece().unwrap();
+    let mut san = String::new();
+
+    if moving_piece.kind == King && mov.start.1.abs_diff(mov.dest.1) == 2 {
+        san.push_str(match mov.dest.1 {
+            G_FILE => "O-O",
+            _ => "O-O-O",
+        });
+    } else {
+        let is_capture = !board_state.board.square(mov.dest).is_empty()
+            || (moving_piece.kind == Pawn && mov.start.1 != mov.dest.1);
+        if moving_piece.kind == Pawn {
+            if is_capture {
+                san.push(file_char(mov.start));
+            }
+        } else {
+            san.push(piece_letter(moving_piece.kind));
+            san.push_str(&disambiguation(board_state, mov, moving_piece));
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&mov.dest.to_string());
+        if let Some(promotion) = mov.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion.kind));
+        }
+    }
+
+    let mut board_after_move = board_state.clone();
+    board_after_move.make_move(mov);
+    if board_after_move.is_in_check() {
+        san.push(match board_after_move.has_legal_move() {
+            true => '+',
+            false => '#',
+        });
+    }
+    san
+}
+
+// Finds the legal move described by a SAN string. Check and mate suffixes and annotations such as
+// ! and ? are ignored, castling may be written with zeros, and the = before a promotion may be left out.
+// The promotion piece may be written in lowercase, as in e8=q or e8q.
+pub fn parse_san(board_state: &BoardState, san: &str) -> Result<ChessMove, SanError> {
+    let invalid_syntax = || SanError::InvalidSyntax(san.to_string());
+    let stripped_san = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = generate_moves(board_state);
+
+    let is_king_move = |mov: &ChessMove| {
+        board_state
+            .board
+            .square(mov.start)
+            .piece()
+            .map(|piece| piece.kind)
+            == Some(King)
+    };
+    let candidates: Vec<ChessMove> = match stripped_san {
+        "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+            let castling_file = match stripped_san.len() {
+                3 => G_FILE,
+                _ => C_FILE,
+            };
+            legal_moves
+                .into_iter()
+                .filter(|mov| {
+                    is_king_move(mov)
+                        && mov.start.1 == E_FILE
+                        && mov.dest.1 == castling_file
+                        && mov.start.0 == mov.dest.0
+                })
+                .collect()
+        }
+        _ => {
+            let description = SanDescription::parse(stripped_san).ok_or_else(invalid_syntax)?;
+            legal_moves
+                .into_iter()
+                .filter(|&mov| description.matches(board_state, mov))
+                .collect()
+        }
+    };
+    match candidates[..] {
+        [mov] => Ok(mov),
+        [] => Err(SanError::IllegalMove(san.to_string())),
+        _ => Err(SanError::AmbiguousMove(san.to_string())),
+    }
+}
+
+// The parts of a non-castling SAN move
+struct SanDescription {
+    kind: PieceKind,
+    from_file: Option<usize>,
+    from_rank: Option<usize>,
+    dest: ChessCell,
+    promotion: Option<PieceKind>,
+}
+impl SanDescription {
+    fn parse(san: &str) -> Option<SanDescription> {
+        let mut chars: Vec<char> = san.chars().collect();
+
+        let promotion = match chars[..] {
+            [.., '=', letter]
+            | [.., '1'..='8', letter @ ('N' | 'B' | 'R' | 'Q' | 'n' | 'b' | 'r' | 'q')] => {
+                let promotion = piece_kind_from_letter(letter.to_ascii_uppercase())
+                    .filter(|&kind| kind != King)?;
+                chars.truncate(chars.len() - 1);
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            _ => None,
+        };
+        let kind = match chars.first() {
+            Some(&letter) if letter.is_ascii_uppercase() => {
+                chars.remove(0);
+                piece_kind_from_letter(letter)?
+            }
+            _ => Pawn,
+        };
+        if chars.len() < 2 {
+            return None;
+        }
+        let dest: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let dest = dest.parse::<ChessCell>().ok()?;
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        let (from_file, from_rank) = match chars[..] {
+            [] => (None, None),
+            [file @ 'a'..='h'] => (Some(file_from_char(file)), None),
+            [rank @ '1'..='8'] => (None, Some(rank_from_char(rank))),
+            [file @ 'a'..='h', rank @ '1'..='8'] => {
+                (Some(file_from_char(file)), Some(rank_from_char(rank)))
+            }
+            _ => return None,
+        };
+        Some(SanDescription {
+            kind,
+            from_file,
+            from_rank,
+            dest,
+            promotion,
+        })
+    }
+    fn matches(&self, board_state: &BoardState, mov: ChessMove) -> bool {
+        let moving_piece = board_state.board.square(mov.start).piece().unwrap();
+        // A pawn that does not capture stays on its file
+        let from_file = match (self.kind, self.from_file) {
+            (Pawn, None) => Some(self.dest.1),
+            (_, from_file) => from_file,
+        };
+        moving_piece.kind == self.kind
+            && mov.dest == self.dest
+            && mov.promotion.map(|piece| piece.kind) == self.promotion
+            && from_file.is_none_or(|file| mov.start.1 == file)
+            && self.from_rank.is_none_or(|rank| mov.start.0 == rank)
+    }
+}
+
+// The start square details needed to tell the move apart from other legal moves by the same kind
+// of piece to the same square: the file if it is unique, else the rank if it is unique, else both.
+fn disambiguation(board_state: &BoardState, mov: ChessMove, moving_piece: Piece) -> String {
+    let rivals: Vec<ChessCell> = generate_moves(board_state)
+        .into_iter()
+        .filter(|other| {
+            other.dest == mov.dest
+                && other.start != mov.start
+                && board_state.board.square(other.start).piece() == Some(moving_piece)
+        })
+        .map(|other| other.start)
+        .collect();
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|rival| rival.1 != mov.start.1) {
+        file_char(mov.start).to_string()
+    } else if rivals.iter().all(|rival| rival.0 != mov.start.0) {
+        rank_char(mov.start).to_string()
+    } else {
+        mov.start.to_string()
+    }
+}
+
+fn piece_letter(kind: PieceKind) -> char {
+    match kind {
+        Pawn => 'P',
+        Knight => 'N',
+        Bishop => 'B',
+        Rook => 'R',
+        Queen => 'Q',
+        King => 'K',
+    }
+}
+fn piece_kind_from_letter(letter: char) -> Option<PieceKind> {
+    match letter {
+        'P' => Some(Pawn),
+        'N' => Some(Knight),
+        'B' => Some(Bishop),
+        'R' => Some(Rook),
+        'Q' => Some(Queen),
+        'K' => Some(King),
+        _ => None,
+    }
+}
+fn file_char(cell: ChessCell) -> char {
+    cell.to_string().chars().next().unwrap()
+}
+fn rank_char(cell: ChessCell) -> char {
+    cell.to_string().chars().nth(1).unwrap()
+}
+fn file_from_char(file: char) -> usize {
+    (file as u8 - b'a') as usize + A_FILE
+}
+fn rank_from_char(rank: char) -> usize {
+    (rank as u8 - b'1') as usize + RANK_1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_elements::PieceColor::*;
+
+    fn assert_san_round_trips(board_state: &BoardState, mov: ChessMove, expected_san: &str) {
+        assert_eq!(move_to_san(board_state, mov), expected_san);
+        assert_eq!(parse_san(board_state, expected_san), Ok(mov));
+    }
+
+    #[test]
+    fn simple_moves_are_formatted_in_san() {
+        let board_state = BoardState::new_game();
+
+        assert_san_round_trips(&board_state, (E2, E4).into(), "e4");
+        assert_san_round_trips(&board_state, (G1, F3).into(), "Nf3");
+    }
+
+    #[test]
+    fn ambiguous_knight_moves_are_disambiguated_by_file_or_rank() {
+        let board_state =
+            BoardState::from_fen("r3k2r/p1pn1ppp/8/1N6/8/1N6/PPP1NPPP/R3K2R w KQkq - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (E2, D4).into(), "Ned4");
+        assert_san_round_trips(&board_state, (B5, D4).into(), "N5d4");
+        assert_san_round_trips(&board_state, (B3, D4).into(), "N3d4");
+        assert_eq!(
+            parse_san(&board_state, "Nd4"),
+            Err(SanError::AmbiguousMove("Nd4".to_string()))
+        );
+    }
+
+    #[test]
+    fn knights_on_two_files_and_ranks_need_the_full_square() {
+        let board_state = BoardState::from_fen("4k3/8/8/1N3N2/8/1N3N2/8/4K3 w - - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (B5, D4).into(), "Nb5d4");
+        assert_san_round_trips(&board_state, (F3, D4).into(), "Nf3d4");
+    }
+
+    #[test]
+    fn ambiguous_rook_moves_are_disambiguated() {
+        let board_state = BoardState::from_fen("3r3r/4k3/8/R7/8/8/8/R3K3 b - - 0 1").unwrap();
+        let white_board_state = BoardState::from_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (D8, F8).into(), "Rdf8");
+        assert_san_round_trips(&white_board_state, (A5, A3).into(), "R5a3");
+        assert_san_round_trips(&white_board_state, (A1, A3).into(), "R1a3");
+    }
+
+    #[test]
+    fn pawn_captures_and_en_passant_name_the_file() {
+        let board_state =
+            BoardState::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+
+        assert_san_round_trips(&board_state, (E5, F6).into(), "exf6");
+    }
+
+    #[test]
+    fn castling_is_written_with_capital_o() {
+        let board_state = BoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (E1, G1).into(), "O-O");
+        assert_san_round_trips(&board_state, (E1, C1).into(), "O-O-O");
+        assert_eq!(parse_san(&board_state, "0-0-0"), Ok((E1, C1).into()));
+    }
+
+    #[test]
+    fn promotions_are_written_with_equals_sign_and_check_suffix() {
+        let board_state = BoardState::from_fen("2k5/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (E7, E8, Piece::queen(White)).into(), "e8=Q+");
+        assert_san_round_trips(&board_state, (E7, E8, Piece::knight(White)).into(), "e8=N");
+        assert_eq!(
+            parse_san(&board_state, "e8Q"),
+            Ok((E7, E8, Piece::queen(White)).into())
+        );
+    }
+
+    #[test]
+    fn lowercase_promotion_letters_are_accepted() {
+        let board_state = BoardState::from_fen("3rk3/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+
+        assert_eq!(
+            parse_san(&board_state, "exd8=q"),
+            Ok((E7, D8, Piece::queen(White)).into())
+        );
+        assert_eq!(
+            parse_san(&board_state, "exd8n"),
+            Ok((E7, D8, Piece::knight(White)).into())
+        );
+        assert_eq!(
+            parse_san(&board_state, "exd8b"),
+            Ok((E7, D8, Piece::bishop(White)).into())
+        );
+    }
+
+    #[test]
+    fn capturing_promotion_is_parsed() {
+        let board_state = BoardState::from_fen("3rk3/4P3/8/8/8/8/8/K7 w - - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (E7, D8, Piece::rook(White)).into(), "exd8=R+");
+    }
+
+    #[test]
+    fn checkmate_has_hash_suffix() {
+        let board_state = BoardState::from_fen("7k/8/6K1/8/8/8/Q7/8 w - - 0 1").unwrap();
+
+        assert_san_round_trips(&board_state, (A2, A8).into(), "Qa8#");
+    }
+
+    #[test]
+    fn illegal_and_malformed_moves_are_rejected() {
+        let board_state = BoardState::new_game();
+
+        assert_eq!(
+            parse_san(&board_state, "e5"),
+            Err(SanError::IllegalMove("e5".to_string()))
+        );
+        assert_eq!(
+            parse_san(&board_state, "Nxe9"),
+            Err(SanError::InvalidSyntax("Nxe9".to_string()))
+        );
+        assert_eq!(
+            parse_san(&board_state, "O-O"),
+            Err(SanError::IllegalMove("O-O".to_string()))
+        );
+    }
+}
@@ -1,66 +1,565 @@
-use crate::board_elements::ChessMove;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board_elements::{ChessMove, Piece};
 use crate::board_state::BoardState;
-use crate::move_generation::generate_pseudo_moves_for_player;
+use crate::constants::{MATE_SCORE, MATE_THRESHOLD, TABLEBASE_WIN_SCORE};
+use crate::move_generation::{generate_moves, generate_pseudo_captures_for_player};
 use crate::move_scoring::move_score;
+use crate::syzygy::{Tablebase, Wdl};
+use crate::transposition_table::{Bound, TranspositionTable};
 
-pub fn search(board_state: &BoardState, depth: u8) -> (i32, Option<ChessMove>) {
-    let mut alpha = -i32::MAX;
-    let beta = i32::MAX;
-    let mut best_eval = -i32::MAX;
-    let mut best_move = None;
-    let mut possible_moves = generate_pseudo_moves_for_player(board_state);
+pub const DEFAULT_DEPTH: u8 = 4;
+pub const MAX_DEPTH: u8 = 64;
+// Assumed number of moves left in the game when the time control does not say
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+// Time kept in reserve so the engine never loses on time because of IO latency
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// How many nodes are searched between checks of the clock and the stop flag
+const NODES_BETWEEN_STOP_CHECKS: u64 = 1024;
+// Captures that cannot raise the eval to alpha even with this much positional gain are not searched
+const DELTA_MARGIN: i32 = 200;
 
-    possible_moves.sort_by_cached_key(|&mov| -move_score(board_state, mov));
-    for mov in possible_moves {
-        let mut copy_board = board_state.clone();
-        copy_board.make_move(mov);
-        if !copy_board.is_valid_move() {
-            continue;
-        }
-        let eval = -negamax(&copy_board, depth - 1, -beta, -alpha);
+// The time left on the clock of the side to move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Clock {
+    pub remaining: Duration,
+    pub increment: Duration,
+    pub moves_to_go: Option<u64>,
+}
+impl Clock {
+    // Splits the remaining time evenly over the moves left until the next time control.
+    fn time_budget(self) -> Duration {
+        let moves_to_go = self.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let budget = self.remaining / moves_to_go as u32 + self.increment / 2;
+        budget
+            .min(self.remaining.saturating_sub(MOVE_OVERHEAD))
+            .max(Duration::from_millis(1))
+    }
+}
 
-        if eval > best_eval {
-            best_eval = eval;
-            best_move = copy_board.last_move;
+// Bounds on how long a search may run. The search stops at whichever limit is reached first.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u8>,
+    pub movetime: Option<Duration>,
+    pub nodes: Option<u64>,
+    pub clock: Option<Clock>,
+    // Search until stopped from the outside
+    pub infinite: bool,
+}
+impl SearchLimits {
+    pub fn depth(depth: u8) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        }
+    }
+    pub fn movetime(movetime: Duration) -> SearchLimits {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..Default::default()
+        }
+    }
+    pub fn nodes(nodes: u64) -> SearchLimits {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..Default::default()
+        }
+    }
+    // Returns how long the search may run, or None if it is not bounded by time.
+    pub fn time_budget(&self) -> Option<Duration> {
+        if self.infinite {
+            return None;
         }
-        alpha = alpha.max(eval);
+        if let Some(movetime) = self.movetime {
+            return Some(
+                movetime
+                    .saturating_sub(MOVE_OVERHEAD)
+                    .max(Duration::from_millis(1)),
+            );
+        }
+        self.clock.map(Clock::time_budget)
+    }
+    pub fn max_depth(&self) -> u8 {
+        match self.depth {
+            Some(depth) => depth.clamp(1, MAX_DEPTH),
+            None if self.infinite || self.nodes.is_some() || self.time_budget().is_some() => {
+                MAX_DEPTH
+            }
+            None => DEFAULT_DEPTH,
+        }
+    }
+}
+
+// The outcome of a search. Also reported after every completed iteration of the iterative deepening.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchResult {
+    pub eval: i32,
+    pub best_move: Option<ChessMove>,
+    // The line of play the search expects, starting with the best move
+    pub pv: Vec<ChessMove>,
+    pub depth: u8,
+    // The deepest ply reached, including the quiescence search
+    pub seldepth: u8,
+    pub nodes: u64,
+    // The number of positions found in the tablebase
+    pub tablebase_hits: u64,
+    pub elapsed: Duration,
+}
+impl SearchResult {
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.elapsed.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+pub fn search(board_state: &BoardState, depth: u8) -> SearchResult {
+    search_with_limits(
+        board_state,
+        &SearchLimits::depth(depth),
+        &AtomicBool::new(false),
+        &mut TranspositionTable::default(),
+        |_| (),
+    )
+}
+
+// Returns the number of moves until mate if the eval is a forced mate. The number is negative
+// when the side to move is the one getting mated.
+pub fn moves_to_mate(eval: i32) -> Option<i32> {
+    if eval >= MATE_THRESHOLD {
+        Some((MATE_SCORE - eval + 1) / 2)
+    } else if eval <= -MATE_THRESHOLD {
+        Some(-(MATE_SCORE + eval) / 2)
+    } else {
+        None
+    }
+}
+
+// The eval of the side to move when there are no legal moves, ply moves from the root.
+// Mates closer to the root are preferred.
+fn terminal_eval(board_state: &BoardState, ply: u8) -> i32 {
+    match board_state.terminal_eval() {
+        0 => 0,
+        mated_eval => mated_eval + ply as i32,
     }
-    (best_eval, best_move)
 }
 
-fn negamax(board_state: &BoardState, depth: u8, alpha: i32, beta: i32) -> i32 {
-    if board_state.is_terminal() {
-        return board_state.terminal_eval();
+// The eval of a tablebase outcome for the side to move, with wins closer to the root preferred.
+// Cursed wins and blessed losses are draws under the fifty move rule, but the opponent may still go wrong.
+fn tablebase_eval(wdl: Wdl, distance: u32) -> i32 {
+    match wdl {
+        Wdl::Win => TABLEBASE_WIN_SCORE - distance as i32,
+        Wdl::CursedWin => 1,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -1,
+        Wdl::Loss => -TABLEBASE_WIN_SCORE + distance as i32,
     }
-    if depth == 0 {
-        return board_state.pov_eval();
+}
+
+// Searches one ply deeper at a time until a limit is reached or stop is set from another thread.
+// Returns the result of the last completed iteration. report is called after every completed iteration.
+pub fn search_with_limits(
+    board_state: &BoardState,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    transposition_table: &mut TranspositionTable,
+    report: impl FnMut(&SearchResult),
+) -> SearchResult {
+    search_with_tablebase(board_state, limits, stop, transposition_table, None, report)
+}
+
+// Same as search_with_limits, but positions in the tablebase are scored by it. When the root is in
+// the tablebase the move that is best by distance to zeroing is played without searching.
+pub fn search_with_tablebase(
+    board_state: &BoardState,
+    limits: &SearchLimits,
+    stop: &AtomicBool,
+    transposition_table: &mut TranspositionTable,
+    tablebase: Option<&Tablebase>,
+    mut report: impl FnMut(&SearchResult),
+) -> SearchResult {
+    let start_time = Instant::now();
+    let mut board_state = board_state.clone();
+    if !board_state.has_legal_move() {
+        return SearchResult {
+            eval: board_state.terminal_eval(),
+            ..Default::default()
+        };
+    }
+    if let Some((best_move, wdl, dtz)) =
+        tablebase.and_then(|tablebase| tablebase.probe_root(&board_state))
+    {
+        let result = SearchResult {
+            eval: tablebase_eval(wdl, dtz.unsigned_abs()),
+            best_move: Some(best_move),
+            pv: vec![best_move],
+            depth: 1,
+            seldepth: 1,
+            nodes: 1,
+            tablebase_hits: 1,
+            elapsed: start_time.elapsed(),
+        };
+        report(&result);
+        return result;
     }
-    let mut best_eval = -i32::MAX;
-    let mut available_pseudo_moves = generate_pseudo_moves_for_player(board_state);
+    let time_budget = limits.time_budget();
+    transposition_table.new_search();
+    let mut searcher = Searcher::new(limits, time_budget, stop, transposition_table);
+    searcher.tablebase = tablebase;
+    let mut result = SearchResult::default();
+    for depth in 1..=limits.max_depth() {
+        searcher.seldepth = 0;
+        let Some(eval) = searcher.search_root(&mut board_state, depth, result.best_move) else {
+            break;
+        };
+        let pv = searcher.pv_table.line(0).to_vec();
+        result = SearchResult {
+            eval,
+            best_move: pv.first().copied(),
+            pv,
+            depth,
+            seldepth: searcher.seldepth,
+            nodes: searcher.nodes,
+            tablebase_hits: searcher.tablebase_hits,
+            elapsed: searcher.start_time.elapsed(),
+        };
+        searcher.interruptible = true;
 
-    available_pseudo_moves.sort_by_cached_key(|&mov| -move_score(board_state, mov));
-    for mov in available_pseudo_moves {
-        let mut copy_board = board_state.clone();
-        copy_board.make_move(mov);
-        if !copy_board.is_valid_move() {
-            continue;
+        report(&result);
+        if stop.load(Ordering::SeqCst) {
+            break;
         }
-        let eval = -negamax(&copy_board, depth - 1, -beta, -alpha);
-        let alpha = alpha.max(eval);
-        best_eval = eval.max(best_eval);
-        if alpha >= beta {
+        // The next iteration takes several times longer than this one, so do not start it
+        // unless most of the budget is still left.
+        if time_budget.is_some_and(|time_budget| result.elapsed * 2 > time_budget) {
             break;
         }
     }
-    best_eval
+    // Work done in an interrupted iteration still counts
+    result.nodes = searcher.nodes;
+    result.tablebase_hits = searcher.tablebase_hits;
+    result.elapsed = searcher.start_time.elapsed();
+    result
+}
+
+// Triangular table of principal variations. The line at each ply is the best move found there
+// followed by the line of the ply below it.
+struct PvTable {
+    lines: Vec<Vec<ChessMove>>,
+}
+impl PvTable {
+    fn new() -> PvTable {
+        PvTable { lines: Vec::new() }
+    }
+    fn line(&self, ply: u8) -> &[ChessMove] {
+        self.lines.get(ply as usize).map_or(&[], Vec::as_slice)
+    }
+    fn clear(&mut self, ply: u8) {
+        let ply = ply as usize;
+        if self.lines.len() <= ply + 1 {
+            self.lines.resize_with(ply + 2, Vec::new);
+        }
+        self.lines[ply].clear();
+    }
+    fn update(&mut self, ply: u8, mov: ChessMove) {
+        let (current, below) = self.lines.split_at_mut(ply as usize + 1);
+        let line = &mut current[ply as usize];
+        line.clear();
+        line.push(mov);
+        line.extend_from_slice(&below[0]);
+    }
+}
+
+struct Searcher<'a> {
+    stop: &'a AtomicBool,
+    transposition_table: &'a mut TranspositionTable,
+    tablebase: Option<&'a Tablebase>,
+    pv_table: PvTable,
+    start_time: Instant,
+    deadline: Option<Instant>,
+    node_limit: Option<u64>,
+    nodes: u64,
+    tablebase_hits: u64,
+    seldepth: u8,
+    // Set once an iteration has completed, so there is a best move to fall back on when interrupted
+    interruptible: bool,
+    aborted: bool,
+}
+impl<'a> Searcher<'a> {
+    fn new(
+        limits: &SearchLimits,
+        time_budget: Option<Duration>,
+        stop: &'a AtomicBool,
+        transposition_table: &'a mut TranspositionTable,
+    ) -> Searcher<'a> {
+        let start_time = Instant::now();
+        Searcher {
+            stop,
+            transposition_table,
+            tablebase: None,
+            pv_table: PvTable::new(),
+            start_time,
+            deadline: time_budget.map(|time_budget| start_time + time_budget),
+            node_limit: limits.nodes,
+            nodes: 0,
+            tablebase_hits: 0,
+            seldepth: 0,
+            interruptible: false,
+            aborted: false,
+        }
+    }
+    fn should_abort(&mut self) -> bool {
+        if self.aborted || !self.interruptible {
+            return self.aborted;
+        }
+        if self
+            .node_limit
+            .is_some_and(|node_limit| self.nodes >= node_limit)
+        {
+            self.aborted = true;
+        } else if self.nodes.is_multiple_of(NODES_BETWEEN_STOP_CHECKS) {
+            self.aborted = self.stop.load(Ordering::Relaxed)
+                || self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.aborted
+    }
+    // Only probes right after a capture or pawn move, where the fifty move counter cannot change
+    // the outcome the tablebase gives
+    fn probe_tablebase(&mut self, board_state: &BoardState) -> Option<Wdl> {
+        if board_state.halfmove_clock != 0 {
+            return None;
+        }
+        let wdl = self.tablebase?.probe_wdl(board_state)?;
+        self.tablebase_hits += 1;
+        Some(wdl)
+    }
+    // Counts a visited node at ply and returns true if the search must stop
+    fn enter_node(&mut self, ply: u8) -> bool {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        self.pv_table.clear(ply);
+        self.should_abort()
+    }
+    // Returns None if the search was aborted before every root move was searched.
+    // The principal variation is left in the PV table at ply 0.
+    fn search_root(
+        &mut self,
+        board_state: &mut BoardState,
+        depth: u8,
+        previous_best_move: Option<ChessMove>,
+    ) -> Option<i32> {
+        let mut alpha = -i32::MAX;
+        let beta = i32::MAX;
+        let mut best_eval = -i32::MAX;
+        let mut best_move = None;
+        let mut possible_moves = generate_moves(board_state);
+        self.pv_table.clear(0);
+
+        // The best move of the previous iteration is searched first
+        possible_moves.sort_by_cached_key(|&mov| match Some(mov) == previous_best_move {
+            true => i32::MIN,
+            false => -move_score(board_state, mov),
+        });
+        for mov in possible_moves {
+            board_state.make_move(mov);
+            let eval = -self.negamax(board_state, depth - 1, 1, -beta, -alpha);
+            board_state.unmake_move();
+            if self.aborted {
+                return None;
+            }
+
+            if eval > best_eval {
+                best_eval = eval;
+                best_move = Some(mov);
+                self.pv_table.update(0, mov);
+            }
+            alpha = alpha.max(eval);
+        }
+        self.transposition_table.store(
+            board_state.zobrist_key,
+            depth,
+            0,
+            best_eval,
+            Bound::Exact,
+            best_move,
+        );
+        best_move.map(|_| best_eval)
+    }
+    fn negamax(
+        &mut self,
+        board_state: &mut BoardState,
+        depth: u8,
+        ply: u8,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        // A repeated position, one without mating material or one reached after fifty moves without
+        // progress is a draw, unless the move that reached it was checkmate. Only a king in check
+        // can be mated, so the moves are only generated then.
+        if board_state.is_repetition()
+            || board_state.is_insufficient_material()
+            || (board_state.is_fifty_move_draw()
+                && (!board_state.is_in_check() || board_state.has_legal_move()))
+        {
+            self.pv_table.clear(ply);
+            return 0;
+        }
+        if let Some(wdl) = self.probe_tablebase(board_state) {
+            self.pv_table.clear(ply);
+            return tablebase_eval(wdl, ply as u32);
+        }
+        if depth == 0 {
+            return self.quiescence(board_state, ply, alpha, beta);
+        }
+        if self.enter_node(ply) {
+            return 0;
+        }
+        let original_alpha = alpha;
+        let mut transposition_move = None;
+        if let Some(entry) = self.transposition_table.probe(board_state.zobrist_key, ply) {
+            transposition_move = entry.best_move;
+            if entry.depth >= depth {
+                let is_cutoff = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => entry.score >= beta,
+                    Bound::Upper => entry.score <= alpha,
+                };
+                // The principal variation ends here, since the rest of the line is not stored
+                if is_cutoff {
+                    return entry.score;
+                }
+            }
+        }
+        let mut best_eval = -i32::MAX;
+        let mut best_move = None;
+        let mut legal_moves = generate_moves(board_state);
+
+        // The best move found the last time this position was searched is tried first
+        legal_moves.sort_by_cached_key(|&mov| match Some(mov) == transposition_move {
+            true => i32::MIN,
+            false => -move_score(board_state, mov),
+        });
+        for mov in legal_moves {
+            board_state.make_move(mov);
+            let eval = -self.negamax(board_state, depth - 1, ply + 1, -beta, -alpha);
+            board_state.unmake_move();
+            if self.aborted {
+                return 0;
+            }
+            if eval > best_eval {
+                best_eval = eval;
+                best_move = Some(mov);
+            }
+            if eval > alpha {
+                alpha = eval;
+                self.pv_table.update(ply, mov);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        if best_move.is_none() {
+            return terminal_eval(board_state, ply);
+        }
+        let bound = if best_eval >= beta {
+            Bound::Lower
+        } else if best_eval <= original_alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        self.transposition_table.store(
+            board_state.zobrist_key,
+            depth,
+            ply,
+            best_eval,
+            bound,
+            best_move,
+        );
+        best_eval
+    }
+    // Searches captures and promotions until the position is quiet, so the static eval is never
+    // taken in the middle of an exchange. The side to move may stand pat instead of capturing,
+    // except in check, where every evasion is searched and having none is checkmate. Stalemates
+    // are left to the main search.
+    fn quiescence(
+        &mut self,
+        board_state: &mut BoardState,
+        ply: u8,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.enter_node(ply) {
+            return 0;
+        }
+        let in_check = board_state.is_in_check();
+        let stand_pat = board_state.pov_eval();
+        let (mut best_eval, mut moves) = if in_check {
+            let evasions = generate_moves(board_state);
+            if evasions.is_empty() {
+                self.pv_table.clear(ply);
+                return terminal_eval(board_state, ply);
+            }
+            (-i32::MAX, evasions)
+        } else {
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            (stand_pat, generate_pseudo_captures_for_player(board_state))
+        };
+
+        // Most valuable victim, least valuable attacker first
+        moves.sort_by_cached_key(|&mov| -move_score(board_state, mov));
+        for mov in moves {
+            let captured_value = match board_state.board.square(mov.dest).piece() {
+                Some(captured_piece) => captured_piece.value(),
+                // En passant
+                None if mov.promotion.is_none() => Piece::pawn(board_state.to_move).value(),
+                None => 0,
+            };
+            if !in_check
+                && mov.promotion.is_none()
+                && stand_pat + captured_value + DELTA_MARGIN < alpha
+            {
+                continue;
+            }
+            board_state.make_move(mov);
+            if !board_state.is_valid_move() {
+                board_state.unmake_move();
+                continue;
+            }
+            let eval = -self.quiescence(board_state, ply + 1, -beta, -alpha);
+            board_state.unmake_move();
+            if self.aborted {
+                return 0;
+            }
+            best_eval = best_eval.max(eval);
+            if eval > alpha {
+                alpha = eval;
+                self.pv_table.update(ply, mov);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_eval
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
     use crate::board_state::BoardState;
     use crate::constants::*;
+    use crate::move_generation::generate_moves;
 
-    use super::search;
+    use super::*;
 
     #[test]
     fn mate_in_two_has_correct_eval_and_move() {
@@ -69,9 +568,209 @@ mod tests {
         )
         .unwrap();
 
-        let (eval, best_move) = search(&board_state, 3);
+        let SearchResult {
+            eval, best_move, ..
+        } = search(&board_state, 3);
 
         assert!(eval > 1_000_000);
+        assert_eq!(moves_to_mate(eval), Some(2));
         assert_eq!(best_move, Some((D5, F6).into()))
     }
+
+    #[test]
+    fn mate_in_two_is_found_with_a_reused_transposition_table() {
+        let board_state = BoardState::from_fen(
+            "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 0",
+        )
+        .unwrap();
+        let mut transposition_table = TranspositionTable::new(1);
+
+        for _ in 0..2 {
+            let SearchResult {
+                eval, best_move, ..
+            } = search_with_limits(
+                &board_state,
+                &SearchLimits::depth(4),
+                &AtomicBool::new(false),
+                &mut transposition_table,
+                |_| (),
+            );
+
+            assert_eq!(moves_to_mate(eval), Some(2));
+            assert_eq!(best_move, Some((D5, F6).into()));
+        }
+    }
+
+    #[test]
+    fn principal_variation_of_mate_in_two_is_the_mating_line() {
+        let mut board_state = BoardState::from_fen(
+            "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 0",
+        )
+        .unwrap();
+
+        let result = search(&board_state, 4);
+
+        assert_eq!(result.pv.len(), 3);
+        assert_eq!(result.pv[0], (D5, F6).into());
+        for mov in result.pv {
+            assert!(generate_moves(&board_state).contains(&mov));
+            board_state.make_move(mov);
+        }
+        assert!(board_state.is_terminal());
+    }
+
+    #[test]
+    fn search_result_reports_statistics_of_the_last_iteration() {
+        let board_state = BoardState::new_game();
+
+        let result = search(&board_state, 3);
+
+        assert_eq!(result.depth, 3);
+        assert!(result.seldepth >= 3);
+        assert!(result.pv.len() >= 3);
+        assert_eq!(result.best_move, result.pv.first().copied());
+        assert!(result.nodes > 0);
+        assert!(result.nps() > 0);
+    }
+
+    #[test]
+    fn getting_mated_is_reported_as_negative_moves_to_mate() {
+        assert_eq!(moves_to_mate(-MATE_SCORE + 4), Some(-2));
+        assert_eq!(moves_to_mate(MATE_SCORE - 1), Some(1));
+        assert_eq!(moves_to_mate(350), None);
+    }
+
+    #[test]
+    fn defended_pawn_is_not_taken_with_the_queen() {
+        let board_state = BoardState::from_fen("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1").unwrap();
+
+        let SearchResult {
+            eval, best_move, ..
+        } = search(&board_state, 1);
+
+        assert_ne!(best_move, Some((D1, D5).into()));
+        assert!(eval < board_state.pov_eval() + 100);
+    }
+
+    #[test]
+    fn pinned_knight_attacked_by_pawn_is_counted_as_lost() {
+        let board_state = BoardState::from_fen("4r1k1/8/8/8/3p4/4N3/8/4K3 w - - 0 1").unwrap();
+
+        let SearchResult { eval, .. } = search(&board_state, 1);
+
+        assert!(eval < board_state.pov_eval() - 200);
+    }
+
+    #[test]
+    fn losing_side_repeats_the_position_with_perpetual_check() {
+        let mut board_state = BoardState::from_fen("7k/R7/1R6/4q3/8/7P/6P1/7K b - - 0 1").unwrap();
+        for mov in [(E5, E1), (H1, H2), (E1, E5), (H2, H1)] {
+            board_state.make_move(mov.into());
+        }
+
+        let SearchResult {
+            eval, best_move, ..
+        } = search(&board_state, 3);
+
+        assert_eq!(eval, 0);
+        assert_eq!(best_move, Some((E5, E1).into()));
+    }
+
+    #[test]
+    fn winning_side_cannot_avoid_fifty_move_draw() {
+        let board_state = BoardState::from_fen("8/8/8/4k3/8/8/8/3QK3 w - - 99 80").unwrap();
+
+        let SearchResult { eval, .. } = search(&board_state, 3);
+
+        assert_eq!(eval, 0);
+    }
+
+    #[test]
+    fn iterative_deepening_reports_every_depth() {
+        let board_state = BoardState::new_game();
+        let mut reported_depths = Vec::new();
+
+        search_with_limits(
+            &board_state,
+            &SearchLimits::depth(3),
+            &AtomicBool::new(false),
+            &mut TranspositionTable::default(),
+            |progress| reported_depths.push(progress.depth),
+        );
+
+        assert_eq!(reported_depths, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn search_respects_node_limit() {
+        let board_state = BoardState::new_game();
+        let mut searched_nodes = 0;
+
+        let SearchResult { best_move, .. } = search_with_limits(
+            &board_state,
+            &SearchLimits::nodes(2_000),
+            &AtomicBool::new(false),
+            &mut TranspositionTable::default(),
+            |progress| searched_nodes = progress.nodes,
+        );
+
+        assert!(best_move.is_some());
+        assert!(searched_nodes <= 2_000);
+    }
+
+    #[test]
+    fn search_respects_movetime() {
+        let board_state = BoardState::new_game();
+        let start_time = Instant::now();
+
+        let SearchResult { best_move, .. } = search_with_limits(
+            &board_state,
+            &SearchLimits::movetime(Duration::from_millis(200)),
+            &AtomicBool::new(false),
+            &mut TranspositionTable::default(),
+            |_| (),
+        );
+
+        assert!(best_move.is_some());
+        assert!(start_time.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn infinite_search_stops_when_stop_flag_is_set() {
+        let board_state = BoardState::new_game();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopper = {
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                stop.store(true, Ordering::SeqCst);
+            })
+        };
+        let limits = SearchLimits {
+            infinite: true,
+            ..Default::default()
+        };
+
+        let SearchResult { best_move, .. } = search_with_limits(
+            &board_state,
+            &limits,
+            &stop,
+            &mut TranspositionTable::default(),
+            |_| (),
+        );
+
+        stopper.join().unwrap();
+        assert!(best_move.is_some());
+    }
+
+    #[test]
+    fn clock_budget_spreads_remaining_time_over_moves_to_go() {
+        let clock = Clock {
+            remaining: Duration::from_secs(60),
+            increment: Duration::from_secs(2),
+            moves_to_go: Some(20),
+        };
+
+        assert_eq!(clock.time_budget(), Duration::from_secs(4));
+    }
 }
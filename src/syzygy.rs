@@ -0,0 +1,1982 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::board_elements::ChessCell;
+use crate::board_elements::ChessMove;
+use crate::board_elements::PieceColor;
+use crate::board_elements::PieceColor::*;
+use crate::board_elements::PieceKind;
+use crate::board_elements::PieceKind::*;
+use crate::board_state::BoardState;
+use crate::chess_board::ChessBoard;
+use crate::move_generation::generate_moves;
+
+// The largest tables in the Syzygy format have seven pieces, kings included
+pub const MAX_PIECES: usize = 7;
+const WDL_SUFFIX: &str = "rtbw";
+const DTZ_SUFFIX: &str = "rtbz";
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+// Piece letters in the order they appear in table names, such as KRPvKP
+const NAME_ORDER: [PieceKind; 6] = [King, Queen, Rook, Bishop, Knight, Pawn];
+
+// Flags stored with every compressed table
+const FLAG_SIDE_TO_MOVE: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// The outcome of a tablebase position for the side to move. A cursed win is a win that takes
+// more than fifty moves without a capture or pawn move, so it is a draw under the fifty move
+// rule, and a blessed loss is the other side of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+impl Wdl {
+    fn from_value(value: i32) -> Option<Wdl> {
+        match value {
+            -2 => Some(Wdl::Loss),
+            -1 => Some(Wdl::BlessedLoss),
+            0 => Some(Wdl::Draw),
+            1 => Some(Wdl::CursedWin),
+            2 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+    fn value(self) -> i32 {
+        self as i32 - 2
+    }
+    pub fn signum(self) -> i32 {
+        self.value().signum()
+    }
+}
+impl std::ops::Neg for Wdl {
+    type Output = Wdl;
+    fn neg(self) -> Wdl {
+        Wdl::from_value(-self.value()).unwrap()
+    }
+}
+
+// Syzygy tables found in one or more directories. The tables are read the first time a position
+// with their material is probed, and are found by the material key of their name.
+pub struct Tablebase {
+    wdl_tables: HashMap<u64, LazyTable>,
+    dtz_tables: HashMap<u64, LazyTable>,
+    max_pieces: usize,
+    // Positions with more pieces than this are not probed
+    pub probe_limit: usize,
+}
+impl Tablebase {
+    // Finds the tables in the directories, separated like the PATH environment variable
+    pub fn open(paths: &str) -> std::io::Result<Tablebase> {
+        let mut tablebase = Tablebase {
+            wdl_tables: HashMap::new(),
+            dtz_tables: HashMap::new(),
+            max_pieces: 0,
+            probe_limit: MAX_PIECES,
+        };
+        for directory in std::env::split_paths(paths) {
+            for entry in std::fs::read_dir(&directory)? {
+                let path = entry?.path();
+                tablebase.add_table(path);
+            }
+        }
+        Ok(tablebase)
+    }
+    fn add_table(&mut self, path: PathBuf) {
+        let (Some(name), Some(suffix)) = (
+            path.file_stem().and_then(|name| name.to_str()),
+            path.extension().and_then(|suffix| suffix.to_str()),
+        ) else {
+            return;
+        };
+        let (Some(piece_count), Some(material)) = (table_piece_count(name), table_material(name))
+        else {
+            return;
+        };
+        let (name, is_wdl) = (name.to_string(), suffix == WDL_SUFFIX);
+        let tables = match suffix {
+            WDL_SUFFIX => &mut self.wdl_tables,
+            DTZ_SUFFIX => &mut self.dtz_tables,
+            _ => return,
+        };
+        tables.insert(
+            material,
+            LazyTable {
+                name,
+                path,
+                table: OnceLock::new(),
+            },
+        );
+        if is_wdl {
+            self.max_pieces = self.max_pieces.max(piece_count);
+        }
+    }
+    // The number of pieces of the largest WDL table found
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+    pub fn table_count(&self) -> usize {
+        self.wdl_tables.len() + self.dtz_tables.len()
+    }
+    // Castling is not part of the tables, so positions with castling rights are never probed
+    pub fn can_probe(&self, board_state: &BoardState) -> bool {
+        let piece_count =
+            (board_state.white_bitboard.0 | board_state.black_bitboard.0).count_ones();
+        piece_count as usize <= self.probe_limit.min(self.max_pieces)
+            && board_state.available_castling_types(White).is_empty()
+            && board_state.available_castling_types(Black).is_empty()
+    }
+    // The outcome of the position with best play, or None if it is not in the tablebase.
+    // The fifty move counter is not taken into account.
+    pub fn probe_wdl(&self, board_state: &BoardState) -> Option<Wdl> {
+        if !self.can_probe(board_state) {
+            return None;
+        }
+        let (wdl, _) = self.search(&mut board_state.clone(), false)?;
+        Some(wdl)
+    }
+    // The number of plies until the next capture or pawn move with best play, positive when the
+    // side to move wins and negative when it loses, or 0 for a draw. A cursed win or blessed loss
+    // is 100 plies further away than it really is, so it is past the reach of the fifty move rule.
+    // Like the tables themselves, the distance can be one ply longer than the true one.
+    pub fn probe_dtz(&self, board_state: &BoardState) -> Option<i32> {
+        if !self.can_probe(board_state) {
+            return None;
+        }
+        self.dtz(&mut board_state.clone())
+    }
+    // Picks the move that wins fastest, or loses slowest, by distance to zeroing, taking the
+    // fifty move counter into account. Returns the move, the outcome under the fifty move rule
+    // and the distance to zeroing from the root position, using the same convention as probe_dtz.
+    pub fn probe_root(&self, board_state: &BoardState) -> Option<(ChessMove, Wdl, i32)> {
+        if !self.can_probe(board_state) {
+            return None;
+        }
+        let mut board_state = board_state.clone();
+        let halfmove_clock = board_state.halfmove_clock as i32;
+        let mut best = None;
+        for mov in generate_moves(&board_state) {
+            board_state.make_move(mov);
+            let dtz = if board_state.halfmove_clock == 0 {
+                self.search(&mut board_state, false)
+                    .map(|(wdl, _)| dtz_before_zeroing(-wdl))
+            } else if board_state.is_repetition() || board_state.is_fifty_move_draw() {
+                Some(0)
+            } else {
+                self.dtz(&mut board_state).map(|dtz| {
+                    let dtz = -dtz;
+                    dtz + dtz.signum()
+                })
+            };
+            // The mated position counts as a loss one ply away, which would make the mate a ply longer
+            let dtz = match dtz {
+                Some(2) if is_checkmate(&mut board_state) => Some(1),
+                dtz => dtz,
+            };
+            board_state.unmake_move();
+            let dtz = dtz?;
+
+            // Wins within the fifty move rule come first, the fastest of them first, then the
+            // cursed wins, draws, blessed losses and losses, the slowest of them first
+            let outcome = match dtz {
+                dtz if dtz > 0 && dtz + halfmove_clock <= 100 => Wdl::Win,
+                dtz if dtz > 0 => Wdl::CursedWin,
+                0 => Wdl::Draw,
+                dtz if -dtz + halfmove_clock <= 100 => Wdl::Loss,
+                _ => Wdl::BlessedLoss,
+            };
+            let rank = (outcome, -dtz);
+            if best.is_none_or(|(_, best_rank)| rank > best_rank) {
+                best = Some((mov, rank));
+            }
+        }
+        best.map(|(mov, (wdl, negative_dtz))| (mov, wdl, -negative_dtz))
+    }
+    fn table(&self, kind: TableKind, material: u64) -> Option<&Table> {
+        let tables = match kind {
+            TableKind::Wdl => &self.wdl_tables,
+            TableKind::Dtz => &self.dtz_tables,
+        };
+        let lazy_table = tables.get(&material)?;
+        lazy_table
+            .table
+            .get_or_init(|| Table::open(&lazy_table.path, kind, &lazy_table.name))
+            .as_ref()
+    }
+    // Tables do not store the right value when the side to move has a winning capture, or in some
+    // cases a drawing one, so captures are searched and the best outcome of those and the stored
+    // value is the outcome of the position. With check_zeroing_moves pawn moves are searched as
+    // well, since DTZ tables do not store positions where a winning pawn move is the best move.
+    // Returns the outcome and whether the best move is a capture or pawn move.
+    fn search(
+        &self,
+        board_state: &mut BoardState,
+        check_zeroing_moves: bool,
+    ) -> Option<(Wdl, bool)> {
+        let moves = generate_moves(board_state);
+        let mut best_wdl = Wdl::Loss;
+        let mut searched_moves = 0;
+        for &mov in &moves {
+            let is_pawn_move = board_state
+                .board
+                .square(mov.start)
+                .piece()
+                .map(|piece| piece.kind)
+                == Some(Pawn);
+            let is_searched = is_capture(board_state, mov) || (check_zeroing_moves && is_pawn_move);
+            if !is_searched {
+                continue;
+            }
+            searched_moves += 1;
+            board_state.make_move(mov);
+            let result = self.search(board_state, false);
+            board_state.unmake_move();
+            let wdl = -result?.0;
+            if wdl > best_wdl {
+                best_wdl = wdl;
+                if wdl == Wdl::Win {
+                    return Some((wdl, true));
+                }
+            }
+        }
+        // When every legal move was searched the stored value may be wrong, for example because
+        // the tables do not contain positions where en passant is possible
+        let every_move_searched = searched_moves > 0 && searched_moves == moves.len();
+        let stored_wdl = match every_move_searched {
+            true => best_wdl,
+            false => self.probe_wdl_table(board_state)?,
+        };
+        match best_wdl >= stored_wdl {
+            true => Some((best_wdl, best_wdl > Wdl::Draw || every_move_searched)),
+            false => Some((stored_wdl, false)),
+        }
+    }
+    fn dtz(&self, board_state: &mut BoardState) -> Option<i32> {
+        let (wdl, zeroing_best_move) = self.search(board_state, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing_best_move {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        if let Some(dtz) = self.probe_dtz_table(board_state, wdl)? {
+            let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+            return Some((dtz + 100 * i32::from(cursed)) * wdl.signum());
+        }
+        // The table only stores positions with the other side to move, so the distance is found
+        // one ply deeper, from the best move with the same outcome
+        let mut best_dtz = None;
+        for mov in generate_moves(board_state) {
+            let is_zeroing_move = is_capture(board_state, mov)
+                || board_state
+                    .board
+                    .square(mov.start)
+                    .piece()
+                    .map(|piece| piece.kind)
+                    == Some(Pawn);
+            board_state.make_move(mov);
+            // For zeroing moves the distance counts from before the move
+            let dtz = match is_zeroing_move {
+                true => self
+                    .search(board_state, false)
+                    .map(|(wdl, _)| -dtz_before_zeroing(wdl)),
+                false => self.dtz(board_state).map(|dtz| -dtz),
+            };
+            let is_mate = dtz == Some(1) && is_checkmate(board_state);
+            board_state.unmake_move();
+            let mut dtz = dtz?;
+            if is_mate {
+                best_dtz = Some(1);
+            }
+            if !is_zeroing_move {
+                dtz += dtz.signum();
+            }
+            if dtz.signum() == wdl.signum() && best_dtz.is_none_or(|best_dtz| dtz < best_dtz) {
+                best_dtz = Some(dtz);
+            }
+        }
+        // Without legal moves the side to move is mated
+        Some(best_dtz.unwrap_or(-1))
+    }
+    fn probe_wdl_table(&self, board_state: &BoardState) -> Option<Wdl> {
+        match self.probe_table(board_state, TableKind::Wdl, Wdl::Draw)? {
+            TableValue::Value(value) => Wdl::from_value(value),
+            TableValue::WrongSideToMove => None,
+        }
+    }
+    // Returns None on failure, and Some(None) if the table is for the other side to move
+    fn probe_dtz_table(&self, board_state: &BoardState, wdl: Wdl) -> Option<Option<i32>> {
+        match self.probe_table(board_state, TableKind::Dtz, wdl)? {
+            TableValue::Value(value) => Some(Some(value)),
+            TableValue::WrongSideToMove => Some(None),
+        }
+    }
+    fn probe_table(
+        &self,
+        board_state: &BoardState,
+        kind: TableKind,
+        wdl: Wdl,
+    ) -> Option<TableValue> {
+        let occupied = board_state.white_bitboard.0 | board_state.black_bitboard.0;
+        // Two bare kings are not stored in any table
+        if occupied.count_ones() == 2 {
+            return Some(TableValue::Value(0));
+        }
+        let white_material = side_material(board_state, White);
+        let black_material = side_material(board_state, Black);
+        // Tables are only stored with the stronger side as white, so the other way around the
+        // colors are swapped and the board is mirrored
+        let (table, black_stronger) = match self.table(kind, white_material | black_material << 32)
+        {
+            Some(table) => (table, false),
+            None => (
+                self.table(kind, black_material | white_material << 32)?,
+                true,
+            ),
+        };
+        // With the same material on both sides only white to move is stored
+        let flip = black_stronger || (table.symmetric && board_state.to_move == Black);
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let side_to_move = usize::from(flip) ^ usize::from(board_state.to_move == Black);
+        let index_tables = index_tables();
+
+        let mut squares = [0; MAX_PIECES];
+        let mut pieces = [0; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut lead_pawn_count = 0;
+        let mut file = 0;
+        // Pawns are split into four tables by the file of the leading pawn, the one nearest the
+        // edge and lowest on the board
+        if table.has_pawns {
+            let lead_piece = table.pairs[0][0].pieces[0] ^ flip_color;
+            let lead_color = if lead_piece & 8 == 0 { White } else { Black };
+            lead_pawns = board_state.pieces(lead_color, Pawn).0;
+            for square in squares_of(lead_pawns) {
+                squares[size] = square ^ flip_squares;
+                size += 1;
+            }
+            lead_pawn_count = size;
+            let leading = (0..lead_pawn_count)
+                .max_by_key(|&i| index_tables.map_pawns[squares[i]])
+                .unwrap();
+            squares.swap(0, leading);
+            file = edge_distance(squares[0] % 8);
+        }
+        let pairs = table.pairs(side_to_move, file);
+        // Symmetric tables without pawns store either side to move, since the colors can be swapped
+        let stores_side_to_move = (pairs.flags & FLAG_SIDE_TO_MOVE) as usize == side_to_move
+            || (table.symmetric && !table.has_pawns);
+        if kind == TableKind::Dtz && !stores_side_to_move {
+            return Some(TableValue::WrongSideToMove);
+        }
+        for square in squares_of(occupied & !lead_pawns) {
+            squares[size] = square ^ flip_squares;
+            pieces[size] = piece_code(&board_state.board, square)? ^ flip_color;
+            size += 1;
+        }
+        if size != table.piece_count {
+            return None;
+        }
+        // Orders the pieces as in the table
+        for i in lead_pawn_count..size - 1 {
+            if let Some(j) = (i + 1..size).find(|&j| pairs.pieces[i] == pieces[j]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        let index = encode(table, pairs, &mut squares[..size], lead_pawn_count)?;
+        let value = pairs.decompress(&table.bytes, index)?;
+        Some(TableValue::Value(match kind {
+            TableKind::Wdl => value as i32 - 2,
+            TableKind::Dtz => table.map_dtz(file, value, wdl)?,
+        }))
+    }
+}
+
+struct LazyTable {
+    name: String,
+    path: PathBuf,
+    // None if the file could not be read or is not a valid table
+    table: OnceLock<Option<Table>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+enum TableValue {
+    Value(i32),
+    // DTZ tables only store one side to move
+    WrongSideToMove,
+}
+
+struct Table {
+    bytes: Vec<u8>,
+    piece_count: usize,
+    has_pawns: bool,
+    // Whether some piece other than a king has no twin of the same color and kind
+    has_unique_pieces: bool,
+    both_sides_have_pawns: bool,
+    // The same material on both sides
+    symmetric: bool,
+    // The compressed data for every file of the leading pawn and side to move
+    pairs: Vec<Vec<PairsData>>,
+    // The start of the DTZ value maps
+    dtz_map: usize,
+}
+impl Table {
+    fn open(path: &Path, kind: TableKind, name: &str) -> Option<Table> {
+        Table::from_bytes(std::fs::read(path).ok()?, kind, name)
+    }
+    fn from_bytes(bytes: Vec<u8>, kind: TableKind, name: &str) -> Option<Table> {
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+        if bytes.get(0..4)? != magic {
+            return None;
+        }
+        let (white, black) = name.split_once('v')?;
+        let count = |side: &str, letter: char| side.chars().filter(|&c| c == letter).count();
+        let white_pawns = count(white, 'P');
+        let black_pawns = count(black, 'P');
+        let has_unique_pieces = "QRBNP"
+            .chars()
+            .any(|letter| count(white, letter) == 1 || count(black, letter) == 1);
+        let mut table = Table {
+            bytes,
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            both_sides_have_pawns: white_pawns > 0 && black_pawns > 0,
+            symmetric: white == black,
+            pairs: Vec::new(),
+            dtz_map: 0,
+        };
+        table.read_layout(kind)?;
+        Some(table)
+    }
+    fn pairs(&self, side_to_move: usize, file: usize) -> &PairsData {
+        let files = &self.pairs[if self.has_pawns { file } else { 0 }];
+        &files[side_to_move % files.len()]
+    }
+    // Reads the header of every compressed table in the file and finds where their data is
+    fn read_layout(&mut self, kind: TableKind) -> Option<()> {
+        let bytes = &self.bytes;
+        let mut offset = 4;
+        let flags = *bytes.get(offset)?;
+        if (flags & 2 != 0) != self.has_pawns {
+            return None;
+        }
+        offset += 1;
+        let sides = if kind == TableKind::Wdl && !self.symmetric {
+            2
+        } else {
+            1
+        };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let mut pairs = vec![vec![PairsData::default(); sides]; files];
+
+        for (file, file_pairs) in pairs.iter_mut().enumerate() {
+            let first = *bytes.get(offset)?;
+            let second = match self.both_sides_have_pawns {
+                true => *bytes.get(offset + 1)?,
+                false => 0xFF,
+            };
+            let orders = [[first & 0xF, second & 0xF], [first >> 4, second >> 4]];
+            offset += 1 + usize::from(self.both_sides_have_pawns);
+            for k in 0..self.piece_count {
+                let piece = *bytes.get(offset)?;
+                for (side, side_pairs) in file_pairs.iter_mut().enumerate() {
+                    side_pairs.pieces[k] = if side == 0 { piece & 0xF } else { piece >> 4 };
+                }
+                offset += 1;
+            }
+            for (side, side_pairs) in file_pairs.iter_mut().enumerate() {
+                self.set_groups(side_pairs, orders[side], file)?;
+            }
+        }
+        offset += offset & 1;
+
+        for side_pairs in pairs.iter_mut().flatten() {
+            offset = side_pairs.read_sizes(bytes, offset)?;
+        }
+        let dtz_map = offset;
+        if kind == TableKind::Dtz {
+            for file_pairs in pairs.iter_mut() {
+                let side_pairs = &mut file_pairs[0];
+                if side_pairs.flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                if side_pairs.flags & FLAG_WIDE != 0 {
+                    offset += offset & 1;
+                    for map_index in side_pairs.map_index.iter_mut() {
+                        *map_index = (offset - dtz_map) / 2 + 1;
+                        offset += 2 * read_u16_le(bytes, offset)? as usize + 2;
+                    }
+                } else {
+                    for map_index in side_pairs.map_index.iter_mut() {
+                        *map_index = offset - dtz_map + 1;
+                        offset += *bytes.get(offset)? as usize + 1;
+                    }
+                }
+            }
+            offset += offset & 1;
+        }
+        // The sizes come from the file, so they are checked rather than trusted not to overflow
+        for side_pairs in pairs.iter_mut().flatten() {
+            side_pairs.sparse_index = offset;
+            offset = offset.checked_add(side_pairs.sparse_index_size.checked_mul(6)?)?;
+        }
+        for side_pairs in pairs.iter_mut().flatten() {
+            side_pairs.block_lengths = offset;
+            offset = offset.checked_add(side_pairs.block_length_count.checked_mul(2)?)?;
+        }
+        for side_pairs in pairs.iter_mut().flatten() {
+            offset = offset.checked_add(0x3F)? & !0x3F;
+            side_pairs.data = offset;
+            offset =
+                offset.checked_add(side_pairs.block_count.checked_mul(side_pairs.block_size)?)?;
+        }
+        if offset > bytes.len() {
+            return None;
+        }
+        self.pairs = pairs;
+        self.dtz_map = dtz_map;
+        Some(())
+    }
+    // Splits the pieces into the groups that are encoded together, and works out the factor
+    // every group is multiplied by in the index
+    fn set_groups(&self, pairs: &mut PairsData, order: [u8; 2], file: usize) -> Option<()> {
+        let index_tables = index_tables();
+        let mut first_group_length: i32 = match (self.has_pawns, self.has_unique_pieces) {
+            (true, _) => 0,
+            (false, true) => 3,
+            (false, false) => 2,
+        };
+        let mut groups = 0;
+        pairs.group_length[0] = 1;
+        for i in 1..self.piece_count {
+            first_group_length -= 1;
+            if first_group_length > 0 || pairs.pieces[i] == pairs.pieces[i - 1] {
+                pairs.group_length[groups] += 1;
+            } else {
+                groups += 1;
+                pairs.group_length[groups] = 1;
+            }
+        }
+        groups += 1;
+        pairs.group_length[groups] = 0;
+
+        let both_sides_have_pawns = self.has_pawns && self.both_sides_have_pawns;
+        let mut next = if both_sides_have_pawns { 2 } else { 1 };
+        let mut free_squares = 64
+            - pairs.group_length[0]
+            - if both_sides_have_pawns {
+                pairs.group_length[1]
+            } else {
+                0
+            };
+        let mut index: u64 = 1;
+        let mut k = 0;
+        while next < groups || k == order[0] || k == order[1] {
+            if k == order[0] {
+                pairs.group_index[0] = index;
+                index *= match (self.has_pawns, self.has_unique_pieces) {
+                    (true, _) => index_tables.lead_pawns_size[pairs.group_length[0]][file],
+                    (false, true) => 31332,
+                    (false, false) => 462,
+                };
+            } else if k == order[1] {
+                pairs.group_index[1] = index;
+                index *= *index_tables
+                    .binomial
+                    .get(pairs.group_length[1])?
+                    .get(48usize.checked_sub(pairs.group_length[0])?)?;
+            } else {
+                pairs.group_index[next] = index;
+                index *= *index_tables
+                    .binomial
+                    .get(pairs.group_length[next])?
+                    .get(free_squares)?;
+                free_squares = free_squares.checked_sub(pairs.group_length[next])?;
+                next += 1;
+            }
+            k += 1;
+        }
+        pairs.group_index[groups] = index;
+        Some(())
+    }
+    // DTZ tables store their values through a map for each outcome, and sometimes in moves
+    // rather than plies. Returns the distance in plies.
+    fn map_dtz(&self, file: usize, value: u32, wdl: Wdl) -> Option<i32> {
+        let pairs = self.pairs(0, file);
+        let map_index = pairs.map_index[match wdl {
+            Wdl::Loss => 1,
+            Wdl::BlessedLoss => 3,
+            Wdl::Draw | Wdl::Win => 0,
+            Wdl::CursedWin => 2,
+        }];
+        let mut value = value as i32;
+        if pairs.flags & FLAG_MAPPED != 0 {
+            let position = map_index + value as usize;
+            value = match pairs.flags & FLAG_WIDE != 0 {
+                true => read_u16_le(&self.bytes, self.dtz_map + 2 * position)? as i32,
+                false => *self.bytes.get(self.dtz_map + position)? as i32,
+            };
+        }
+        let in_moves = match wdl {
+            Wdl::Win => pairs.flags & FLAG_WIN_PLIES == 0,
+            Wdl::Loss => pairs.flags & FLAG_LOSS_PLIES == 0,
+            Wdl::CursedWin | Wdl::BlessedLoss => true,
+            Wdl::Draw => false,
+        };
+        if in_moves {
+            value *= 2;
+        }
+        Some(value + 1)
+    }
+}
+
+// One compressed table. Values are stored in blocks of canonical Huffman codes, where every
+// symbol expands to a pair of symbols until the values are reached.
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    // The pieces in the order they are encoded, colored and numbered like piece_code
+    pieces: [u8; MAX_PIECES],
+    group_length: [usize; MAX_PIECES + 1],
+    group_index: [u64; MAX_PIECES + 1],
+    block_size: usize,
+    // The number of values between two entries of the sparse index
+    span: u64,
+    block_count: usize,
+    max_symbol_length: u8,
+    // Also the value of the whole table when it has the single value flag
+    min_symbol_length: u8,
+    lowest_symbols: usize,
+    // base64[i] is the lowest code of length min_symbol_length + i, left aligned in 64 bits
+    base64: Vec<u64>,
+    // The number of values every symbol expands to, minus one
+    symbol_lengths: Vec<u32>,
+    symbol_tree: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    block_lengths: usize,
+    block_length_count: usize,
+    data: usize,
+    map_index: [usize; 4],
+}
+impl PairsData {
+    fn read_sizes(&mut self, bytes: &[u8], mut offset: usize) -> Option<usize> {
+        self.flags = *bytes.get(offset)?;
+        offset += 1;
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            self.min_symbol_length = *bytes.get(offset)?;
+            return Some(offset + 1);
+        }
+        let table_size =
+            self.group_index[self.group_length.iter().position(|&length| length == 0)?];
+        self.block_size = 1usize.checked_shl(*bytes.get(offset)? as u32)?;
+        self.span = 1u64.checked_shl(*bytes.get(offset + 1)? as u32)?;
+        self.sparse_index_size = table_size.div_ceil(self.span) as usize;
+        let padding = *bytes.get(offset + 2)? as usize;
+        self.block_count = read_u32_le(bytes, offset + 3)? as usize;
+        self.block_length_count = self.block_count + padding;
+        self.max_symbol_length = *bytes.get(offset + 7)?;
+        self.min_symbol_length = *bytes.get(offset + 8)?;
+        if self.min_symbol_length == 0
+            || self.max_symbol_length < self.min_symbol_length
+            || self.max_symbol_length > 64
+        {
+            return None;
+        }
+        offset += 9;
+        self.lowest_symbols = offset;
+
+        let code_lengths = (self.max_symbol_length - self.min_symbol_length) as usize + 1;
+        let lowest_symbols = offset;
+        let lowest_symbol = |i: usize| read_u16_le(bytes, lowest_symbols + 2 * i).map(u64::from);
+        self.base64 = vec![0; code_lengths];
+        for i in (0..code_lengths - 1).rev() {
+            self.base64[i] = (self.base64[i + 1].wrapping_add(lowest_symbol(i)?))
+                .wrapping_sub(lowest_symbol(i + 1)?)
+                / 2;
+        }
+        for (i, base) in self.base64.iter_mut().enumerate() {
+            *base <<= 64 - i - self.min_symbol_length as usize;
+        }
+        offset += code_lengths * 2;
+
+        let symbol_count = read_u16_le(bytes, offset)? as usize;
+        offset += 2;
+        self.symbol_tree = offset;
+        if bytes.len() < offset + symbol_count * 3 {
+            return None;
+        }
+        self.symbol_lengths = vec![0; symbol_count];
+        let mut visited = vec![Visit::NotVisited; symbol_count];
+        for symbol in 0..symbol_count {
+            if visited[symbol] == Visit::NotVisited {
+                self.set_symbol_length(bytes, symbol, &mut visited)?;
+            }
+        }
+        Some(offset + symbol_count * 3 + (symbol_count & 1))
+    }
+    // Fails if a symbol expands into itself, which only a corrupt table can do
+    fn set_symbol_length(
+        &mut self,
+        bytes: &[u8],
+        symbol: usize,
+        visited: &mut [Visit],
+    ) -> Option<()> {
+        visited[symbol] = Visit::InProgress;
+        let (left, right) = self.children(bytes, symbol);
+        if right != 0xFFF {
+            for child in [left, right] {
+                match visited.get(child)? {
+                    Visit::NotVisited => self.set_symbol_length(bytes, child, visited)?,
+                    Visit::InProgress => return None,
+                    Visit::Done => (),
+                }
+            }
+            self.symbol_lengths[symbol] =
+                self.symbol_lengths[left] + self.symbol_lengths[right] + 1;
+        }
+        visited[symbol] = Visit::Done;
+        Some(())
+    }
+    // Every symbol is stored as two 12 bit children, or a value and 0xFFF for a leaf
+    fn children(&self, bytes: &[u8], symbol: usize) -> (usize, usize) {
+        let entry = &bytes[self.symbol_tree + 3 * symbol..self.symbol_tree + 3 * symbol + 3];
+        let left = ((entry[1] as usize & 0xF) << 8) | entry[0] as usize;
+        let right = ((entry[2] as usize) << 4) | (entry[1] as usize >> 4);
+        (left, right)
+    }
+    fn decompress(&self, bytes: &[u8], index: u64) -> Option<u32> {
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(self.min_symbol_length as u32);
+        }
+        // The sparse index gives the block and offset of every span-th value, counted from the
+        // middle of the span
+        let sparse_entry = self.sparse_index + 6 * (index / self.span) as usize;
+        let mut block = read_u32_le(bytes, sparse_entry)? as usize;
+        let mut offset = read_u16_le(bytes, sparse_entry + 4)? as i64;
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+        let block_length = |block: usize| read_u16_le(bytes, self.block_lengths + 2 * block);
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? as i64 + 1;
+        }
+        while offset > block_length(block)? as i64 {
+            offset -= block_length(block)? as i64 + 1;
+            block += 1;
+        }
+
+        let mut position = self.data.checked_add(block.checked_mul(self.block_size)?)?;
+        let mut buffer = read_u64_be(bytes, position)?;
+        position += 8;
+        let mut buffer_size: usize = 64;
+        let min_symbol_length = self.min_symbol_length as usize;
+        let mut symbol = loop {
+            let mut length = 0;
+            while buffer < *self.base64.get(length)? {
+                length += 1;
+            }
+            let symbol = ((buffer - self.base64[length]) >> (64 - length - min_symbol_length))
+                as usize
+                + read_u16_le(bytes, self.lowest_symbols + 2 * length)? as usize;
+            let expanded = *self.symbol_lengths.get(symbol)? as i64 + 1;
+            if offset < expanded {
+                break symbol;
+            }
+            offset -= expanded;
+            length += min_symbol_length;
+            buffer = buffer.checked_shl(length as u32).unwrap_or(0);
+            buffer_size = buffer_size.checked_sub(length)?;
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= (read_u32_be(bytes, position)? as u64) << (64 - buffer_size);
+                position += 4;
+            }
+        };
+        // Expands the symbol into its pair until the value at the offset is reached
+        while self.symbol_lengths[symbol] != 0 {
+            let (left, right) = self.children(bytes, symbol);
+            let left_length = self.symbol_lengths[left] as i64 + 1;
+            if offset < left_length {
+                symbol = left;
+            } else {
+                offset -= left_length;
+                symbol = right;
+            }
+        }
+        Some(self.children(bytes, symbol).0 as u32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    NotVisited,
+    InProgress,
+    Done,
+}
+
+// Maps the position to its index in the table. The pieces are mirrored so the leading piece is in
+// the a1-d1-d4 triangle, or the leading pawn on the queen side, and identical pieces are encoded
+// together regardless of their order.
+fn encode(
+    table: &Table,
+    pairs: &PairsData,
+    squares: &mut [usize],
+    lead_pawn_count: usize,
+) -> Option<u64> {
+    let index_tables = index_tables();
+    if squares[0] % 8 > 3 {
+        for square in squares.iter_mut() {
+            *square ^= 7;
+        }
+    }
+    let mut index;
+    if table.has_pawns {
+        index = index_tables.lead_pawn_index[lead_pawn_count][squares[0]];
+        squares[1..lead_pawn_count].sort_by_key(|&square| index_tables.map_pawns[square]);
+        for (i, &square) in squares.iter().enumerate().take(lead_pawn_count).skip(1) {
+            index += index_tables.binomial[i][index_tables.map_pawns[square] as usize];
+        }
+    } else {
+        if squares[0] / 8 > 3 {
+            for square in squares.iter_mut() {
+                *square ^= 56;
+            }
+        }
+        // The first piece of the leading group that is off the a1-h8 diagonal goes below it
+        if let Some(i) = (0..pairs.group_length[0]).find(|&i| off_diagonal(squares[i]) != 0) {
+            if off_diagonal(squares[i]) > 0 {
+                for square in squares[i..].iter_mut() {
+                    *square = ((*square >> 3) | (*square << 3)) & 63;
+                }
+            }
+        }
+        index = match table.has_unique_pieces {
+            true => encode_unique_pieces(squares),
+            false => index_tables.map_kk[index_tables.map_a1d1d4[squares[0]]][squares[1]],
+        };
+    }
+    index *= pairs.group_index[0];
+
+    // The remaining groups are encoded by the squares not taken by earlier groups
+    let mut remaining_pawns = table.has_pawns && table.both_sides_have_pawns;
+    let mut group_start = pairs.group_length[0];
+    let mut group = 1;
+    while pairs.group_length[group] != 0 {
+        let group_end = group_start + pairs.group_length[group];
+        squares[group_start..group_end].sort_unstable();
+        let mut group_index = 0;
+        for i in group_start..group_end {
+            let taken_below = squares[..group_start]
+                .iter()
+                .filter(|&&square| square < squares[i])
+                .count();
+            let free_square =
+                (squares[i] - taken_below).checked_sub(if remaining_pawns { 8 } else { 0 })?;
+            group_index += index_tables.binomial[i - group_start + 1][free_square];
+        }
+        remaining_pawns = false;
+        index += group_index * pairs.group_index[group];
+        group_start = group_end;
+        group += 1;
+    }
+    Some(index)
+}
+
+// Encodes three unique leading pieces, the first in the a1-d1-d4 triangle, into 0..31332
+fn encode_unique_pieces(squares: &[usize]) -> u64 {
+    let index_tables = index_tables();
+    let [first, second, third] = [squares[0], squares[1], squares[2]];
+    let adjust_second = usize::from(second > first);
+    let adjust_third = usize::from(third > first) + usize::from(third > second);
+    let index = if off_diagonal(first) != 0 {
+        (index_tables.map_a1d1d4[first] * 63 + (second - adjust_second)) * 62 + third - adjust_third
+    } else if off_diagonal(second) != 0 {
+        (6 * 63 + (first / 8) * 28 + index_tables.map_b1h1h7[second]) * 62 + third - adjust_third
+    } else if off_diagonal(third) != 0 {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + (first / 8) * 7 * 28
+            + (second / 8 - adjust_second) * 28
+            + index_tables.map_b1h1h7[third]
+    } else {
+        6 * 63 * 62
+            + 4 * 28 * 62
+            + 4 * 7 * 28
+            + (first / 8) * 6 * 7
+            + (second / 8 - adjust_second) * 6
+            + (third / 8 - adjust_third)
+    };
+    index as u64
+}
+
+// Lookup tables used to encode positions, built on first use
+struct IndexTables {
+    // Squares below the a1-h8 diagonal to 0..28
+    map_b1h1h7: [usize; 64],
+    // Squares in the a1-d1-d4 triangle to 0..10, with the diagonal last
+    map_a1d1d4: [usize; 64],
+    // The 462 legal placements of two kings with the first in the a1-d1-d4 triangle
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; MAX_PIECES],
+    // Pawn squares a2-h7 to 0..48, highest for the pawn that leads
+    map_pawns: [u64; 64],
+    lead_pawn_index: [[u64; 64]; MAX_PIECES],
+    lead_pawns_size: [[u64; 4]; MAX_PIECES],
+}
+
+fn index_tables() -> &'static IndexTables {
+    static INDEX_TABLES: OnceLock<IndexTables> = OnceLock::new();
+    INDEX_TABLES.get_or_init(|| {
+        let mut tables = IndexTables {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; MAX_PIECES],
+            map_pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; MAX_PIECES],
+            lead_pawns_size: [[0; 4]; MAX_PIECES],
+        };
+        for (code, square) in (0..64)
+            .filter(|&square| off_diagonal(square) < 0)
+            .enumerate()
+        {
+            tables.map_b1h1h7[square] = code;
+        }
+        let triangle = [0, 1, 2, 3, 8, 9, 10, 11, 16, 17, 18, 19, 24, 25, 26, 27];
+        let below_diagonal = triangle.iter().filter(|&&square| off_diagonal(square) < 0);
+        let on_diagonal = triangle.iter().filter(|&&square| off_diagonal(square) == 0);
+        for (code, &square) in below_diagonal.chain(on_diagonal).enumerate() {
+            tables.map_a1d1d4[square] = code;
+        }
+
+        // The first king goes through the triangle in the order of its code
+        let mut first_kings: Vec<(usize, usize)> = triangle
+            .iter()
+            .filter(|&&square| off_diagonal(square) <= 0)
+            .map(|&square| (tables.map_a1d1d4[square], square))
+            .collect();
+        first_kings.sort_unstable();
+        let mut code = 0;
+        let mut both_on_diagonal = Vec::new();
+        for (first_index, first) in first_kings {
+            for second in 0..64 {
+                let adjacent =
+                    (first % 8).abs_diff(second % 8) <= 1 && (first / 8).abs_diff(second / 8) <= 1;
+                if adjacent || (off_diagonal(first) == 0 && off_diagonal(second) > 0) {
+                    continue;
+                }
+                if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                    both_on_diagonal.push((first_index, second));
+                } else {
+                    tables.map_kk[first_index][second] = code;
+                    code += 1;
+                }
+            }
+        }
+        for (first_index, second) in both_on_diagonal {
+            tables.map_kk[first_index][second] = code;
+            code += 1;
+        }
+
+        tables.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_PIECES.min(n + 1) {
+                tables.binomial[k][n] = if k > 0 {
+                    tables.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n { tables.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut available_squares = 47;
+        for lead_pawn_count in 1..MAX_PIECES - 1 {
+            for file in 0..4 {
+                let mut index = 0;
+                for rank in 1..7 {
+                    let square = 8 * rank + file;
+                    if lead_pawn_count == 1 {
+                        tables.map_pawns[square] = available_squares;
+                        tables.map_pawns[square ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2);
+                    }
+                    tables.lead_pawn_index[lead_pawn_count][square] = index;
+                    index +=
+                        tables.binomial[lead_pawn_count - 1][tables.map_pawns[square] as usize];
+                }
+                tables.lead_pawns_size[lead_pawn_count][file] = index;
+            }
+        }
+        tables
+    })
+}
+
+// Positive above the a1-h8 diagonal, negative below it
+fn off_diagonal(square: usize) -> i32 {
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+fn edge_distance(file: usize) -> usize {
+    file.min(7 - file)
+}
+
+// The distance to zeroing of a capture or pawn move with the given outcome after it
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+fn is_capture(board_state: &BoardState, mov: ChessMove) -> bool {
+    board_state.board.square(mov.dest).has_piece()
+        || (Some(mov.dest) == board_state.en_passant
+            && board_state
+                .board
+                .square(mov.start)
+                .piece()
+                .map(|piece| piece.kind)
+                == Some(Pawn))
+}
+
+fn is_checkmate(board_state: &mut BoardState) -> bool {
+    board_state.is_in_check() && !board_state.has_legal_move()
+}
+
+// The number of queens, rooks, bishops, knights and pawns of one side, four bits each. A table
+// is keyed by the material of white, with that of black shifted into the high 32 bits.
+fn side_material(board_state: &BoardState, color: PieceColor) -> u64 {
+    NAME_ORDER[1..]
+        .iter()
+        .enumerate()
+        .fold(0, |material, (i, &kind)| {
+            let count = board_state.pieces(color, kind).0.count_ones() as u64;
+            material | count << (4 * i)
+        })
+}
+
+// The material key of a table name such as KRvKN
+fn table_material(name: &str) -> Option<u64> {
+    let (white, black) = name.split_once('v')?;
+    let side = |side: &str| {
+        side.chars()
+            .try_fold(0, |material, letter| match "QRBNP".find(letter) {
+                Some(i) => Some(material + (1 << (4 * i))),
+                None => (letter == 'K').then_some(material),
+            })
+    };
+    Some(side(white)? | side(black)? << 32)
+}
+
+// The number of pieces in a table name such as KRvKN, or None if it is not a table name
+fn table_piece_count(name: &str) -> Option<usize> {
+    let (white, black) = name.split_once('v')?;
+    let is_side =
+        |side: &str| side.starts_with('K') && side[1..].chars().all(|c| "QRBNP".contains(c));
+    let piece_count = white.len() + black.len();
+    (is_side(white) && is_side(black) && piece_count <= MAX_PIECES).then_some(piece_count)
+}
+
+fn squares_of(mut bitboard: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        let square = bitboard.trailing_zeros() as usize;
+        bitboard &= bitboard.wrapping_sub(1);
+        (square < 64).then_some(square)
+    })
+}
+
+// Pieces are numbered 1 to 6 from pawn to king, plus 8 for black
+fn piece_code(board: &ChessBoard, square: usize) -> Option<u8> {
+    let piece = board.square(ChessCell::from_index(square)).piece()?;
+    Some(piece.kind.index() as u8 + 1 + if piece.color == Black { 8 } else { 0 })
+}
+
+fn read_u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+fn read_u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+fn read_u64_be(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+    use crate::board_elements::BitBoard;
+    use crate::constants::*;
+    use crate::search::{search_with_tablebase, SearchLimits};
+    use crate::transposition_table::TranspositionTable;
+
+    const WHITE_KING: u8 = 6;
+    const BLACK_KING: u8 = 14;
+    const ILLEGAL: i16 = -2;
+    const DRAW: i16 = -1;
+    // KQvK, KRvK and KPvK tables written by generate.py in the same directory
+    const FIXTURE_DIRECTORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy");
+
+    fn position(fen: &str) -> BoardState {
+        BoardState::from_fen(fen).unwrap()
+    }
+
+    // Writes a KQvK WDL table where every position is won with white to move and lost with black
+    // to move. Both values are stored with the single value flag, so there is no compressed data.
+    fn synthetic_tablebase(test_name: &str) -> (Tablebase, PathBuf) {
+        let directory = std::env::temp_dir().join(format!(
+            "mango_chess_syzygy_{test_name}_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut bytes = WDL_MAGIC.to_vec();
+        // Split by side to move, the group order, the pieces for both sides and padding
+        bytes.extend([1, 0x00, 0x66, 0x55, 0xEE, 0]);
+        bytes.extend([FLAG_SINGLE_VALUE, 4, FLAG_SINGLE_VALUE, 0]);
+        bytes.resize(64, 0);
+        std::fs::write(directory.join("KQvK.rtbw"), bytes).unwrap();
+        (
+            Tablebase::open(directory.to_str().unwrap()).unwrap(),
+            directory,
+        )
+    }
+
+    // A written table and the value it stores at every index, by file and side to move
+    struct WrittenTable {
+        name: &'static str,
+        kind: TableKind,
+        bytes: Vec<u8>,
+        values: Vec<Vec<Vec<u32>>>,
+    }
+
+    // Compressed KQvK and KRvK tables, written once for all tests. The values come from retrograde
+    // analysis, so they do not depend on the probing code.
+    fn fixture_tables() -> &'static [WrittenTable] {
+        static TABLES: OnceLock<Vec<WrittenTable>> = OnceLock::new();
+        TABLES.get_or_init(|| {
+            let mut tables = Vec::new();
+            for (name, piece, piece_attacks, dtz_flags) in [
+                (
+                    "KQvK",
+                    5,
+                    queen_attacks as fn(usize, BitBoard) -> BitBoard,
+                    0,
+                ),
+                ("KRvK", 4, rook_attacks, FLAG_WIDE),
+            ] {
+                let distances = mate_distances(piece_attacks);
+                let pieces = [WHITE_KING, piece, BLACK_KING];
+                let mate_index =
+                    |squares: &[usize]| (squares[0] * 64 + squares[1]) * 64 + squares[2];
+                tables.push(write_table(
+                    name,
+                    TableKind::Wdl,
+                    &pieces,
+                    0,
+                    &[],
+                    |side, squares| match distances[side][mate_index(squares)] {
+                        ILLEGAL => None,
+                        DRAW => Some(2),
+                        _ => Some(if side == 0 { 4 } else { 0 }),
+                    },
+                ));
+                // Wins are always an odd number of plies, so the map skips every other distance
+                let longest_mate = *distances[0].iter().max().unwrap() as u16;
+                let win_map = (0..=longest_mate / 2).map(|moves| 2 * moves).collect();
+                let dtz_maps = [win_map, Vec::new(), Vec::new(), Vec::new()];
+                let flags = FLAG_MAPPED | FLAG_WIN_PLIES | FLAG_LOSS_PLIES | dtz_flags;
+                tables.push(write_table(
+                    name,
+                    TableKind::Dtz,
+                    &pieces,
+                    flags,
+                    &dtz_maps,
+                    |_, squares| {
+                        let distance = distances[0][mate_index(squares)];
+                        (distance > 0).then_some((distance as u32 - 1) / 2)
+                    },
+                ));
+            }
+            tables
+        })
+    }
+
+    fn fixture_tablebase() -> &'static Tablebase {
+        static TABLEBASE: OnceLock<Tablebase> = OnceLock::new();
+        TABLEBASE.get_or_init(|| {
+            tablebase_of(
+                fixture_tables()
+                    .iter()
+                    .map(|table| (table.name, table.kind, table.bytes.clone())),
+            )
+        })
+    }
+
+    fn tablebase_of(tables: impl Iterator<Item = (&'static str, TableKind, Vec<u8>)>) -> Tablebase {
+        let mut tablebase = Tablebase {
+            wdl_tables: HashMap::new(),
+            dtz_tables: HashMap::new(),
+            max_pieces: 0,
+            probe_limit: MAX_PIECES,
+        };
+        for (name, kind, bytes) in tables {
+            let material = table_material(name).unwrap();
+            let lazy_table = LazyTable {
+                name: name.to_string(),
+                path: PathBuf::from(name),
+                table: OnceLock::from(Table::from_bytes(bytes, kind, name)),
+            };
+            match kind {
+                TableKind::Wdl => tablebase.wdl_tables.insert(material, lazy_table),
+                TableKind::Dtz => tablebase.dtz_tables.insert(material, lazy_table),
+            };
+            tablebase.max_pieces = tablebase.max_pieces.max(table_piece_count(name).unwrap());
+        }
+        tablebase
+    }
+
+    // The plies to checkmate in KQvK or KRvK with white and with black to move, found by working
+    // back from the checkmates. Positions are indexed by the white king, the white piece and the
+    // black king. Illegal positions are ILLEGAL and positions that are not lost for black are DRAW.
+    fn mate_distances(piece_attacks: fn(usize, BitBoard) -> BitBoard) -> [Vec<i16>; 2] {
+        let index =
+            |king: usize, piece: usize, black_king: usize| (king * 64 + piece) * 64 + black_king;
+        let adjacent = |a: usize, b: usize| king_attacks(a).0 & 1 << b != 0;
+        // The black king is left out of the occupancy, so it cannot step back along a check
+        let attacked = |square: usize, king: usize, piece: usize| {
+            adjacent(king, square)
+                || (square != piece
+                    && piece_attacks(piece, BitBoard(1 << king)).0 & 1 << square != 0)
+        };
+        let mut distances = [vec![ILLEGAL; 1 << 18], vec![ILLEGAL; 1 << 18]];
+        // Black's legal moves not known to lose yet, or u8::MAX if black can take the piece
+        let mut moves_left = vec![0u8; 1 << 18];
+        let mut queue = VecDeque::new();
+        for (king, piece, black_king) in (0..1 << 18).map(|i| (i >> 12, i >> 6 & 63, i & 63)) {
+            if king == piece
+                || piece == black_king
+                || adjacent(king, black_king)
+                || king == black_king
+            {
+                continue;
+            }
+            let i = index(king, piece, black_king);
+            let in_check = attacked(black_king, king, piece);
+            if !in_check {
+                distances[0][i] = DRAW;
+            }
+            distances[1][i] = DRAW;
+            for square in squares_of(king_attacks(black_king).0) {
+                if square == piece && !adjacent(king, piece) {
+                    moves_left[i] = u8::MAX;
+                    break;
+                } else if square != piece && !attacked(square, king, piece) {
+                    moves_left[i] += 1;
+                }
+            }
+            if moves_left[i] == 0 && in_check {
+                distances[1][i] = 0;
+                queue.push_back((1, i));
+            }
+        }
+        while let Some((side, i)) = queue.pop_front() {
+            let (king, piece, black_king) = (i >> 12, i >> 6 & 63, i & 63);
+            let distance = distances[side][i] + 1;
+            let occupied = 1 << king | 1 << piece | 1 << black_king;
+            if side == 1 {
+                // White wins by moving the king or the piece here
+                let king_origins = squares_of(king_attacks(king).0 & !occupied)
+                    .map(|from| index(from, piece, black_king));
+                let piece_origins =
+                    squares_of(piece_attacks(piece, BitBoard(occupied)).0 & !occupied)
+                        .map(|from| index(king, from, black_king));
+                for j in king_origins.chain(piece_origins) {
+                    if distances[0][j] == DRAW {
+                        distances[0][j] = distance;
+                        queue.push_back((0, j));
+                    }
+                }
+            } else {
+                // Black loses once every move leads to a win for white
+                for from in squares_of(king_attacks(black_king).0 & !occupied) {
+                    let j = index(king, piece, from);
+                    if distances[1][j] == DRAW {
+                        moves_left[j] -= 1;
+                        if moves_left[j] == 0 {
+                            distances[1][j] = distance;
+                            queue.push_back((1, j));
+                        }
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    fn king_attacks(square: usize) -> BitBoard {
+        slider_attacks(square, BitBoard(u64::MAX), &KING_DIRECTIONS)
+    }
+
+    fn rook_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        slider_attacks(square, occupied, &KING_DIRECTIONS[..4])
+    }
+
+    fn queen_attacks(square: usize, occupied: BitBoard) -> BitBoard {
+        slider_attacks(square, occupied, &KING_DIRECTIONS)
+    }
+
+    // Rank and file steps, the orthogonal ones first
+    const KING_DIRECTIONS: [(i32, i32); 8] = [
+        (0, 1),
+        (1, 0),
+        (0, -1),
+        (-1, 0),
+        (1, 1),
+        (1, -1),
+        (-1, 1),
+        (-1, -1),
+    ];
+
+    // Slides from the square in each direction up to and including the first occupied square
+    fn slider_attacks(square: usize, occupied: BitBoard, directions: &[(i32, i32)]) -> BitBoard {
+        let mut attacks = 0;
+        for &(rank_step, file_step) in directions {
+            let (mut rank, mut file) = ((square / 8) as i32, (square % 8) as i32);
+            loop {
+                (rank, file) = (rank + rank_step, file + file_step);
+                if !(0..8).contains(&rank) || !(0..8).contains(&file) {
+                    break;
+                }
+                let target = 1u64 << (rank * 8 + file);
+                attacks |= target;
+                if occupied.0 & target != 0 {
+                    break;
+                }
+            }
+        }
+        BitBoard(attacks)
+    }
+
+    // Writes a table in the compressed Syzygy format, with the pieces in the same order for every
+    // file and side to move, and the lead pawn first. value gives what is stored for the side to
+    // move and the squares of the pieces, or None for illegal positions.
+    fn write_table(
+        name: &'static str,
+        kind: TableKind,
+        pieces: &[u8],
+        flags: u8,
+        dtz_maps: &[Vec<u16>],
+        value: impl Fn(usize, &[usize]) -> Option<u32>,
+    ) -> WrittenTable {
+        let has_pawns = pieces[0] & 7 == 1;
+        let files = if has_pawns { 4 } else { 1 };
+        let sides = if kind == TableKind::Wdl { 2 } else { 1 };
+        let mut bytes = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        }
+        .to_vec();
+        bytes.push(u8::from(sides == 2) | u8::from(has_pawns) << 1);
+        for _ in 0..files {
+            bytes.push(0x00);
+            bytes.extend(pieces.iter().map(|&piece| piece | piece << 4));
+        }
+        bytes.resize(bytes.len().next_multiple_of(2), 0);
+
+        // Reading the header with single value placeholders works out the groups of the pieces
+        let mut placeholder = bytes.clone();
+        for _ in 0..files * sides {
+            placeholder.extend([FLAG_SINGLE_VALUE, 0]);
+        }
+        placeholder.resize(placeholder.len() + 64, 0);
+        let table = Table::from_bytes(placeholder, kind, name).unwrap();
+        let mut values = vec![vec![Vec::new(); sides]; files];
+        for (file, file_values) in values.iter_mut().enumerate() {
+            for (side, side_values) in file_values.iter_mut().enumerate() {
+                let pairs = table.pairs(side, file);
+                let groups = pairs
+                    .group_length
+                    .iter()
+                    .position(|&length| length == 0)
+                    .unwrap();
+                let mut stored = vec![None; pairs.group_index[groups] as usize];
+                for placement in 0..1usize << (6 * pieces.len()) {
+                    let mut squares: Vec<usize> = (0..pieces.len())
+                        .map(|i| placement >> (6 * i) & 63)
+                        .collect();
+                    let lead_pawn_is_in_file =
+                        (8..56).contains(&squares[0]) && edge_distance(squares[0] % 8) == file;
+                    let distinct = (1..squares.len()).all(|i| !squares[..i].contains(&squares[i]));
+                    if (has_pawns && !lead_pawn_is_in_file) || !distinct {
+                        continue;
+                    }
+                    let Some(value) = value(side, &squares) else {
+                        continue;
+                    };
+                    let index = encode(&table, pairs, &mut squares, usize::from(has_pawns));
+                    let entry = &mut stored[index.unwrap() as usize];
+                    // Positions that share an index are mirror images, so they must agree
+                    assert!(entry.is_none_or(|existing| existing == value));
+                    *entry = Some(value);
+                }
+                // Illegal positions take the most common value, which compresses best
+                let mut counts = HashMap::new();
+                for &value in stored.iter().flatten() {
+                    *counts.entry(value).or_insert(0) += 1;
+                }
+                let filler = counts
+                    .into_iter()
+                    .max_by_key(|&(_, count)| count)
+                    .unwrap()
+                    .0;
+                *side_values = stored.iter().map(|value| value.unwrap_or(filler)).collect();
+            }
+        }
+
+        let compressed: Vec<CompressedValues> = values
+            .iter()
+            .flatten()
+            .map(|values| compress(values, flags))
+            .collect();
+        for compressed in &compressed {
+            bytes.extend(&compressed.sizes);
+        }
+        if kind == TableKind::Dtz {
+            for _ in 0..files {
+                if flags & FLAG_WIDE != 0 {
+                    bytes.resize(bytes.len().next_multiple_of(2), 0);
+                    for map in dtz_maps {
+                        bytes.extend((map.len() as u16).to_le_bytes());
+                        bytes.extend(map.iter().flat_map(|value| value.to_le_bytes()));
+                    }
+                } else {
+                    for map in dtz_maps {
+                        bytes.push(map.len() as u8);
+                        bytes.extend(map.iter().map(|&value| value as u8));
+                    }
+                }
+            }
+            bytes.resize(bytes.len().next_multiple_of(2), 0);
+        }
+        for compressed in &compressed {
+            bytes.extend(&compressed.sparse_index);
+        }
+        for compressed in &compressed {
+            bytes.extend(&compressed.block_lengths);
+        }
+        for compressed in &compressed {
+            bytes.resize(bytes.len().next_multiple_of(64), 0);
+            bytes.extend(&compressed.data);
+        }
+        // The decoder reads ahead of the last code it needs
+        bytes.resize(bytes.len() + 64, 0);
+        WrittenTable {
+            name,
+            kind,
+            bytes,
+            values,
+        }
+    }
+
+    struct CompressedValues {
+        sizes: Vec<u8>,
+        sparse_index: Vec<u8>,
+        block_lengths: Vec<u8>,
+        data: Vec<u8>,
+    }
+
+    // Compresses the values of one table into 64 byte blocks of canonical Huffman codes. Every
+    // value is a symbol, and runs of 2, 4, ... 32 equal values are symbols made of two halves.
+    fn compress(values: &[u32], flags: u8) -> CompressedValues {
+        const BLOCK_SIZE_LOG2: u8 = 6;
+        const SPAN_LOG2: u8 = 9;
+        const LONGEST_RUN_LOG2: usize = 5;
+        let mut distinct = values.to_vec();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let mut tree = Vec::new();
+        for &value in &distinct {
+            tree.push((value as usize, 0xFFF));
+            for _ in 0..LONGEST_RUN_LOG2 {
+                let half = tree.len() - 1;
+                tree.push((half, half));
+            }
+        }
+        let mut symbols = Vec::new();
+        let mut position = 0;
+        while position < values.len() {
+            let value = values[position];
+            let run = values[position..]
+                .iter()
+                .take(1 << LONGEST_RUN_LOG2)
+                .take_while(|&&other| other == value)
+                .count();
+            let run_log2 = run.ilog2() as usize;
+            let symbol =
+                distinct.binary_search(&value).unwrap() * (LONGEST_RUN_LOG2 + 1) + run_log2;
+            symbols.push((symbol, 1 << run_log2));
+            position += 1 << run_log2;
+        }
+
+        // Every symbol is counted once more than it is used, so that each gets a code
+        let mut frequencies = vec![1; tree.len()];
+        for &(symbol, _) in &symbols {
+            frequencies[symbol] += 1;
+        }
+        let lengths = huffman_code_lengths(&frequencies);
+        // Canonical codes number the symbols from the longest code to the shortest
+        let mut order: Vec<usize> = (0..tree.len()).collect();
+        order.sort_by_key(|&symbol| std::cmp::Reverse(lengths[symbol]));
+        let mut renumbered = vec![0; tree.len()];
+        for (new_symbol, &symbol) in order.iter().enumerate() {
+            renumbered[symbol] = new_symbol;
+        }
+        let min_length = *lengths.iter().min().unwrap();
+        let max_length = *lengths.iter().max().unwrap();
+        let code_lengths = max_length - min_length + 1;
+        let count = |i: usize| {
+            lengths
+                .iter()
+                .filter(|&&length| length == min_length + i)
+                .count()
+        };
+        let mut lowest_symbols = vec![0; code_lengths];
+        let mut base = vec![0; code_lengths];
+        for i in (0..code_lengths - 1).rev() {
+            lowest_symbols[i] = lowest_symbols[i + 1] + count(i + 1);
+            base[i] = (base[i + 1] + count(i + 1)) / 2;
+        }
+        let code = |symbol: usize| {
+            let i = lengths[symbol] - min_length;
+            (
+                base[i] + renumbered[symbol] - lowest_symbols[i],
+                lengths[symbol],
+            )
+        };
+
+        let block_bits = 8 << BLOCK_SIZE_LOG2;
+        let mut blocks: Vec<(Vec<bool>, usize)> = vec![(Vec::new(), 0)];
+        for &(symbol, value_count) in &symbols {
+            let (code, length) = code(symbol);
+            if blocks.last().unwrap().0.len() + length > block_bits {
+                blocks.push((Vec::new(), 0));
+            }
+            let (bits, block_value_count) = blocks.last_mut().unwrap();
+            bits.extend((0..length).rev().map(|bit| code >> bit & 1 == 1));
+            *block_value_count += value_count;
+        }
+        let mut data = Vec::new();
+        for (bits, _) in &blocks {
+            let mut block = vec![0u8; 1 << BLOCK_SIZE_LOG2];
+            for (i, &bit) in bits.iter().enumerate() {
+                block[i / 8] |= u8::from(bit) << (7 - i % 8);
+            }
+            data.extend(block);
+        }
+        let block_lengths = blocks
+            .iter()
+            .flat_map(|&(_, value_count)| (value_count as u16 - 1).to_le_bytes())
+            .collect();
+        // Every entry points at the middle of its span, past the last block for the last entry
+        let block_starts: Vec<usize> = blocks
+            .iter()
+            .scan(0, |start, &(_, value_count)| {
+                *start += value_count;
+                Some(*start - value_count)
+            })
+            .collect();
+        let span = 1 << SPAN_LOG2;
+        let mut sparse_index = Vec::new();
+        for entry in 0..values.len().div_ceil(span) {
+            let middle = entry * span + span / 2;
+            let block = block_starts.partition_point(|&start| start <= middle) - 1;
+            sparse_index.extend((block as u32).to_le_bytes());
+            sparse_index.extend(
+                u16::try_from(middle - block_starts[block])
+                    .unwrap()
+                    .to_le_bytes(),
+            );
+        }
+
+        let mut sizes = vec![flags, BLOCK_SIZE_LOG2, SPAN_LOG2, 0];
+        sizes.extend((blocks.len() as u32).to_le_bytes());
+        sizes.extend([max_length as u8, min_length as u8]);
+        sizes.extend(
+            lowest_symbols
+                .iter()
+                .flat_map(|&symbol| (symbol as u16).to_le_bytes()),
+        );
+        sizes.extend((tree.len() as u16).to_le_bytes());
+        let mut entries = vec![[0; 3]; tree.len()];
+        for (symbol, &(left, right)) in tree.iter().enumerate() {
+            let (left, right) = match right {
+                0xFFF => (left, right),
+                _ => (renumbered[left], renumbered[right]),
+            };
+            entries[renumbered[symbol]] = [
+                left as u8,
+                (left >> 8) as u8 | ((right & 0xF) << 4) as u8,
+                (right >> 4) as u8,
+            ];
+        }
+        sizes.extend(entries.concat());
+        sizes.resize(sizes.len() + tree.len() % 2, 0);
+        CompressedValues {
+            sizes,
+            sparse_index,
+            block_lengths,
+            data,
+        }
+    }
+
+    fn huffman_code_lengths(frequencies: &[u64]) -> Vec<usize> {
+        use std::cmp::Reverse;
+        // Every node of the tree is kept with the symbols below it
+        let mut nodes: std::collections::BinaryHeap<Reverse<(u64, Vec<usize>)>> = frequencies
+            .iter()
+            .enumerate()
+            .map(|(symbol, &frequency)| Reverse((frequency, vec![symbol])))
+            .collect();
+        let mut lengths = vec![0; frequencies.len()];
+        while nodes.len() > 1 {
+            let Reverse((first_frequency, first)) = nodes.pop().unwrap();
+            let Reverse((second_frequency, second)) = nodes.pop().unwrap();
+            for &symbol in first.iter().chain(&second) {
+                lengths[symbol] += 1;
+            }
+            nodes.push(Reverse((
+                first_frequency + second_frequency,
+                [first, second].concat(),
+            )));
+        }
+        lengths
+    }
+
+    #[test]
+    fn index_tables_have_the_sizes_of_the_format() {
+        let index_tables = index_tables();
+        let king_codes: std::collections::HashSet<u64> = (0..10)
+            .flat_map(|first| {
+                (0..64).filter_map(move |second| {
+                    let code = index_tables.map_kk[first][second];
+                    (code != 0).then_some(code)
+                })
+            })
+            .collect();
+
+        // Every code except 0 appears exactly once among the 462 king placements
+        assert_eq!(king_codes.len(), 461);
+        assert_eq!(king_codes.iter().max(), Some(&461));
+        assert_eq!(index_tables.binomial[3][48], 17296);
+        assert_eq!(index_tables.lead_pawns_size[1], [6, 6, 6, 6]);
+        assert_eq!(index_tables.map_pawns[A2.as_index()], 47);
+        assert_eq!(index_tables.map_pawns[H2.as_index()], 46);
+    }
+
+    #[test]
+    fn table_names_are_recognized() {
+        assert_eq!(table_piece_count("KRPvKP"), Some(5));
+        assert_eq!(table_piece_count("KvK"), Some(2));
+        assert_eq!(table_piece_count("KRvN"), None);
+        assert_eq!(table_piece_count("KQRBNPvKQ"), None);
+        let board_state = position("8/8/8/4k3/8/3p4/8/KRR5 w - - 0 1");
+        let material =
+            side_material(&board_state, White) | side_material(&board_state, Black) << 32;
+        assert_eq!(table_material("KRRvKP"), Some(material));
+        assert_ne!(table_material("KPvKRR"), Some(material));
+        assert!(Tablebase::open("/nonexistent/mango_chess/syzygy").is_err());
+    }
+
+    #[test]
+    fn single_value_table_is_probed_for_both_colors() {
+        let (mut tablebase, directory) = synthetic_tablebase("single_value");
+
+        let white_queen = tablebase.probe_wdl(&position("7k/8/8/8/8/1Q6/8/K7 w - - 0 1"));
+        let black_to_move = tablebase.probe_wdl(&position("7k/8/8/8/8/1Q6/8/K7 b - - 0 1"));
+        let black_queen = tablebase.probe_wdl(&position("7k/8/8/8/8/1q6/8/K7 w - - 0 1"));
+        // The king takes the queen, leaving two bare kings
+        let queen_hangs = tablebase.probe_wdl(&position("8/8/8/8/8/8/6kQ/K7 b - - 0 1"));
+        tablebase.probe_limit = 2;
+        let over_probe_limit = tablebase.probe_wdl(&position("7k/8/8/8/8/1Q6/8/K7 w - - 0 1"));
+        std::fs::remove_dir_all(directory).unwrap();
+
+        assert_eq!(tablebase.max_pieces(), 3);
+        assert_eq!(white_queen, Some(Wdl::Win));
+        assert_eq!(black_to_move, Some(Wdl::Loss));
+        assert_eq!(black_queen, Some(Wdl::Loss));
+        assert_eq!(queen_hangs, Some(Wdl::Draw));
+        assert_eq!(over_probe_limit, None);
+    }
+
+    #[test]
+    fn search_scores_captures_into_the_tablebase() {
+        let (tablebase, directory) = synthetic_tablebase("search");
+        let board_state = position("7k/8/8/3r4/8/8/8/K2Q4 w - - 0 1");
+
+        let result = search_with_tablebase(
+            &board_state,
+            &SearchLimits::depth(2),
+            &AtomicBool::new(false),
+            &mut TranspositionTable::default(),
+            Some(&tablebase),
+            |_| (),
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+
+        assert_eq!(result.best_move, Some((D1, D5).into()));
+        assert_eq!(result.eval, TABLEBASE_WIN_SCORE - 1);
+        assert!(result.tablebase_hits > 0);
+    }
+
+    #[test]
+    fn compressed_tables_decompress_to_the_written_values() {
+        for written_table in fixture_tables() {
+            let table = Table::from_bytes(
+                written_table.bytes.clone(),
+                written_table.kind,
+                written_table.name,
+            )
+            .unwrap();
+            for (file, file_values) in written_table.values.iter().enumerate() {
+                for (side, values) in file_values.iter().enumerate() {
+                    let pairs = table.pairs(side, file);
+                    for (index, &value) in values.iter().enumerate() {
+                        assert_eq!(pairs.decompress(&table.bytes, index as u64), Some(value));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wdl_of_generated_table_positions() {
+        let tablebase = fixture_tablebase();
+        let probe = |fen| tablebase.probe_wdl(&position(fen));
+
+        assert_eq!(probe("7k/8/8/8/8/8/2Q5/K7 w - - 0 1"), Some(Wdl::Win));
+        // The king takes the queen
+        assert_eq!(probe("8/8/8/8/8/8/6kQ/K7 b - - 0 1"), Some(Wdl::Draw));
+        // Stalemate and checkmate
+        assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(probe("8/8/8/8/8/2k5/8/KR6 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("kr6/8/2K5/8/8/8/8/8 b - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/8/8/2k5/1R6/7K b - - 0 1"), Some(Wdl::Draw));
+    }
+
+    #[test]
+    fn dtz_and_root_move_of_generated_table_positions() {
+        let tablebase = fixture_tablebase();
+        let mate_in_one = position("7k/8/6K1/8/8/8/Q7/8 w - - 0 1");
+
+        assert_eq!(tablebase.probe_dtz(&mate_in_one), Some(1));
+        assert_eq!(
+            tablebase.probe_dtz(&position("1k6/8/1K6/8/8/8/8/7R w - - 0 1")),
+            Some(1)
+        );
+        // Black's only move is Kb8, after which Rh8 mates
+        assert_eq!(
+            tablebase.probe_dtz(&position("k7/8/1K6/8/8/8/8/7R b - - 0 1")),
+            Some(-2)
+        );
+        let (best_move, wdl, dtz) = tablebase.probe_root(&mate_in_one).unwrap();
+        let mut board_state = mate_in_one.clone();
+        board_state.make_move(best_move);
+        assert_eq!((wdl, dtz), (Wdl::Win, 1));
+        assert!(is_checkmate(&mut board_state));
+    }
+
+    #[test]
+    fn search_plays_generated_table_moves_at_the_root() {
+        let tablebase = fixture_tablebase();
+        let board_state = position("8/8/8/3k4/8/8/8/4K2R w - - 0 1");
+
+        let result = search_with_tablebase(
+            &board_state,
+            &SearchLimits::depth(4),
+            &AtomicBool::new(false),
+            &mut TranspositionTable::default(),
+            Some(tablebase),
+            |_| (),
+        );
+
+        assert!(result.eval > TABLEBASE_WIN_SCORE - 100);
+        assert_eq!(result.tablebase_hits, 1);
+        assert_eq!(
+            tablebase.probe_wdl(&{
+                let mut board_state = board_state.clone();
+                board_state.make_move(result.best_move.unwrap());
+                board_state
+            }),
+            Some(Wdl::Loss)
+        );
+    }
+
+    #[test]
+    fn corrupt_tables_fail_without_panicking() {
+        let written_table = fixture_tables()
+            .iter()
+            .find(|table| table.name == "KQvK" && table.kind == TableKind::Wdl)
+            .unwrap();
+        let bytes = &written_table.bytes;
+        let positions = [
+            position("7k/8/8/8/8/8/2Q5/K7 w - - 0 1"),
+            position("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"),
+            position("8/8/8/3k4/8/8/8/4K2Q b - - 0 1"),
+        ];
+        let probe_corrupt_table = |bytes: Vec<u8>| {
+            let tablebase = tablebase_of(std::iter::once(("KQvK", TableKind::Wdl, bytes)));
+            for board_state in &positions {
+                tablebase.probe_wdl(board_state);
+            }
+        };
+
+        for length in (0..bytes.len()).step_by(7) {
+            probe_corrupt_table(bytes[..length].to_vec());
+        }
+        for position in 4..bytes.len() {
+            let mut corrupt_bytes = bytes.clone();
+            corrupt_bytes[position] ^= 0xA5;
+            probe_corrupt_table(corrupt_bytes);
+        }
+    }
+
+    #[test]
+    fn wdl_of_tablebase_positions() {
+        let tablebase = Tablebase::open(FIXTURE_DIRECTORY).unwrap();
+        let probe = |fen| tablebase.probe_wdl(&position(fen));
+
+        assert_eq!(tablebase.table_count(), 6);
+        assert_eq!(probe("7k/8/8/8/8/8/2Q5/K7 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/8/8/8/6kQ/K7 b - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+        // Stalemate
+        assert_eq!(probe("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("8/8/8/8/8/2k5/8/KR6 w - - 0 1"), Some(Wdl::Win));
+        // The king takes the rook
+        assert_eq!(probe("8/8/8/8/8/2k5/1R6/7K b - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("kr6/8/2K5/8/8/8/8/8 b - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(probe("8/8/8/8/8/4k3/4P3/4K3 w - - 0 1"), Some(Wdl::Draw));
+        // The king in the corner holds against the rook pawn, but is too far from this one
+        assert_eq!(probe("k7/8/1K6/P7/8/8/8/8 w - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("8/6k1/8/8/8/8/P7/K7 w - - 0 1"), Some(Wdl::Win));
+    }
+
+    // The expected distances come from the solver in generate.py, which knows nothing of the
+    // file format
+    #[test]
+    fn dtz_and_root_move_of_tablebase_positions() {
+        let tablebase = Tablebase::open(FIXTURE_DIRECTORY).unwrap();
+        let probe = |fen| tablebase.probe_dtz(&position(fen));
+        let mate_in_one = position("7k/8/6K1/8/8/8/Q7/8 w - - 0 1");
+
+        assert_eq!(tablebase.probe_dtz(&mate_in_one), Some(1));
+        assert_eq!(probe("1k6/8/1K6/8/8/8/8/7R w - - 0 1"), Some(1));
+        assert_eq!(probe("k7/8/1K6/8/8/8/8/7R b - - 0 1"), Some(-2));
+        assert_eq!(probe("7k/8/8/8/8/8/2Q5/K7 w - - 0 1"), Some(13));
+        assert_eq!(probe("8/8/8/8/8/2k5/8/KR6 w - - 0 1"), Some(27));
+        assert_eq!(probe("kr6/8/2K5/8/8/8/8/8 b - - 0 1"), Some(27));
+        assert_eq!(probe("8/8/8/8/8/8/3k4/K6r w - - 0 1"), Some(-8));
+        // The pawn move is the winning zeroing move
+        assert_eq!(probe("4k3/8/4K3/8/4P3/8/8/8 w - - 0 1"), Some(1));
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(-4));
+        assert_eq!(probe("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1"), Some(-4));
+        assert_eq!(probe("8/8/8/8/8/4k3/4P3/4K3 w - - 0 1"), Some(0));
+        let (best_move, wdl, dtz) = tablebase.probe_root(&mate_in_one).unwrap();
+        let mut board_state = mate_in_one.clone();
+        board_state.make_move(best_move);
+        assert_eq!((wdl, dtz), (Wdl::Win, 1));
+        assert!(is_checkmate(&mut board_state));
+    }
+
+    // Positions on the long diagonals, so that every way of encoding the leading pieces is read
+    // for every order the tables store them in
+    #[test]
+    fn pieces_on_the_diagonal_are_found_in_the_tables() {
+        let tablebase = Tablebase::open(FIXTURE_DIRECTORY).unwrap();
+
+        for (fen, wdl, dtz) in [
+            ("8/8/8/4k3/8/2K5/8/Q7 w - - 0 1", Wdl::Win, 13),
+            ("8/8/8/4k3/8/2K5/8/Q7 b - - 0 1", Wdl::Loss, -14),
+            ("8/8/8/8/7k/2K5/8/Q7 w - - 0 1", Wdl::Win, 9),
+            ("8/8/8/8/7k/2K5/8/Q7 b - - 0 1", Wdl::Loss, -14),
+            ("8/8/8/4k3/8/8/8/Q2K4 b - - 0 1", Wdl::Loss, -18),
+            ("8/8/8/4k3/8/8/8/1Q1K4 w - - 0 1", Wdl::Win, 15),
+            ("8/8/8/4k3/8/8/8/1Q1K4 b - - 0 1", Wdl::Loss, -18),
+            ("8/6k1/8/8/3R4/8/8/K7 w - - 0 1", Wdl::Win, 27),
+            ("8/6k1/8/8/3R4/8/8/K7 b - - 0 1", Wdl::Loss, -28),
+            ("8/8/8/8/3R4/8/5k2/K7 w - - 0 1", Wdl::Win, 21),
+            ("8/8/8/8/3R4/8/5k2/K7 b - - 0 1", Wdl::Loss, -24),
+            ("8/6k1/8/8/3R4/8/8/1K6 w - - 0 1", Wdl::Win, 25),
+            ("8/6k1/8/8/3R4/8/8/1K6 b - - 0 1", Wdl::Loss, -26),
+        ] {
+            let board_state = position(fen);
+            assert_eq!(tablebase.probe_wdl(&board_state), Some(wdl), "{fen}");
+            assert_eq!(tablebase.probe_dtz(&board_state), Some(dtz), "{fen}");
+        }
+    }
+
+    #[test]
+    fn search_plays_tablebase_moves_at_the_root() {
+        let tablebase = Tablebase::open(FIXTURE_DIRECTORY).unwrap();
+        let board_state = position("8/8/8/3k4/8/8/8/4K2R w - - 0 1");
+
+        let result = search_with_tablebase(
+            &board_state,
+            &SearchLimits::depth(4),
+            &AtomicBool::new(false),
+            &mut TranspositionTable::default(),
+            Some(&tablebase),
+            |_| (),
+        );
+
+        assert!(result.eval > TABLEBASE_WIN_SCORE - 100);
+        assert_eq!(result.tablebase_hits, 1);
+        assert_eq!(
+            tablebase.probe_wdl(&{
+                let mut board_state = board_state.clone();
+                board_state.make_move(result.best_move.unwrap());
+                board_state
+            }),
+            Some(Wdl::Loss)
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::board_elements::ChessMove;
+use crate::constants::TABLEBASE_THRESHOLD;
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+pub const MAX_SIZE_MB: usize = 4096;
+
+// How the stored score relates to the true score of the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    // The search failed high, so the true score is at least this
+    Lower,
+    // The search failed low, so the true score is at most this
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TranspositionEntry {
+    pub zobrist_key: u64,
+    pub depth: u8,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<ChessMove>,
+    // The search the entry was stored in, so entries from earlier searches can be replaced
+    generation: u8,
+}
+
+// A fixed size hash table from zobrist keys to earlier search results.
+pub struct TranspositionTable {
+    entries: Vec<Option<TranspositionEntry>>,
+    generation: u8,
+}
+impl Default for TranspositionTable {
+    fn default() -> TranspositionTable {
+        TranspositionTable::new(DEFAULT_SIZE_MB)
+    }
+}
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let entry_size = std::mem::size_of::<Option<TranspositionEntry>>();
+        let entry_count = (size_mb.clamp(1, MAX_SIZE_MB) * 1024 * 1024 / entry_size).max(1);
+        TranspositionTable {
+            entries: vec![None; entry_count],
+            generation: 0,
+        }
+    }
+    pub fn resize(&mut self, size_mb: usize) {
+        *self = TranspositionTable::new(size_mb);
+    }
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.generation = 0;
+    }
+    // Marks every stored entry as belonging to an earlier search
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+    #[inline]
+    fn index(&self, zobrist_key: u64) -> usize {
+        (zobrist_key % self.entries.len() as u64) as usize
+    }
+    // Returns the entry for the position, with mate scores made relative to the probing node at ply.
+    pub fn probe(&self, zobrist_key: u64, ply: u8) -> Option<TranspositionEntry> {
+        let entry = self.entries[self.index(zobrist_key)]?;
+        if entry.zobrist_key != zobrist_key {
+            return None;
+        }
+        Some(TranspositionEntry {
+            score: score_from_table(entry.score, ply),
+            ..entry
+        })
+    }
+    // Stores a search result. An existing entry is kept only if it belongs to a different position
+    // searched deeper in the current search.
+    pub fn store(
+        &mut self,
+        zobrist_key: u64,
+        depth: u8,
+        ply: u8,
+        score: i32,
+        bound: Bound,
+        best_move: Option<ChessMove>,
+    ) {
+        let index = self.index(zobrist_key);
+        if let Some(existing) = self.entries[index] {
+            let is_stale = existing.generation != self.generation;
+            if !is_stale && existing.zobrist_key != zobrist_key && existing.depth > depth {
+                return;
+            }
+        }
+        self.entries[index] = Some(TranspositionEntry {
+            zobrist_key,
+            depth,
+            score: score_to_table(score, ply),
+            bound,
+            best_move,
+            generation: self.generation,
+        });
+    }
+}
+
+// Mate and tablebase scores are relative to the root when searching, but must be relative to the
+// node itself when stored, since the same position can be reached at different distances from
+// the root.
+fn score_to_table(score: i32, ply: u8) -> i32 {
+    if score >= TABLEBASE_THRESHOLD {
+        score + ply as i32
+    } else if score <= -TABLEBASE_THRESHOLD {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+fn score_from_table(score: i32, ply: u8) -> i32 {
+    if score >= TABLEBASE_THRESHOLD {
+        score - ply as i32
+    } else if score <= -TABLEBASE_THRESHOLD {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    #[test]
+    fn stored_entry_can_be_probed() {
+        let mut transposition_table = TranspositionTable::new(1);
+
+        transposition_table.store(42, 5, 0, 120, Bound::Exact, Some((E2, E4).into()));
+        let entry = transposition_table.probe(42, 0).unwrap();
+
+        assert_eq!(entry.depth, 5);
+        assert_eq!(entry.score, 120);
+        assert_eq!(entry.bound, Bound::Exact);
+        assert_eq!(entry.best_move, Some((E2, E4).into()));
+    }
+
+    #[test]
+    fn probing_different_key_in_same_slot_misses() {
+        let mut transposition_table = TranspositionTable::new(1);
+        let slot_count = transposition_table.entries.len() as u64;
+
+        transposition_table.store(7, 5, 0, 120, Bound::Exact, None);
+
+        assert!(transposition_table.probe(7 + slot_count, 0).is_none());
+    }
+
+    #[test]
+    fn shallower_entry_does_not_replace_deeper_entry_from_same_search() {
+        let mut transposition_table = TranspositionTable::new(1);
+        let slot_count = transposition_table.entries.len() as u64;
+
+        transposition_table.store(7, 6, 0, 120, Bound::Exact, None);
+        transposition_table.store(7 + slot_count, 2, 0, -40, Bound::Lower, None);
+
+        assert!(transposition_table.probe(7, 0).is_some());
+        assert!(transposition_table.probe(7 + slot_count, 0).is_none());
+    }
+
+    #[test]
+    fn entries_from_earlier_searches_are_replaced() {
+        let mut transposition_table = TranspositionTable::new(1);
+        let slot_count = transposition_table.entries.len() as u64;
+
+        transposition_table.store(7, 6, 0, 120, Bound::Exact, None);
+        transposition_table.new_search();
+        transposition_table.store(7 + slot_count, 2, 0, -40, Bound::Lower, None);
+
+        assert!(transposition_table.probe(7, 0).is_none());
+        assert!(transposition_table.probe(7 + slot_count, 0).is_some());
+    }
+
+    #[test]
+    fn mate_scores_are_adjusted_for_ply() {
+        let mut transposition_table = TranspositionTable::new(1);
+        // Mate in 3 plies from a node 4 plies from the root
+        let mate_score = MATE_SCORE - 7;
+
+        transposition_table.store(42, 5, 4, mate_score, Bound::Exact, None);
+
+        // Reached 2 plies from the root, the same mate is 5 plies away
+        assert_eq!(
+            transposition_table.probe(42, 2).unwrap().score,
+            MATE_SCORE - 5
+        );
+    }
+
+    #[test]
+    fn tablebase_scores_are_adjusted_for_ply() {
+        let mut transposition_table = TranspositionTable::new(1);
+        // A tablebase loss found 6 plies from the root
+        let loss_score = -TABLEBASE_WIN_SCORE + 6;
+
+        transposition_table.store(42, 3, 6, loss_score, Bound::Upper, None);
+
+        assert_eq!(
+            transposition_table.probe(42, 1).unwrap().score,
+            -TABLEBASE_WIN_SCORE + 1
+        );
+    }
+}
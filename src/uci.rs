@@ -0,0 +1,592 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::board_elements::ChessMove;
+use crate::board_elements::PieceColor;
+use crate::board_elements::PieceColor::*;
+use crate::board_state::BoardState;
+use crate::book::{BookSelection, OpeningBook, DEFAULT_MAX_PLY};
+use crate::search;
+use crate::search::{moves_to_mate, Clock, SearchLimits};
+use crate::syzygy::{Tablebase, MAX_PIECES};
+use crate::transposition_table::{TranspositionTable, DEFAULT_SIZE_MB, MAX_SIZE_MB};
+
+pub const ENGINE_NAME: &str = "mango_chess";
+pub const ENGINE_AUTHOR: &str = "Mathias Augustesen";
+
+// The parameters of a single "go" command
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GoParameters {
+    pub depth: Option<u8>,
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub movestogo: Option<u64>,
+    pub nodes: Option<u64>,
+    pub infinite: bool,
+}
+impl GoParameters {
+    pub fn from_tokens<'a>(mut tokens: impl Iterator<Item = &'a str>) -> GoParameters {
+        let mut go_parameters = GoParameters::default();
+        while let Some(token) = tokens.next() {
+            let mut next_number = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+            match token {
+                "depth" => {
+                    go_parameters.depth =
+                        next_number().map(|depth| u8::try_from(depth).unwrap_or(u8::MAX))
+                }
+                "movetime" => go_parameters.movetime = next_number().map(Duration::from_millis),
+                "wtime" => go_parameters.wtime = next_number().map(Duration::from_millis),
+                "btime" => go_parameters.btime = next_number().map(Duration::from_millis),
+                "winc" => go_parameters.winc = next_number().map(Duration::from_millis),
+                "binc" => go_parameters.binc = next_number().map(Duration::from_millis),
+                "movestogo" => go_parameters.movestogo = next_number(),
+                "nodes" => go_parameters.nodes = next_number(),
+                "infinite" => go_parameters.infinite = true,
+                _ => (),
+            }
+        }
+        go_parameters
+    }
+    // Converts the parameters to search limits, using the clock of the side to move
+    pub fn search_limits(&self, to_move: PieceColor) -> SearchLimits {
+        let (remaining, increment) = match to_move {
+            White => (self.wtime, self.winc),
+            Black => (self.btime, self.binc),
+        };
+        SearchLimits {
+            depth: self.depth,
+            movetime: self.movetime,
+            nodes: self.nodes,
+            clock: remaining.map(|remaining| Clock {
+                remaining,
+                increment: increment.unwrap_or_default(),
+                moves_to_go: self.movestogo,
+            }),
+            infinite: self.infinite,
+        }
+    }
+}
+
+// Formats an eval as a UCI score, either in centipawns or in moves until mate
+pub fn format_score(eval: i32) -> String {
+    match moves_to_mate(eval) {
+        Some(moves) => format!("mate {moves}"),
+        None => format!("cp {eval}"),
+    }
+}
+
+// Formats a line of moves as space separated long algebraic notation
+pub fn pv_to_uci(pv: &[ChessMove]) -> String {
+    pv.iter()
+        .map(|mov| mov.to_uci())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+// The opening book and tablebase settings, shared by the UCI and CECP front ends
+pub(crate) struct EngineOptions {
+    // Whether to play moves from the book before searching, set with the OwnBook option
+    own_book: bool,
+    // Empty until a BookFile is set
+    book: OpeningBook,
+    // None until a SyzygyPath is set
+    pub tablebase: Option<Arc<Tablebase>>,
+    syzygy_probe_limit: usize,
+}
+impl EngineOptions {
+    pub fn new() -> EngineOptions {
+        EngineOptions {
+            own_book: false,
+            book: OpeningBook::from_entries(Vec::new()),
+            tablebase: None,
+            syzygy_probe_limit: MAX_PIECES,
+        }
+    }
+    // Sets one of the book or tablebase options. Returns a message to show the user, or an error
+    // for an unknown option or a bad value. Must not be called while a search holds the tablebase.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<Option<String>, String> {
+        let is_checked = value == "true" || value == "1";
+        match name {
+            "OwnBook" => self.own_book = is_checked,
+            "BookFile" => self.load_book(value)?,
+            // The number of plies of the game that are played from the book
+            "BookDepth" => match value.parse::<u32>() {
+                Ok(max_ply) => self.book.max_ply = max_ply,
+                Err(_) => return Err("BookDepth must be a number of plies".to_string()),
+            },
+            "BestBookMove" => {
+                self.book.selection = match is_checked {
+                    true => BookSelection::Best,
+                    false => BookSelection::WeightedRandom,
+                }
+            }
+            "SyzygyPath" => return self.load_tablebase(value),
+            // Positions with more pieces than this are not probed
+            "SyzygyProbeLimit" => match value.parse::<usize>() {
+                Ok(probe_limit) => {
+                    self.syzygy_probe_limit = probe_limit.min(MAX_PIECES);
+                    if let Some(tablebase) = self.tablebase.as_mut().and_then(Arc::get_mut) {
+                        tablebase.probe_limit = self.syzygy_probe_limit;
+                    }
+                }
+                Err(_) => return Err("SyzygyProbeLimit must be a number of pieces".to_string()),
+            },
+            name => return Err(format!("Unknown option: {name}")),
+        }
+        Ok(None)
+    }
+    // A move from the book for the position, if OwnBook is set and the game is still in book
+    pub fn book_move(&self, board_state: &BoardState) -> Option<ChessMove> {
+        match self.own_book {
+            true => self.book.choose_move(board_state),
+            false => None,
+        }
+    }
+    fn load_tablebase(&mut self, paths: &str) -> Result<Option<String>, String> {
+        self.tablebase = None;
+        if paths.is_empty() || paths == "<empty>" {
+            return Ok(None);
+        }
+        match Tablebase::open(paths) {
+            Ok(mut tablebase) => {
+                tablebase.probe_limit = self.syzygy_probe_limit;
+                let message = format!(
+                    "Found {} tablebase files with up to {} pieces",
+                    tablebase.table_count(),
+                    tablebase.max_pieces()
+                );
+                self.tablebase = Some(Arc::new(tablebase));
+                Ok(Some(message))
+            }
+            Err(error) => Err(format!("Failed to open tablebase {paths}: {error}")),
+        }
+    }
+    // Replaces the book, keeping the BookDepth and BestBookMove settings
+    fn load_book(&mut self, path: &str) -> Result<(), String> {
+        let mut book = match path {
+            "" | "<empty>" => OpeningBook::from_entries(Vec::new()),
+            path => OpeningBook::open(path)
+                .map_err(|error| format!("Failed to open book {path}: {error}"))?,
+        };
+        book.selection = self.book.selection;
+        book.max_ply = self.book.max_ply;
+        self.book = book;
+        Ok(())
+    }
+}
+
+struct UciEngine<W: Write + Send + 'static> {
+    board_state: BoardState,
+    output: Arc<Mutex<W>>,
+    stop: Arc<AtomicBool>,
+    infinite: bool,
+    search_thread: Option<JoinHandle<()>>,
+    transposition_table: Arc<Mutex<TranspositionTable>>,
+    options: EngineOptions,
+}
+impl<W: Write + Send + 'static> UciEngine<W> {
+    fn new(output: W) -> UciEngine<W> {
+        UciEngine {
+            board_state: BoardState::new_game(),
+            output: Arc::new(Mutex::new(output)),
+            stop: Arc::new(AtomicBool::new(false)),
+            infinite: false,
+            search_thread: None,
+            transposition_table: Arc::new(Mutex::new(TranspositionTable::default())),
+            options: EngineOptions::new(),
+        }
+    }
+    fn send(&self, message: &str) {
+        send(&self.output, message);
+    }
+    // Returns false when the engine should exit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_ascii_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                self.send(&format!("id name {ENGINE_NAME}"));
+                self.send(&format!("id author {ENGINE_AUTHOR}"));
+                self.send(&format!(
+                    "option name Hash type spin default {DEFAULT_SIZE_MB} min 1 max {MAX_SIZE_MB}"
+                ));
+                self.send("option name OwnBook type check default false");
+                self.send("option name BookFile type string default <empty>");
+                self.send(&format!(
+                    "option name BookDepth type spin default {DEFAULT_MAX_PLY} min 0 max 1000"
+                ));
+                self.send("option name BestBookMove type check default false");
+                self.send("option name SyzygyPath type string default <empty>");
+                self.send(&format!(
+                    "option name SyzygyProbeLimit type spin default {MAX_PIECES} min 0 max {MAX_PIECES}"
+                ));
+                self.send("uciok");
+            }
+            Some("isready") => self.send("readyok"),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.board_state = BoardState::new_game();
+                self.transposition_table.lock().unwrap().clear();
+            }
+            Some("position") => {
+                self.stop_search();
+                self.set_position(tokens);
+            }
+            Some("go") => {
+                self.stop_search();
+                self.go(GoParameters::from_tokens(tokens));
+            }
+            Some("stop") => self.stop_search(),
+            // Prints the current position, for debugging from a terminal
+            Some("d") => self.send(&format!(
+                "{}\nFen: {}",
+                self.board_state.board, self.board_state
+            )),
+            Some("setoption") => {
+                self.stop_search();
+                self.set_option(tokens);
+            }
+            Some("quit") => {
+                self.stop_search();
+                return false;
+            }
+            Some(unknown) => self.send(&format!("info string Unknown command: {unknown}")),
+            None => (),
+        }
+        true
+    }
+    fn set_position<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        let mut board_state = match tokens.next() {
+            Some("startpos") => BoardState::new_game(),
+            Some("fen") => {
+                let fen: Vec<&str> = tokens
+                    .by_ref()
+                    .take_while(|&token| token != "moves")
+                    .collect();
+                match BoardState::from_fen(&fen.join(" ")) {
+                    Ok(board_state) => board_state,
+                    Err(error) => {
+                        self.send(&format!("info string {error}"));
+                        return;
+                    }
+                }
+            }
+            _ => {
+                self.send("info string Expected startpos or fen after position");
+                return;
+            }
+        };
+        for token in tokens.skip_while(|&token| token == "moves") {
+            match board_state.parse_uci_move(token) {
+                Some(mov) => board_state.make_move(mov),
+                None => {
+                    self.send(&format!("info string Illegal move: {token}"));
+                    return;
+                }
+            }
+        }
+        self.board_state = board_state;
+    }
+    fn set_option<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        let name: Vec<&str> = tokens
+            .by_ref()
+            .skip_while(|&token| token == "name")
+            .take_while(|&token| token != "value")
+            .collect();
+        let value: Vec<&str> = tokens.collect();
+        match name.join(" ").as_str() {
+            "Hash" => match value.join(" ").parse::<usize>() {
+                Ok(size_mb) => self.transposition_table.lock().unwrap().resize(size_mb),
+                Err(_) => self.send("info string Hash must be a number of megabytes"),
+            },
+            // The search has been stopped, so nothing else holds the tablebase
+            name => match self.options.set(name, &value.join(" ")) {
+                Ok(Some(message)) | Err(message) => self.send(&format!("info string {message}")),
+                Ok(None) => (),
+            },
+        }
+    }
+    fn go(&mut self, go_parameters: GoParameters) {
+        if !go_parameters.infinite {
+            if let Some(book_move) = self.options.book_move(&self.board_state) {
+                self.send("info string book move");
+                self.send(&format!("bestmove {}", book_move.to_uci()));
+                return;
+            }
+        }
+        self.stop.store(false, Ordering::SeqCst);
+        self.infinite = go_parameters.infinite;
+        let board_state = self.board_state.clone();
+        let output = Arc::clone(&self.output);
+        let stop = Arc::clone(&self.stop);
+        let transposition_table = Arc::clone(&self.transposition_table);
+        let tablebase = self.options.tablebase.clone();
+        self.search_thread = Some(std::thread::spawn(move || {
+            let mut transposition_table = transposition_table.lock().unwrap();
+            think(
+                &board_state,
+                &go_parameters,
+                &stop,
+                &mut transposition_table,
+                tablebase.as_deref(),
+                &output,
+            )
+        }));
+    }
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.wait_for_search();
+    }
+    fn wait_for_search(&mut self) {
+        if let Some(search_thread) = self.search_thread.take() {
+            if self.infinite {
+                self.stop.store(true, Ordering::SeqCst);
+            }
+            search_thread.join().unwrap();
+        }
+    }
+}
+
+fn think<W: Write>(
+    board_state: &BoardState,
+    go_parameters: &GoParameters,
+    stop: &AtomicBool,
+    transposition_table: &mut TranspositionTable,
+    tablebase: Option<&Tablebase>,
+    output: &Mutex<W>,
+) {
+    let result = search::search_with_tablebase(
+        board_state,
+        &go_parameters.search_limits(board_state.to_move),
+        stop,
+        transposition_table,
+        tablebase,
+        |progress| {
+            send(
+                output,
+                &format!(
+                    "info depth {} seldepth {} score {} nodes {} nps {} tbhits {} time {} pv {}",
+                    progress.depth,
+                    progress.seldepth,
+                    format_score(progress.eval),
+                    progress.nodes,
+                    progress.nps(),
+                    progress.tablebase_hits,
+                    progress.elapsed.as_millis(),
+                    pv_to_uci(&progress.pv)
+                ),
+            )
+        },
+    );
+    // In infinite mode the best move may only be sent once the GUI says stop
+    if go_parameters.infinite {
+        while !stop.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+    match result.best_move {
+        Some(best_move) => send(output, &format!("bestmove {}", best_move.to_uci())),
+        None => send(output, "bestmove 0000"),
+    }
+}
+
+pub(crate) fn send<W: Write>(output: &Mutex<W>, message: &str) {
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{message}").unwrap();
+    output.flush().unwrap();
+}
+
+// Runs the UCI loop until quit is received or the input ends.
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) {
+    let mut engine = UciEngine::new(output);
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle_command(line.trim()) {
+            return;
+        }
+    }
+    engine.wait_for_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_elements::PieceKind::Queen;
+    use crate::book::{encode_move, BookEntry};
+    use crate::constants::*;
+    use std::io::Cursor;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_script(script: &str) -> String {
+        let buffer = SharedBuffer::default();
+        run(Cursor::new(script.to_string()), buffer.clone());
+        let output = buffer.0.lock().unwrap();
+        String::from_utf8(output.clone()).unwrap()
+    }
+
+    #[test]
+    fn uci_handshake_identifies_engine() {
+        let output = run_script("uci\nisready\nquit\n");
+
+        assert!(output.contains("id name mango_chess"));
+        assert!(output.contains("uciok"));
+        assert!(output.contains("readyok"));
+    }
+
+    #[test]
+    fn go_depth_from_startpos_with_moves_returns_legal_bestmove() {
+        let output = run_script("position startpos moves g1f3 b8c6\ngo depth 2\n");
+
+        assert!(!output.contains("Illegal move"));
+        let best_move = output
+            .lines()
+            .find_map(|line| line.strip_prefix("bestmove "))
+            .unwrap();
+        let mut board_state = BoardState::new_game();
+        board_state.make_move((G1, F3).into());
+        board_state.make_move((B8, C6).into());
+        assert!(board_state.parse_uci_move(best_move).is_some());
+    }
+
+    #[test]
+    fn go_from_fen_finds_mate_in_two() {
+        let output = run_script(
+            "position fen r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 0\ngo depth 3\n",
+        );
+
+        assert!(output.contains("bestmove d5f6"));
+        assert!(output.contains("score mate 2"));
+        assert!(output.contains("pv d5f6 g7f6 c4f7"));
+    }
+
+    #[test]
+    fn hash_option_is_announced_and_accepted() {
+        let output = run_script("uci\nsetoption name Hash value 4\ngo depth 2\n");
+
+        assert!(output.contains("option name Hash type spin default 16 min 1"));
+        assert!(!output.contains("Unknown option"));
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn book_move_is_played_when_own_book_is_set() {
+        let board_state = BoardState::new_game();
+        let entry = BookEntry {
+            key: board_state.polyglot_key(),
+            raw_move: encode_move(&board_state, (G1, F3).into()),
+            weight: 1,
+            learn: 0,
+        };
+        let path = std::env::temp_dir().join(format!(
+            "mango_chess_uci_book_move_is_played_{}.bin",
+            std::process::id()
+        ));
+        std::fs::write(&path, entry.to_bytes()).unwrap();
+
+        let output = run_script(&format!(
+            "setoption name OwnBook value true\nsetoption name BookFile value {}\n\
+             position startpos\ngo depth 3\nposition startpos moves g1f3\ngo depth 1\n",
+            path.display()
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(output.contains("info string book move\nbestmove g1f3\n"));
+        assert_eq!(output.matches("info string book move").count(), 1);
+        assert_eq!(output.matches("bestmove").count(), 2);
+    }
+
+    #[test]
+    fn d_command_prints_fen_of_current_position() {
+        let output = run_script("position startpos moves e2e4\nd\nquit\n");
+
+        assert!(output.contains("Fen: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"));
+    }
+
+    #[test]
+    fn illegal_move_in_position_command_is_reported() {
+        let output = run_script("position startpos moves e2e5\nquit\n");
+
+        assert!(output.contains("info string Illegal move: e2e5"));
+    }
+
+    #[test]
+    fn parsing_go_command_reads_all_parameters() {
+        let go_parameters = GoParameters::from_tokens(
+            "wtime 60000 btime 30000 winc 1000 binc 500 movestogo 20 nodes 5000 depth 7"
+                .split_ascii_whitespace(),
+        );
+
+        assert_eq!(go_parameters.wtime, Some(Duration::from_millis(60000)));
+        assert_eq!(go_parameters.btime, Some(Duration::from_millis(30000)));
+        assert_eq!(go_parameters.winc, Some(Duration::from_millis(1000)));
+        assert_eq!(go_parameters.binc, Some(Duration::from_millis(500)));
+        assert_eq!(go_parameters.movestogo, Some(20));
+        assert_eq!(go_parameters.nodes, Some(5000));
+        assert_eq!(go_parameters.depth, Some(7));
+    }
+
+    #[test]
+    fn go_depth_beyond_the_depth_type_saturates() {
+        let go_parameters = GoParameters::from_tokens("depth 300".split_ascii_whitespace());
+
+        assert_eq!(go_parameters.depth, Some(u8::MAX));
+    }
+
+    #[test]
+    fn search_limits_use_clock_of_side_to_move() {
+        let go_parameters = GoParameters::from_tokens(
+            "wtime 60000 btime 30000 winc 1000 movestogo 30".split_ascii_whitespace(),
+        );
+
+        let white_clock = go_parameters.search_limits(White).clock.unwrap();
+        let black_clock = go_parameters.search_limits(Black).clock.unwrap();
+
+        assert_eq!(white_clock.remaining, Duration::from_millis(60000));
+        assert_eq!(white_clock.increment, Duration::from_millis(1000));
+        assert_eq!(black_clock.remaining, Duration::from_millis(30000));
+        assert_eq!(black_clock.increment, Duration::ZERO);
+        assert_eq!(black_clock.moves_to_go, Some(30));
+    }
+
+    #[test]
+    fn go_nodes_finishes_with_bestmove() {
+        let output = run_script("position startpos\ngo nodes 500\n");
+
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn go_infinite_searches_until_stop() {
+        let output = run_script("position startpos\ngo infinite\nisready\nstop\n");
+
+        let readyok = output.find("readyok").unwrap();
+        let bestmove = output.find("bestmove ").unwrap();
+        assert!(readyok < bestmove);
+    }
+
+    #[test]
+    fn promotion_moves_are_formatted_with_piece_suffix() {
+        let board_state = BoardState::from_fen("7k/P7/8/8/8/8/8/7K w - - 0 1").unwrap();
+
+        let mov = board_state.parse_uci_move("a7a8q").unwrap();
+
+        assert_eq!(mov.promotion.map(|piece| piece.kind), Some(Queen));
+        assert_eq!(mov.to_uci(), "a7a8q");
+    }
+}
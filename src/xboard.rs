@@ -0,0 +1,552 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::board_elements::ChessMove;
+use crate::board_elements::GameResult;
+use crate::board_elements::PieceColor;
+use crate::board_state::BoardState;
+use crate::book::DEFAULT_MAX_PLY;
+use crate::search;
+use crate::search::{moves_to_mate, Clock, SearchLimits};
+use crate::syzygy::MAX_PIECES;
+use crate::transposition_table::TranspositionTable;
+use crate::uci::{pv_to_uci, send, EngineOptions, ENGINE_NAME};
+
+// The game as seen by the GUI, shared with the search thread so it can play the engine's move
+struct Game {
+    board_state: BoardState,
+    // Positions before each move, used by undo and remove
+    history: Vec<BoardState>,
+}
+impl Game {
+    fn new(board_state: BoardState) -> Game {
+        Game {
+            board_state,
+            history: Vec::new(),
+        }
+    }
+    fn play(&mut self, mov: ChessMove) {
+        self.history.push(self.board_state.clone());
+        self.board_state.make_move(mov);
+    }
+    fn undo(&mut self) {
+        if let Some(board_state) = self.history.pop() {
+            self.board_state = board_state;
+        }
+    }
+}
+
+// The settings of the level, st, sd and time commands
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TimeControl {
+    pub moves_per_session: u64,
+    pub base: Duration,
+    pub increment: Duration,
+    pub time_per_move: Option<Duration>,
+    pub max_depth: Option<u8>,
+    // Remaining time on the engine's clock, as reported by the time command
+    pub engine_time: Option<Duration>,
+}
+impl TimeControl {
+    // Parses the arguments of "level MPS BASE INC", where BASE is minutes or minutes:seconds
+    pub fn set_level<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) -> Option<()> {
+        let moves_per_session = tokens.next()?.parse().ok()?;
+        let base = tokens.next()?;
+        let base_seconds = match base.split_once(':') {
+            Some((minutes, seconds)) => {
+                minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?
+            }
+            None => base.parse::<u64>().ok()? * 60,
+        };
+        let increment = tokens.next()?.parse::<f64>().ok()?;
+        self.moves_per_session = moves_per_session;
+        self.base = Duration::from_secs(base_seconds);
+        self.increment = Duration::from_secs_f64(increment);
+        self.time_per_move = None;
+        Some(())
+    }
+    // moves_played is the number of moves the engine has made so far in the game.
+    pub fn search_limits(&self, moves_played: u64) -> SearchLimits {
+        let remaining = self
+            .engine_time
+            .or(Some(self.base).filter(|base| !base.is_zero()));
+        let moves_to_go = match self.moves_per_session {
+            0 => None,
+            moves_per_session => Some(moves_per_session - moves_played % moves_per_session),
+        };
+        SearchLimits {
+            depth: self.max_depth,
+            movetime: self.time_per_move,
+            clock: remaining.map(|remaining| Clock {
+                remaining,
+                increment: self.increment,
+                moves_to_go,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+struct XBoardEngine<W: Write + Send + 'static> {
+    game: Arc<Mutex<Game>>,
+    output: Arc<Mutex<W>>,
+    // Set by "?" to make the engine play the best move found so far
+    stop: Arc<AtomicBool>,
+    // Set when the game changes under the search, so the move it finds must be thrown away
+    abort: Arc<AtomicBool>,
+    search_thread: Option<JoinHandle<()>>,
+    // The side played by the engine, or None in force mode
+    engine_color: Option<PieceColor>,
+    time_control: TimeControl,
+    post: bool,
+    transposition_table: Arc<Mutex<TranspositionTable>>,
+    options: EngineOptions,
+}
+impl<W: Write + Send + 'static> XBoardEngine<W> {
+    fn new(output: W) -> XBoardEngine<W> {
+        XBoardEngine {
+            game: Arc::new(Mutex::new(Game::new(BoardState::new_game()))),
+            output: Arc::new(Mutex::new(output)),
+            stop: Arc::new(AtomicBool::new(false)),
+            abort: Arc::new(AtomicBool::new(false)),
+            search_thread: None,
+            engine_color: Some(PieceColor::Black),
+            time_control: TimeControl::default(),
+            post: false,
+            transposition_table: Arc::new(Mutex::new(TranspositionTable::default())),
+            options: EngineOptions::new(),
+        }
+    }
+    fn send(&self, message: &str) {
+        send(&self.output, message);
+    }
+    // Returns false when the engine should exit
+    fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_ascii_whitespace();
+        let Some(command) = tokens.next() else {
+            return true;
+        };
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "otim" | "name" | "rating" | "ics" => (),
+            "protover" => {
+                self.send(&format!(
+                    "feature myname=\"{ENGINE_NAME}\" ping=1 setboard=1 usermove=1 playother=1 \
+                     memory=1 egt=\"syzygy\" colors=0 sigint=0 sigterm=0 done=0"
+                ));
+                for option in [
+                    "OwnBook -check 0".to_string(),
+                    "BookFile -file ".to_string(),
+                    format!("BookDepth -spin {DEFAULT_MAX_PLY} 0 1000"),
+                    "BestBookMove -check 0".to_string(),
+                    format!("SyzygyProbeLimit -spin {MAX_PIECES} 0 {MAX_PIECES}"),
+                ] {
+                    self.send(&format!("feature option=\"{option}\""));
+                }
+                self.send("feature done=1");
+            }
+            // Options announced with feature option are set as "option NAME=VALUE"
+            "option" => {
+                self.abort_search();
+                let option = line["option".len()..].trim();
+                let (name, value) = option.split_once('=').unwrap_or((option, ""));
+                self.set_option(name, value);
+            }
+            // The GUI passes the tablebase directories as "egtpath syzygy PATHS"
+            "egtpath" => {
+                self.abort_search();
+                match tokens.next() {
+                    Some("syzygy") => {
+                        let paths: Vec<&str> = tokens.collect();
+                        self.set_option("SyzygyPath", &paths.join(" "));
+                    }
+                    _ => self.send(&format!("Error (unsupported tablebase): {line}")),
+                }
+            }
+            "new" => {
+                self.abort_search();
+                *self.game.lock().unwrap() = Game::new(BoardState::new_game());
+                self.engine_color = Some(PieceColor::Black);
+                self.time_control.max_depth = None;
+                self.time_control.time_per_move = None;
+                self.transposition_table.lock().unwrap().clear();
+            }
+            "force" | "result" => {
+                self.abort_search();
+                self.engine_color = None;
+            }
+            "go" => {
+                self.abort_search();
+                self.engine_color = Some(self.to_move());
+                self.start_thinking();
+            }
+            "playother" => {
+                self.abort_search();
+                self.engine_color = Some(self.to_move().opposite());
+            }
+            "usermove" => match tokens.next() {
+                Some(user_move) => self.user_move(user_move),
+                None => self.send("Error (missing move): usermove"),
+            },
+            "setboard" => {
+                self.abort_search();
+                let fen: Vec<&str> = tokens.collect();
+                match BoardState::from_fen(&fen.join(" ")) {
+                    Ok(board_state) => *self.game.lock().unwrap() = Game::new(board_state),
+                    Err(_) => self.send("tellusererror Illegal position"),
+                }
+            }
+            "level" => {
+                if self.time_control.set_level(tokens).is_none() {
+                    self.send(&format!("Error (bad arguments): {line}"));
+                }
+            }
+            "st" => match tokens
+                .next()
+                .and_then(|seconds| seconds.parse::<f64>().ok())
+            {
+                Some(seconds) => {
+                    self.time_control.time_per_move = Some(Duration::from_secs_f64(seconds))
+                }
+                None => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            "sd" => match tokens.next().and_then(|depth| depth.parse::<u8>().ok()) {
+                Some(depth) => self.time_control.max_depth = Some(depth),
+                None => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            // The GUI sends the total memory the engine may use in megabytes
+            "memory" => match tokens.next().and_then(|size| size.parse::<usize>().ok()) {
+                Some(size_mb) => {
+                    self.abort_search();
+                    self.transposition_table.lock().unwrap().resize(size_mb);
+                }
+                None => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            "time" => match tokens.next().and_then(|time| time.parse::<u64>().ok()) {
+                // The engine's clock is sent in centiseconds
+                Some(centiseconds) => {
+                    self.time_control.engine_time = Some(Duration::from_millis(centiseconds * 10))
+                }
+                None => self.send(&format!("Error (bad arguments): {line}")),
+            },
+            "undo" => {
+                self.abort_search();
+                self.game.lock().unwrap().undo();
+            }
+            "remove" => {
+                self.abort_search();
+                let mut game = self.game.lock().unwrap();
+                game.undo();
+                game.undo();
+            }
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => self.send(&format!("pong {}", tokens.next().unwrap_or_default())),
+            "?" => self.stop.store(true, Ordering::SeqCst),
+            "quit" => {
+                self.abort_search();
+                return false;
+            }
+            // Moves may also be sent without the usermove prefix
+            _ if self
+                .game
+                .lock()
+                .unwrap()
+                .board_state
+                .parse_uci_move(command)
+                .is_some() =>
+            {
+                self.user_move(command)
+            }
+            _ => self.send(&format!("Error (unknown command): {command}")),
+        }
+        true
+    }
+    fn set_option(&mut self, name: &str, value: &str) {
+        match self.options.set(name, value) {
+            Ok(Some(message)) => self.send(&format!("telluser {message}")),
+            Ok(None) => (),
+            Err(error) => self.send(&format!("tellusererror {error}")),
+        }
+    }
+    fn to_move(&self) -> PieceColor {
+        self.game.lock().unwrap().board_state.to_move
+    }
+    fn user_move(&mut self, user_move: &str) {
+        self.wait_for_search();
+        {
+            let mut game = self.game.lock().unwrap();
+            match game.board_state.parse_uci_move(user_move) {
+                Some(mov) => game.play(mov),
+                None => {
+                    drop(game);
+                    self.send(&format!("Illegal move: {user_move}"));
+                    return;
+                }
+            }
+        }
+        if self.engine_color == Some(self.to_move()) {
+            self.start_thinking();
+        }
+    }
+    fn start_thinking(&mut self) {
+        let (board_state, moves_played) = {
+            let game = self.game.lock().unwrap();
+            (game.board_state.clone(), game.history.len() as u64 / 2)
+        };
+        if let Some(book_move) = self.options.book_move(&board_state) {
+            let mut game = self.game.lock().unwrap();
+            game.play(book_move);
+            self.send(&format!("move {}", book_move.to_uci()));
+            if let Some(result) = game.board_state.get_game_winner() {
+                self.send(&game_result(result));
+            }
+            return;
+        }
+        let search_limits = self.time_control.search_limits(moves_played);
+        let post = self.post;
+        let game = Arc::clone(&self.game);
+        let output = Arc::clone(&self.output);
+        let stop = Arc::clone(&self.stop);
+        let abort = Arc::clone(&self.abort);
+        let transposition_table = Arc::clone(&self.transposition_table);
+        let tablebase = self.options.tablebase.clone();
+        self.search_thread = Some(std::thread::spawn(move || {
+            let result = search::search_with_tablebase(
+                &board_state,
+                &search_limits,
+                &stop,
+                &mut transposition_table.lock().unwrap(),
+                tablebase.as_deref(),
+                |progress| {
+                    if post {
+                        // Thinking output is depth, score, time in centiseconds, nodes and pv
+                        send(
+                            &output,
+                            &format!(
+                                "{} {} {} {} {}",
+                                progress.depth,
+                                format_score(progress.eval),
+                                progress.elapsed.as_millis() / 10,
+                                progress.nodes,
+                                pv_to_uci(&progress.pv)
+                            ),
+                        );
+                    }
+                },
+            );
+            if abort.load(Ordering::SeqCst) {
+                return;
+            }
+            let mut game = game.lock().unwrap();
+            if let Some(best_move) = result.best_move {
+                game.play(best_move);
+                send(&output, &format!("move {}", best_move.to_uci()));
+            }
+            if let Some(result) = game.board_state.get_game_winner() {
+                send(&output, &game_result(result));
+            }
+        }));
+    }
+    fn abort_search(&mut self) {
+        self.abort.store(true, Ordering::SeqCst);
+        self.stop.store(true, Ordering::SeqCst);
+        self.wait_for_search();
+    }
+    fn wait_for_search(&mut self) {
+        if let Some(search_thread) = self.search_thread.take() {
+            search_thread.join().unwrap();
+        }
+        self.abort.store(false, Ordering::SeqCst);
+        self.stop.store(false, Ordering::SeqCst);
+    }
+}
+
+// Mate scores are sent as 100000 + N for mate in N moves, and negated when getting mated
+fn format_score(eval: i32) -> i32 {
+    match moves_to_mate(eval) {
+        Some(moves) if moves > 0 => 100_000 + moves,
+        Some(moves) => -100_000 + moves,
+        None => eval,
+    }
+}
+
+fn game_result(result: GameResult) -> String {
+    match result {
+        GameResult::Winner(PieceColor::White) => "1-0 {White mates}".to_string(),
+        GameResult::Winner(PieceColor::Black) => "0-1 {Black mates}".to_string(),
+        GameResult::Draw(reason) => format!("1/2-1/2 {{Draw by {reason}}}"),
+    }
+}
+
+// Runs the CECP loop until quit is received or the input ends.
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) {
+    let mut engine = XBoardEngine::new(output);
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle_command(line.trim()) {
+            return;
+        }
+    }
+    engine.wait_for_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::{encode_move, BookEntry};
+    use crate::constants::*;
+    use std::io::Cursor;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_script(script: &str) -> String {
+        let buffer = SharedBuffer::default();
+        run(Cursor::new(script.to_string()), buffer.clone());
+        let output = buffer.0.lock().unwrap();
+        String::from_utf8(output.clone()).unwrap()
+    }
+
+    #[test]
+    fn protover_announces_features() {
+        let output = run_script("xboard\nprotover 2\nping 7\nquit\n");
+
+        assert!(output.contains("feature myname=\"mango_chess\""));
+        assert!(output.contains("usermove=1"));
+        assert!(output.contains("done=1"));
+        assert!(output.contains("pong 7"));
+    }
+
+    #[test]
+    fn engine_replies_to_user_move_as_black() {
+        let output = run_script("xboard\nprotover 2\nnew\nsd 2\nusermove g1f3\n");
+
+        assert!(output.lines().any(|line| line.starts_with("move ")));
+    }
+
+    #[test]
+    fn engine_finds_mate_in_two_after_setboard_and_go() {
+        let output = run_script(
+            "xboard\nforce\nsetboard r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 0\nsd 3\npost\ngo\n",
+        );
+
+        assert!(output.contains("move d5f6"));
+        assert!(output.lines().any(|line| line.starts_with("3 100002 ")));
+    }
+
+    #[test]
+    fn memory_command_is_accepted() {
+        let output = run_script("xboard\nprotover 2\nmemory 4\nnew\nsd 2\nusermove g1f3\n");
+
+        assert!(output.contains("memory=1"));
+        assert!(!output.contains("Error"));
+        assert!(output.lines().any(|line| line.starts_with("move ")));
+    }
+
+    #[test]
+    fn book_move_is_played_when_own_book_is_set() {
+        let board_state = BoardState::new_game();
+        let entry = BookEntry {
+            key: board_state.polyglot_key(),
+            raw_move: encode_move(&board_state, (G1, F3).into()),
+            weight: 1,
+            learn: 0,
+        };
+        let path = std::env::temp_dir().join(format!(
+            "mango_chess_xboard_book_move_is_played_{}.bin",
+            std::process::id()
+        ));
+        std::fs::write(&path, entry.to_bytes()).unwrap();
+
+        let output = run_script(&format!(
+            "xboard\nprotover 2\noption OwnBook=1\noption BookFile={}\nnew\nsd 1\ngo\n",
+            path.display()
+        ));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(output.contains("feature option=\"OwnBook -check 0\""));
+        assert!(output.contains("\nmove g1f3\n"));
+        assert!(!output.contains("Error"));
+    }
+
+    #[test]
+    fn bad_option_values_are_reported_to_the_user() {
+        let output = run_script("xboard\noption BookDepth=deep\noption Ponder=1\n");
+
+        assert!(output.contains("tellusererror BookDepth must be a number of plies"));
+        assert!(output.contains("tellusererror Unknown option: Ponder"));
+    }
+
+    #[test]
+    fn force_mode_does_not_reply_to_moves() {
+        let output = run_script("xboard\nnew\nforce\nusermove g1f3\nusermove g8f6\n");
+
+        assert!(!output.contains("move "));
+    }
+
+    #[test]
+    fn illegal_user_move_is_rejected() {
+        let output = run_script("xboard\nforce\nusermove e2e5\n");
+
+        assert!(output.contains("Illegal move: e2e5"));
+    }
+
+    #[test]
+    fn undo_takes_back_the_last_move() {
+        let output = run_script("xboard\nforce\nusermove g1f3\nundo\nusermove g1h3\n");
+
+        assert!(!output.contains("Illegal move"));
+    }
+
+    #[test]
+    fn level_with_minutes_and_seconds_is_parsed() {
+        let mut time_control = TimeControl::default();
+
+        time_control.set_level("40 2:30 1".split_ascii_whitespace());
+
+        assert_eq!(time_control.moves_per_session, 40);
+        assert_eq!(time_control.base, Duration::from_secs(150));
+        assert_eq!(time_control.increment, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn search_limits_count_moves_left_in_session() {
+        let mut time_control = TimeControl::default();
+        time_control.set_level("40 5 0".split_ascii_whitespace());
+        time_control.engine_time = Some(Duration::from_secs(100));
+
+        let clock = time_control.search_limits(30).clock.unwrap();
+
+        assert_eq!(clock.remaining, Duration::from_secs(100));
+        assert_eq!(clock.moves_to_go, Some(10));
+    }
+
+    #[test]
+    fn st_and_sd_become_movetime_and_depth_limits() {
+        let time_control = TimeControl {
+            time_per_move: Some(Duration::from_millis(200)),
+            max_depth: Some(3),
+            ..Default::default()
+        };
+
+        let search_limits = time_control.search_limits(0);
+
+        assert_eq!(search_limits.movetime, Some(Duration::from_millis(200)));
+        assert_eq!(search_limits.depth, Some(3));
+        assert_eq!(search_limits.clock, None);
+    }
+}
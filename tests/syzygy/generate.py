@@ -0,0 +1,637 @@
+#!/usr/bin/env python3
+"""Writes the KQvK, KRvK and KPvK Syzygy tables in this directory.
+
+The tests in src/syzygy.rs read these files, so this script shares no code with the engine: the
+outcomes come from the retrograde solver below, the indexes from the encoding of the Syzygy
+format, and the values are compressed with pairs of symbols and canonical Huffman codes the way
+the format expects. Run it from anywhere with python3; it rewrites the six files next to it.
+
+With --probe FEN... it prints the outcome and distance to zeroing of each position instead, so
+the expected values in the tests can be checked against this independent solver.
+"""
+
+import functools
+import heapq
+import os
+import sys
+from collections import Counter
+
+WDL_MAGIC = bytes([0x71, 0xE8, 0x23, 0x5D])
+DTZ_MAGIC = bytes([0xD7, 0x66, 0x0C, 0xA5])
+FLAG_MAPPED = 2
+FLAG_WIN_PLIES = 4
+FLAG_LOSS_PLIES = 8
+FLAG_WIDE = 16
+
+W_PAWN, W_ROOK, W_QUEEN, W_KING, B_KING = 1, 4, 5, 6, 14
+
+KING_STEPS = [(0, 1), (1, 0), (0, -1), (-1, 0), (1, 1), (1, -1), (-1, 1), (-1, -1)]
+ROOK_STEPS = KING_STEPS[:4]
+
+
+def rank_of(square):
+    return square >> 3
+
+
+def file_of(square):
+    return square & 7
+
+
+def walk(square, steps, occupied, slide):
+    targets = 0
+    for rank_step, file_step in steps:
+        rank, file = rank_of(square), file_of(square)
+        while True:
+            rank, file = rank + rank_step, file + file_step
+            if not (0 <= rank < 8 and 0 <= file < 8):
+                break
+            target = 1 << (rank * 8 + file)
+            targets |= target
+            if not slide or occupied & target:
+                break
+    return targets
+
+
+KING = [walk(square, KING_STEPS, 0, False) for square in range(64)]
+
+
+@functools.lru_cache(maxsize=None)
+def queen_attacks(square, occupied):
+    return walk(square, KING_STEPS, occupied, True)
+
+
+@functools.lru_cache(maxsize=None)
+def rook_attacks(square, occupied):
+    return walk(square, ROOK_STEPS, occupied, True)
+
+
+def pawn_attacks(square):
+    attacks = 0
+    if file_of(square) > 0:
+        attacks |= 1 << (square + 7)
+    if file_of(square) < 7:
+        attacks |= 1 << (square + 9)
+    return attacks
+
+
+def squares(bitboard):
+    while bitboard:
+        low = bitboard & -bitboard
+        yield low.bit_length() - 1
+        bitboard ^= low
+
+
+# Retrograde analysis. White has the king and one piece, black the bare king, so white either
+# wins or draws. dtz_white[i] is the distance to zeroing in plies of a white win with white to
+# move, and dtz_black[i] the same for a black loss with black to move, or None otherwise. A
+# zeroing move is a pawn move or a capture, and a checkmate counts as zeroing as well.
+
+
+def solve(positions, white_legal, black_legal, black_moves, white_exits, white_unmoves,
+          black_unmoves):
+    """Solves one set of positions that non-zeroing moves never leave.
+
+    black_moves(i) gives the black king moves that stay in the set, or None when black can
+    take the white piece. white_exits(i) tells whether white has a winning zeroing move.
+    """
+    dtz_white = {}
+    dtz_black = {}
+    moves_left = {}
+    levels = [[], []]
+    for i in positions:
+        if black_legal(i):
+            moves = black_moves(i)
+            if moves is None:
+                continue
+            moves_left[i] = moves
+            if moves == 0 and white_attacks_black_king(i):
+                dtz_black[i] = 0
+                levels[0].append((1, i))
+        if white_legal(i) and white_exits(i):
+            dtz_white[i] = 1
+            levels[1].append((0, i))
+    distance = 0
+    while distance < len(levels):
+        if distance + 1 == len(levels):
+            levels.append([])
+        for side, i in levels[distance]:
+            if side == 1:
+                for j in white_unmoves(i):
+                    if white_legal(j) and j not in dtz_white:
+                        dtz_white[j] = distance + 1
+                        levels[distance + 1].append((0, j))
+            else:
+                for j in black_unmoves(i):
+                    if j in moves_left and j not in dtz_black:
+                        moves_left[j] -= 1
+                        if moves_left[j] == 0:
+                            dtz_black[j] = distance + 1
+                            levels[distance + 1].append((1, j))
+        distance += 1
+        if not any(levels[distance:]):
+            break
+    return dtz_white, dtz_black
+
+
+# Set by each solver for solve() to find whether the black king is in check
+white_attacks_black_king = None
+
+
+def solve_piece_ending(piece_attacks):
+    """KQK or KRK, indexed by (white king * 64 + piece) * 64 + black king."""
+    global white_attacks_black_king
+
+    def unpack(i):
+        return i >> 12, (i >> 6) & 63, i & 63
+
+    def attacked(square, king, piece):
+        # The black king is not in the occupancy, so it cannot step back along a check
+        return bool(KING[king] >> square & 1) or (
+            square != piece and bool(piece_attacks(piece, 1 << king) >> square & 1))
+
+    def black_legal(i):
+        king, piece, black_king = unpack(i)
+        return len({king, piece, black_king}) == 3 and not KING[king] >> black_king & 1
+
+    def white_legal(i):
+        king, piece, black_king = unpack(i)
+        return black_legal(i) and not attacked(black_king, king, piece)
+
+    def black_moves(i):
+        king, piece, black_king = unpack(i)
+        moves = 0
+        for square in squares(KING[black_king]):
+            if square == piece:
+                if not KING[king] >> piece & 1:
+                    return None
+            elif not attacked(square, king, piece):
+                moves += 1
+        return moves
+
+    def white_unmoves(i):
+        king, piece, black_king = unpack(i)
+        occupied = 1 << king | 1 << piece | 1 << black_king
+        for origin in squares(KING[king] & ~occupied):
+            yield (origin * 64 + piece) * 64 + black_king
+        for origin in squares(piece_attacks(piece, occupied) & ~occupied):
+            yield (king * 64 + origin) * 64 + black_king
+
+    def black_unmoves(i):
+        king, piece, black_king = unpack(i)
+        occupied = 1 << king | 1 << piece | 1 << black_king
+        for origin in squares(KING[black_king] & ~occupied):
+            yield (king * 64 + piece) * 64 + origin
+
+    white_attacks_black_king = lambda i: attacked(i & 63, i >> 12, (i >> 6) & 63)
+    return solve(range(1 << 18), white_legal, black_legal, black_moves, lambda i: False,
+                 white_unmoves, black_unmoves)
+
+
+def solve_pawn_ending(promotions):
+    """KPK, indexed by (pawn * 64 + white king) * 64 + black king, one pawn square at a time
+    from the seventh rank down, since pawn moves lead to squares that are already solved."""
+    global white_attacks_black_king
+    dtz_white, dtz_black = {}, {}
+
+    def unpack(i):
+        return i >> 12, (i >> 6) & 63, i & 63
+
+    def attacked(square, pawn, king):
+        return bool(KING[king] >> square & 1) or bool(pawn_attacks(pawn) >> square & 1)
+
+    def black_legal(i):
+        pawn, king, black_king = unpack(i)
+        return len({pawn, king, black_king}) == 3 and not KING[king] >> black_king & 1
+
+    def white_legal(i):
+        pawn, king, black_king = unpack(i)
+        return black_legal(i) and not pawn_attacks(pawn) >> black_king & 1
+
+    def black_moves(i):
+        pawn, king, black_king = unpack(i)
+        moves = 0
+        for square in squares(KING[black_king]):
+            if square == pawn:
+                if not KING[king] >> pawn & 1:
+                    return None
+            elif not attacked(square, pawn, king):
+                moves += 1
+        return moves
+
+    def white_exits(i):
+        pawn, king, black_king = unpack(i)
+        push = pawn + 8
+        if push in (king, black_king):
+            return False
+        if push >= 56:
+            return any((king * 64 + push) * 64 + black_king in lost for lost in promotions)
+        if (push * 64 + king) * 64 + black_king in dtz_black:
+            return True
+        double_push = pawn + 16
+        return (rank_of(pawn) == 1 and double_push not in (king, black_king)
+                and (double_push * 64 + king) * 64 + black_king in dtz_black)
+
+    def white_unmoves(i):
+        pawn, king, black_king = unpack(i)
+        occupied = 1 << pawn | 1 << king | 1 << black_king
+        for origin in squares(KING[king] & ~occupied):
+            yield (pawn * 64 + origin) * 64 + black_king
+
+    def black_unmoves(i):
+        pawn, king, black_king = unpack(i)
+        occupied = 1 << pawn | 1 << king | 1 << black_king
+        for origin in squares(KING[black_king] & ~occupied):
+            yield (pawn * 64 + king) * 64 + origin
+
+    white_attacks_black_king = lambda i: attacked(i & 63, i >> 12, (i >> 6) & 63)
+    for pawn in range(55, 7, -1):
+        positions = range(pawn << 12, (pawn + 1) << 12)
+        white, black = solve(positions, white_legal, black_legal, black_moves, white_exits,
+                             white_unmoves, black_unmoves)
+        dtz_white.update(white)
+        dtz_black.update(black)
+    return dtz_white, dtz_black
+
+
+# Indexes as in the Syzygy format
+
+
+def off_diagonal(square):
+    return rank_of(square) - file_of(square)
+
+
+MAP_B1H1H7 = {}
+for square in range(64):
+    if off_diagonal(square) < 0:
+        MAP_B1H1H7[square] = len(MAP_B1H1H7)
+MAP_A1D1D4 = {}
+for square in range(28):
+    if off_diagonal(square) < 0 and file_of(square) <= 3:
+        MAP_A1D1D4[square] = len(MAP_A1D1D4)
+for square in range(28):
+    if off_diagonal(square) == 0 and file_of(square) <= 3:
+        MAP_A1D1D4[square] = len(MAP_A1D1D4)
+
+
+def unique_pieces_index(squares):
+    """Index of three unique pieces, the first in the a1-d1-d4 triangle, in 0..31332."""
+    if file_of(squares[0]) > 3:
+        squares = [square ^ 7 for square in squares]
+    if rank_of(squares[0]) > 3:
+        squares = [square ^ 56 for square in squares]
+    for i in range(3):
+        if off_diagonal(squares[i]) > 0:
+            squares = squares[:i] + [(square >> 3 | square << 3) & 63 for square in squares[i:]]
+        if off_diagonal(squares[i]) != 0:
+            break
+    first, second, third = squares
+    above_first = int(second > first)
+    above_both = int(third > first) + int(third > second)
+    if off_diagonal(first):
+        return (MAP_A1D1D4[first] * 63 + second - above_first) * 62 + third - above_both
+    if off_diagonal(second):
+        return (6 * 63 + rank_of(first) * 28 + MAP_B1H1H7[second]) * 62 + third - above_both
+    if off_diagonal(third):
+        return (6 * 63 * 62 + 4 * 28 * 62 + rank_of(first) * 7 * 28
+                + (rank_of(second) - above_first) * 28 + MAP_B1H1H7[third])
+    return (6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank_of(first) * 7 * 6
+            + (rank_of(second) - above_first) * 6 + rank_of(third) - above_both)
+
+
+def group_factors(group_sizes, lead_size, order):
+    """The factor each group is multiplied by, given the lead group's position in the order."""
+    factors = [0] * len(group_sizes)
+    factor = 1
+    free = 64 - group_sizes[0]
+    rest = iter(range(1, len(group_sizes)))
+    for k in range(len(group_sizes)):
+        if k == order:
+            factors[0] = factor
+            factor *= lead_size
+        else:
+            group = next(rest)
+            factors[group] = factor
+            factor *= free
+            free -= group_sizes[group]
+    return factors, factor
+
+
+def pawn_index(squares, order):
+    """Index of a lone pawn first and two single pieces, for the file of the pawn."""
+    if file_of(squares[0]) > 3:
+        squares = [square ^ 7 for square in squares]
+    factors, _ = group_factors([1, 1, 1], 6, order)
+    index = (rank_of(squares[0]) - 1) * factors[0]
+    for i in (1, 2):
+        below = sum(1 for other in squares[:i] if other < squares[i])
+        index += (squares[i] - below) * factors[i]
+    return index
+
+
+# Compression
+
+
+class Pairs:
+    """The compressed values of one side to move and file."""
+
+    BLOCK_SIZE_LOG2 = 5
+    SPAN_LOG2 = 7
+    PADDING = 1
+
+    def __init__(self, values, flags):
+        self.flags = flags
+        symbols, self.tree = pair_symbols(values)
+        self.encode(symbols)
+
+    def encode(self, symbols):
+        counts = Counter(symbols)
+        lengths = code_lengths([counts[symbol] + 1 for symbol in range(len(self.tree))])
+        # Canonical codes number the symbols from the longest code to the shortest
+        order = sorted(range(len(self.tree)), key=lambda symbol: -lengths[symbol])
+        renumbered = {symbol: number for number, symbol in enumerate(order)}
+        self.min_length, self.max_length = min(lengths), max(lengths)
+        count = Counter(lengths)
+        self.lowest = [0] * (self.max_length - self.min_length + 1)
+        base = [0] * len(self.lowest)
+        for i in range(len(self.lowest) - 2, -1, -1):
+            self.lowest[i] = self.lowest[i + 1] + count[self.min_length + i + 1]
+            assert (base[i + 1] + count[self.min_length + i + 1]) % 2 == 0
+            base[i] = (base[i + 1] + count[self.min_length + i + 1]) // 2
+        # A complete code uses up every code of the shortest length
+        assert base[0] + count[self.min_length] == 1 << self.min_length
+
+        self.entries = [None] * len(self.tree)
+        for symbol, (left, right) in enumerate(self.tree):
+            if right != 0xFFF:
+                left, right = renumbered[left], renumbered[right]
+            self.entries[renumbered[symbol]] = (left, right)
+        expansions = [1] * len(self.tree)
+        for symbol, (left, right) in enumerate(self.tree):
+            if right != 0xFFF:
+                expansions[symbol] = expansions[left] + expansions[right]
+
+        block_bits = 8 << self.BLOCK_SIZE_LOG2
+        blocks = [[[], 0]]
+        for symbol in symbols:
+            length = lengths[symbol]
+            i = length - self.min_length
+            code = base[i] + renumbered[symbol] - self.lowest[i]
+            if len(blocks[-1][0]) + length > block_bits:
+                blocks.append([[], 0])
+            blocks[-1][0].extend((code >> bit) & 1 for bit in range(length - 1, -1, -1))
+            blocks[-1][1] += expansions[symbol]
+        self.data = bytearray()
+        for bits, _ in blocks:
+            block = bytearray(1 << self.BLOCK_SIZE_LOG2)
+            for i, bit in enumerate(bits):
+                block[i // 8] |= bit << (7 - i % 8)
+            self.data += block
+        self.block_lengths = [value_count - 1 for _, value_count in blocks] + [0] * self.PADDING
+        self.block_count = len(blocks)
+
+        # Every span has an entry for the value in its middle, past the last block at the end
+        starts = []
+        start = 0
+        for _, value_count in blocks:
+            starts.append(start)
+            start += value_count
+        span = 1 << self.SPAN_LOG2
+        self.sparse_index = []
+        block = 0
+        for entry in range((start + span - 1) // span):
+            middle = entry * span + span // 2
+            while block + 1 < len(starts) and starts[block + 1] <= middle:
+                block += 1
+            assert middle - starts[block] < 1 << 16
+            self.sparse_index.append((block, middle - starts[block]))
+
+    def sizes(self):
+        out = bytearray([self.flags, self.BLOCK_SIZE_LOG2, self.SPAN_LOG2, self.PADDING])
+        out += self.block_count.to_bytes(4, "little")
+        out += bytes([self.max_length, self.min_length])
+        for lowest in self.lowest:
+            out += lowest.to_bytes(2, "little")
+        out += len(self.entries).to_bytes(2, "little")
+        for left, right in self.entries:
+            out += bytes([left & 0xFF, (left >> 8) | (right & 0xF) << 4, right >> 4])
+        out += bytes(len(self.entries) & 1)
+        return out
+
+
+def pair_symbols(values, longest_expansion=256, most_symbols=400):
+    """Replaces the most frequent pair of neighbouring symbols with a new symbol, over and over.
+    Returns the symbols and, for each symbol, its two halves or its value and 0xFFF."""
+    tree = [(value, 0xFFF) for value in sorted(set(values))]
+    leaf = {value: symbol for symbol, (value, _) in enumerate(tree)}
+    expansions = [1] * len(tree)
+    symbols = [leaf[value] for value in values]
+    while len(tree) < most_symbols:
+        pairs = Counter(zip(symbols, symbols[1:]))
+        candidates = [(count, pair) for pair, count in pairs.items()
+                      if expansions[pair[0]] + expansions[pair[1]] <= longest_expansion]
+        if not candidates:
+            break
+        count, pair = max(candidates)
+        if count < 8:
+            break
+        new_symbol = len(tree)
+        tree.append(pair)
+        expansions.append(expansions[pair[0]] + expansions[pair[1]])
+        replaced = []
+        i = 0
+        while i < len(symbols):
+            if i + 1 < len(symbols) and (symbols[i], symbols[i + 1]) == pair:
+                replaced.append(new_symbol)
+                i += 2
+            else:
+                replaced.append(symbols[i])
+                i += 1
+        symbols = replaced
+    return symbols, tree
+
+
+def code_lengths(frequencies):
+    heap = [(frequency, [symbol]) for symbol, frequency in enumerate(frequencies)]
+    heapq.heapify(heap)
+    lengths = [0] * len(frequencies)
+    while len(heap) > 1:
+        first_frequency, first = heapq.heappop(heap)
+        second_frequency, second = heapq.heappop(heap)
+        for symbol in first + second:
+            lengths[symbol] += 1
+        heapq.heappush(heap, (first_frequency + second_frequency, first + second))
+    return lengths
+
+
+def filled(values, size):
+    """Positions that cannot occur take the most common value, which compresses best."""
+    filler = Counter(value for value in values if value is not None).most_common(1)[0][0]
+    return [filler if values[index] is None else values[index] for index in range(size)]
+
+
+def write_file(name, magic, has_pawns, pieces_by_file, orders, pairs_by_file, dtz_maps=None):
+    out = bytearray(magic)
+    out.append(int(len(pairs_by_file[0]) == 2) | int(has_pawns) << 1)
+    for pieces, order in zip(pieces_by_file, orders):
+        out.append(order[0] | order[-1] << 4)
+        for k in range(len(pieces[0])):
+            out.append(pieces[0][k] | pieces[-1][k] << 4)
+    out += bytes(len(out) & 1)
+    all_pairs = [pairs for file_pairs in pairs_by_file for pairs in file_pairs]
+    for pairs in all_pairs:
+        out += pairs.sizes()
+    if dtz_maps is not None:
+        for file_pairs, maps in zip(pairs_by_file, dtz_maps):
+            if not file_pairs[0].flags & FLAG_MAPPED:
+                continue
+            if file_pairs[0].flags & FLAG_WIDE:
+                out += bytes(len(out) & 1)
+                for values in maps:
+                    out += len(values).to_bytes(2, "little")
+                    for value in values:
+                        out += value.to_bytes(2, "little")
+            else:
+                for values in maps:
+                    out.append(len(values))
+                    out += bytes(values)
+        out += bytes(len(out) & 1)
+    for pairs in all_pairs:
+        for block, offset in pairs.sparse_index:
+            out += block.to_bytes(4, "little") + offset.to_bytes(2, "little")
+    for pairs in all_pairs:
+        for length in pairs.block_lengths:
+            out += length.to_bytes(2, "little")
+    for pairs in all_pairs:
+        out += bytes(-len(out) % 64)
+        out += pairs.data
+    with open(os.path.join(os.path.dirname(os.path.abspath(__file__)), name), "wb") as file:
+        file.write(out)
+
+
+def write_piece_ending(name, piece, solved, wdl_orders, dtz_order, dtz_flags):
+    dtz_white, dtz_black = solved
+    names = {W_KING: 0, piece: 1, B_KING: 2}
+    wdl_pairs = []
+    for side, pieces in enumerate(wdl_orders):
+        values = [None] * 31332
+        for i in range(1 << 18):
+            placement = (i >> 12, (i >> 6) & 63, i & 63)
+            if len(set(placement)) < 3 or KING[placement[0]] >> placement[2] & 1:
+                continue
+            if side == 0:
+                value = 4 if i in dtz_white else 2
+            else:
+                value = 0 if i in dtz_black else 2
+            index = unique_pieces_index([placement[names[p]] for p in pieces])
+            assert values[index] in (None, value)
+            values[index] = value
+        wdl_pairs.append(Pairs(filled(values, 31332), 0))
+    write_file(name + ".rtbw", WDL_MAGIC, False, [wdl_orders], [[0, 0]], [wdl_pairs])
+
+    # Wins are always an odd number of plies, so the map skips every other distance
+    distances = sorted({dtz - 1 for dtz in dtz_white.values()})
+    stored = {distance: value for value, distance in enumerate(distances)}
+    values = [None] * 31332
+    for i, dtz in dtz_white.items():
+        placement = (i >> 12, (i >> 6) & 63, i & 63)
+        index = unique_pieces_index([placement[names[p]] for p in dtz_order])
+        assert values[index] in (None, stored[dtz - 1])
+        values[index] = stored[dtz - 1]
+    flags = FLAG_MAPPED | FLAG_WIN_PLIES | FLAG_LOSS_PLIES | dtz_flags
+    write_file(name + ".rtbz", DTZ_MAGIC, False, [[dtz_order]], [[0]],
+               [[Pairs(filled(values, 31332), flags)]], [[distances, [], [], []]])
+
+
+def pawn_placements(file):
+    for pawn in range(8, 56):
+        if min(file_of(pawn), 7 - file_of(pawn)) != file:
+            continue
+        for king in range(64):
+            for black_king in range(64):
+                if len({pawn, king, black_king}) == 3 and not KING[king] >> black_king & 1:
+                    yield (pawn, king, black_king), (pawn * 64 + king) * 64 + black_king
+
+
+def write_pawn_ending(solved, wdl_orders, wdl_order_bytes):
+    dtz_white, dtz_black = solved
+    names = {W_PAWN: 0, W_KING: 1, B_KING: 2}
+    wdl_pairs, dtz_pairs = [], []
+    for file in range(4):
+        file_pairs = []
+        for side, pieces in enumerate(wdl_orders):
+            values = [None] * 23436
+            for placement, i in pawn_placements(file):
+                if side == 0:
+                    value = 4 if i in dtz_white else 2
+                else:
+                    value = 0 if i in dtz_black else 2
+                index = pawn_index([placement[names[p]] for p in pieces], wdl_order_bytes[side])
+                assert values[index] in (None, value)
+                values[index] = value
+            file_pairs.append(Pairs(filled(values, 23436), 0))
+        wdl_pairs.append(file_pairs)
+        values = [None] * 23436
+        for placement, i in pawn_placements(file):
+            if i in dtz_white:
+                index = pawn_index(list(placement), 0)
+                values[index] = dtz_white[i] - 1
+        dtz_pairs.append([Pairs(filled(values, 23436), FLAG_WIN_PLIES)])
+    write_file("KPvK.rtbw", WDL_MAGIC, True, [wdl_orders] * 4, [wdl_order_bytes] * 4, wdl_pairs)
+    write_file("KPvK.rtbz", DTZ_MAGIC, True, [[[W_PAWN, W_KING, B_KING]]] * 4, [[0]] * 4,
+               dtz_pairs, [None] * 4)
+
+
+def solve_all():
+    queen = solve_piece_ending(queen_attacks)
+    rook = solve_piece_ending(rook_attacks)
+    # The longest wins are mate in 10 with the queen and mate in 16 with the rook
+    assert max(queen[0].values()) == 19 and max(rook[0].values()) == 31
+    pawn = solve_pawn_ending([queen[1], rook[1]])
+    return {"KQvK": queen, "KRvK": rook, "KPvK": pawn}
+
+
+def probe(solved, fen):
+    """The outcome and distance to zeroing for a position in one of the three endings."""
+    placement, to_move = fen.split()[:2]
+    pieces = {}
+    for rank, row in enumerate(reversed(placement.split("/"))):
+        file = 0
+        for letter in row:
+            if letter.isdigit():
+                file += int(letter)
+            else:
+                pieces[letter] = rank * 8 + file
+                file += 1
+    white_to_move = to_move == "w"
+    if not any(letter in pieces for letter in "QRP"):
+        pieces = {letter.swapcase(): square ^ 56 for letter, square in pieces.items()}
+        white_to_move = not white_to_move
+    letter = next(letter for letter in "QRP" if letter in pieces)
+    dtz_white, dtz_black = solved["K" + letter + "vK"]
+    if letter == "P":
+        i = (pieces["P"] * 64 + pieces["K"]) * 64 + pieces["k"]
+    else:
+        i = (pieces["K"] * 64 + pieces[letter]) * 64 + pieces["k"]
+    if white_to_move:
+        return ("Win", dtz_white[i]) if i in dtz_white else ("Draw", 0)
+    return ("Loss", -dtz_black[i]) if i in dtz_black else ("Draw", 0)
+
+
+def main():
+    solved = solve_all()
+    if sys.argv[1:2] == ["--probe"]:
+        for fen in sys.argv[2:]:
+            print(fen, *probe(solved, fen))
+        return
+    # The pieces are stored in a different order for each side to move, so readers have to
+    # follow the order in the file
+    write_piece_ending("KQvK", W_QUEEN, solved["KQvK"], [[W_QUEEN, W_KING, B_KING],
+                       [B_KING, W_QUEEN, W_KING]], [B_KING, W_KING, W_QUEEN], 0)
+    write_piece_ending("KRvK", W_ROOK, solved["KRvK"], [[W_KING, W_ROOK, B_KING],
+                       [W_ROOK, B_KING, W_KING]], [W_KING, W_ROOK, B_KING], FLAG_WIDE)
+    write_pawn_ending(solved["KPvK"], [[W_PAWN, B_KING, W_KING], [W_PAWN, W_KING, B_KING]],
+                      [0, 1])
+
+
+if __name__ == "__main__":
+    main()